@@ -0,0 +1,161 @@
+use std::fmt::Debug;
+
+use bech32::{
+	primitives::decode::{CheckedHrpstring, CheckedHrpstringError},
+	Bech32, Hrp,
+};
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum AddressCodecError {
+	#[error("Expected {expected} address bytes, got {actual}")]
+	InvalidLength { expected: usize, actual: usize },
+	#[error("Invalid address encoding: {0}")]
+	InvalidEncoding(String),
+	#[error("Invalid address checksum")]
+	InvalidChecksum,
+	#[error("Expected address prefix {expected}, got {actual}")]
+	UnexpectedPrefix { expected: String, actual: String },
+}
+
+/// Textual address format of a chain. Addresses travel between chains as raw bytes, the codec
+/// parses user input into them and renders them back.
+pub trait AddressCodec: Debug + Send + Sync {
+	/// Parses an address, verifying its checksum when the format has one.
+	fn parse(&self, address: &str) -> Result<Vec<u8>, AddressCodecError>;
+
+	fn render(&self, address: &[u8]) -> Result<String, AddressCodecError>;
+
+	/// Checks that raw bytes form an address of this chain.
+	fn validate(&self, address: &[u8]) -> Result<(), AddressCodecError>;
+}
+
+fn check_length(address: &[u8], expected: usize) -> Result<(), AddressCodecError> {
+	if address.len() != expected {
+		return Err(AddressCodecError::InvalidLength { expected, actual: address.len() });
+	}
+	Ok(())
+}
+
+/// 20 byte hex addresses with the mixed-case checksum of EIP-55, as used by EVM chains. All lower
+/// or all upper case addresses carry no checksum and are accepted as is.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Eip55;
+
+impl Eip55 {
+	const LENGTH: usize = 20;
+
+	fn checksummed(address: &[u8]) -> String {
+		let lower = hex::encode(address);
+		let hash = Keccak256::digest(lower.as_bytes());
+		let checksummed: String = lower
+			.chars()
+			.enumerate()
+			.map(|(i, c)| {
+				let nibble = if i % 2 == 0 { hash[i / 2] >> 4 } else { hash[i / 2] & 0x0f };
+				if nibble >= 8 {
+					c.to_ascii_uppercase()
+				} else {
+					c
+				}
+			})
+			.collect();
+		format!("0x{checksummed}")
+	}
+}
+
+impl AddressCodec for Eip55 {
+	fn parse(&self, address: &str) -> Result<Vec<u8>, AddressCodecError> {
+		let digits = address
+			.strip_prefix("0x")
+			.ok_or_else(|| AddressCodecError::InvalidEncoding("missing 0x prefix".to_string()))?;
+		let bytes = hex::decode(digits)
+			.map_err(|error| AddressCodecError::InvalidEncoding(error.to_string()))?;
+		self.validate(&bytes)?;
+
+		let mixed_case = digits.chars().any(|c| c.is_ascii_lowercase())
+			&& digits.chars().any(|c| c.is_ascii_uppercase());
+		if mixed_case && Self::checksummed(&bytes) != address {
+			return Err(AddressCodecError::InvalidChecksum);
+		}
+		Ok(bytes)
+	}
+
+	fn render(&self, address: &[u8]) -> Result<String, AddressCodecError> {
+		self.validate(address)?;
+		Ok(Self::checksummed(address))
+	}
+
+	fn validate(&self, address: &[u8]) -> Result<(), AddressCodecError> {
+		check_length(address, Self::LENGTH)
+	}
+}
+
+/// Bech32 addresses with a fixed human readable prefix, as used by Cosmos SDK chains.
+#[derive(Debug, Clone)]
+pub struct Bech32Codec {
+	hrp: Hrp,
+	length: usize,
+}
+
+impl Bech32Codec {
+	pub fn new(hrp: &str, length: usize) -> Result<Self, AddressCodecError> {
+		let hrp = Hrp::parse(hrp)
+			.map_err(|error| AddressCodecError::InvalidEncoding(error.to_string()))?;
+		Ok(Self { hrp, length })
+	}
+}
+
+impl AddressCodec for Bech32Codec {
+	fn parse(&self, address: &str) -> Result<Vec<u8>, AddressCodecError> {
+		let checked = CheckedHrpstring::new::<Bech32>(address).map_err(|error| match error {
+			CheckedHrpstringError::Checksum(_) => AddressCodecError::InvalidChecksum,
+			error => AddressCodecError::InvalidEncoding(error.to_string()),
+		})?;
+		if checked.hrp() != self.hrp {
+			return Err(AddressCodecError::UnexpectedPrefix {
+				expected: self.hrp.to_lowercase(),
+				actual: checked.hrp().to_lowercase(),
+			});
+		}
+		let bytes: Vec<u8> = checked.byte_iter().collect();
+		self.validate(&bytes)?;
+		Ok(bytes)
+	}
+
+	fn render(&self, address: &[u8]) -> Result<String, AddressCodecError> {
+		self.validate(address)?;
+		bech32::encode::<Bech32>(self.hrp, address)
+			.map_err(|error| AddressCodecError::InvalidEncoding(error.to_string()))
+	}
+
+	fn validate(&self, address: &[u8]) -> Result<(), AddressCodecError> {
+		check_length(address, self.length)
+	}
+}
+
+/// Base58 addresses of a fixed length, e.g. 32 byte Solana public keys.
+#[derive(Debug, Clone, Copy)]
+pub struct Base58 {
+	pub length: usize,
+}
+
+impl AddressCodec for Base58 {
+	fn parse(&self, address: &str) -> Result<Vec<u8>, AddressCodecError> {
+		let bytes = bs58::decode(address)
+			.into_vec()
+			.map_err(|error| AddressCodecError::InvalidEncoding(error.to_string()))?;
+		self.validate(&bytes)?;
+		Ok(bytes)
+	}
+
+	fn render(&self, address: &[u8]) -> Result<String, AddressCodecError> {
+		self.validate(address)?;
+		Ok(bs58::encode(address).into_string())
+	}
+
+	fn validate(&self, address: &[u8]) -> Result<(), AddressCodecError> {
+		check_length(address, self.length)
+	}
+}
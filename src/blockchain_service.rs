@@ -6,12 +6,18 @@ use std::{
 use futures::{Stream, StreamExt};
 
 use crate::{
-	bridge_contracts::{BridgeContractCounterparty, BridgeContractInitiator},
+	bridge_contracts::{
+		BridgeContractCounterparty, BridgeContractCounterpartyError,
+		BridgeContractCounterpartyResult, BridgeContractInitiator,
+	},
 	bridge_monitoring::{
 		BridgeContractCounterpartyEvent, BridgeContractCounterpartyMonitoring,
 		BridgeContractInitiatorEvent, BridgeContractInitiatorMonitoring,
 	},
-	types::{BridgeAddressType, BridgeHashType},
+	types::{
+		Amount, AssetId, BridgeAddressType, BridgeHashType, BridgeTransferDetails,
+		BridgeTransferId, HashLock, HashLockPreImage, InitiatorAddress, RecipientAddress, TimeLock,
+	},
 };
 
 #[derive(Debug, PartialEq, Eq)]
@@ -177,3 +183,87 @@ impl<
 		f.debug_struct(stringify!("AbstractBlockchainService")).finish()
 	}
 }
+
+/// Counterparty contract and monitoring of a chain that only has an initiator contract deployed.
+/// Calls fail and the monitoring never yields, so the direction into the chain must be disabled.
+pub struct NoCounterparty<A, H>(std::marker::PhantomData<fn() -> (A, H)>);
+
+impl<A, H> NoCounterparty<A, H> {
+	pub fn new() -> Self {
+		Self(std::marker::PhantomData)
+	}
+}
+
+impl<A, H> Default for NoCounterparty<A, H> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<A, H> Clone for NoCounterparty<A, H> {
+	fn clone(&self) -> Self {
+		Self::new()
+	}
+}
+
+fn no_counterparty_error() -> BridgeContractCounterpartyError {
+	BridgeContractCounterpartyError::GenericError("no counterparty contract on this chain".into())
+}
+
+#[async_trait::async_trait]
+impl<A, H> BridgeContractCounterparty for NoCounterparty<A, H>
+where
+	A: BridgeAddressType,
+	H: BridgeHashType,
+{
+	type Address = A;
+	type Hash = H;
+
+	async fn lock_bridge_transfer_assets(
+		&mut self,
+		_bridge_transfer_id: BridgeTransferId<H>,
+		_hash_lock: HashLock<H>,
+		_time_lock: TimeLock,
+		_initiator: InitiatorAddress<Vec<u8>>,
+		_recipient: RecipientAddress<A>,
+		_asset: AssetId,
+		_amount: Amount,
+	) -> BridgeContractCounterpartyResult<BridgeTransferId<H>> {
+		Err(no_counterparty_error())
+	}
+
+	async fn complete_bridge_transfer(
+		&mut self,
+		_bridge_transfer_id: BridgeTransferId<H>,
+		_secret: HashLockPreImage,
+	) -> BridgeContractCounterpartyResult<()> {
+		Err(no_counterparty_error())
+	}
+
+	async fn abort_bridge_transfer(
+		&mut self,
+		_bridge_transfer_id: BridgeTransferId<H>,
+	) -> BridgeContractCounterpartyResult<()> {
+		Err(no_counterparty_error())
+	}
+
+	async fn get_bridge_transfer_details(
+		&mut self,
+		_bridge_transfer_id: BridgeTransferId<H>,
+	) -> BridgeContractCounterpartyResult<Option<BridgeTransferDetails<H, A>>> {
+		Err(no_counterparty_error())
+	}
+}
+
+impl<A, H> Stream for NoCounterparty<A, H> {
+	type Item = BridgeContractCounterpartyEvent<A, H>;
+
+	fn poll_next(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Option<Self::Item>> {
+		Poll::Pending
+	}
+}
+
+impl<A, H> BridgeContractCounterpartyMonitoring for NoCounterparty<A, H> {
+	type Address = A;
+	type Hash = H;
+}
@@ -1,8 +1,12 @@
 use thiserror::Error;
 
-use crate::types::{
-	Amount, BridgeAddressType, BridgeHashType, BridgeTransferDetails, BridgeTransferId, HashLock,
-	HashLockPreImage, InitiatorAddress, RecipientAddress, TimeLock,
+use crate::{
+	hash_lock::SecretConstraints,
+	types::{
+		Amount, AssetId, BridgeAddressType, BridgeHashType, BridgeTransferDetails,
+		BridgeTransferId, ChainId, HashLock, HashLockPreImage, InitiatorAddress, RecipientAddress,
+		TimeLock,
+	},
 };
 
 #[derive(Error, Debug, Clone)]
@@ -47,13 +51,17 @@ pub trait BridgeContractInitiator: Clone + Unpin + Send + Sync {
 	type Address: BridgeAddressType;
 	type Hash: BridgeHashType;
 
+	#[allow(clippy::too_many_arguments)]
 	async fn initiate_bridge_transfer(
 		&mut self,
 		initiator_address: InitiatorAddress<Self::Address>,
 		recipient_address: RecipientAddress<Vec<u8>>,
 		hash_lock: HashLock<Self::Hash>,
+		counterparty_hash_lock: Option<HashLock<Vec<u8>>>,
 		time_lock: TimeLock,
+		asset: AssetId,
 		amount: Amount,
+		destination_chain: Option<ChainId>,
 	) -> BridgeContractInitiatorResult<()>;
 
 	async fn complete_bridge_transfer(
@@ -71,6 +79,12 @@ pub trait BridgeContractInitiator: Clone + Unpin + Send + Sync {
 		&mut self,
 		bridge_transfer_id: BridgeTransferId<Self::Hash>,
 	) -> BridgeContractInitiatorResult<Option<BridgeTransferDetails<Self::Hash, Self::Address>>>;
+
+	/// Pre-images the contract accepts, for contracts that expose their limits. They take
+	/// precedence over the configured `SecretConstraints` of the chain.
+	fn secret_constraints(&self) -> Option<SecretConstraints> {
+		None
+	}
 }
 
 #[async_trait::async_trait]
@@ -78,6 +92,10 @@ pub trait BridgeContractCounterparty: Clone + Unpin + Send + Sync {
 	type Address: BridgeAddressType;
 	type Hash: BridgeHashType;
 
+	/// Locks the assets under the proposed `bridge_transfer_id`. Contracts that assign their own
+	/// ids may ignore the proposal. Returns the id the lock was placed under, which later
+	/// counterparty events refer to.
+	#[allow(clippy::too_many_arguments)]
 	async fn lock_bridge_transfer_assets(
 		&mut self,
 		bridge_transfer_id: BridgeTransferId<Self::Hash>,
@@ -85,8 +103,9 @@ pub trait BridgeContractCounterparty: Clone + Unpin + Send + Sync {
 		time_lock: TimeLock,
 		initiator: InitiatorAddress<Vec<u8>>,
 		recipient: RecipientAddress<Self::Address>,
+		asset: AssetId,
 		amount: Amount,
-	) -> BridgeContractCounterpartyResult<()>;
+	) -> BridgeContractCounterpartyResult<BridgeTransferId<Self::Hash>>;
 
 	async fn complete_bridge_transfer(
 		&mut self,
@@ -103,4 +122,10 @@ pub trait BridgeContractCounterparty: Clone + Unpin + Send + Sync {
 		&mut self,
 		bridge_transfer_id: BridgeTransferId<Self::Hash>,
 	) -> BridgeContractCounterpartyResult<Option<BridgeTransferDetails<Self::Hash, Self::Address>>>;
+
+	/// Pre-images the contract accepts, for contracts that expose their limits. They take
+	/// precedence over the configured `SecretConstraints` of the chain.
+	fn secret_constraints(&self) -> Option<SecretConstraints> {
+		None
+	}
 }
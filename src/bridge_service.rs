@@ -1,28 +1,113 @@
-use futures::{Stream, StreamExt};
+use futures::{task::AtomicWaker, Stream, StreamExt};
 use std::task::{Context, Poll};
-use std::{convert::From, pin::Pin};
-use tracing::{trace, warn};
+use std::{collections::VecDeque, convert::From, fmt::Debug, pin::Pin, sync::Arc};
+use tracing::{error, trace, warn};
 
 use crate::{
+	address_codec::AddressCodec,
 	blockchain_service::{BlockchainService, ContractEvent},
 	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
 	bridge_service::{
-		active_swap::ActiveSwapEvent,
+		active_swap::{ActiveSwapEvent, ActiveSwapMapError},
 		events::{CEvent, CWarn, IEvent, IWarn},
 	},
-	types::BridgeTransferId,
+	chain_clock::ChainClock,
+	hash_lock::{HashLockScheme, SecretConstraints, Sha256},
+	types::{Amount, AssetId, ChainId, HashLockPreImage, TryConvert},
 };
 
 pub mod active_swap;
+pub mod anomaly;
+pub mod assets;
+pub mod call_limit;
+pub mod circuit_breaker;
+pub mod direction;
 pub mod events;
+pub mod hash_lock_index;
+pub mod netting;
+pub mod quote;
+pub mod router;
+pub mod time_lock;
 
 use self::{
-	active_swap::{ActiveSwapConfig, ActiveSwapMap},
-	events::Event,
+	active_swap::{ActiveSwapConfig, ActiveSwapMap, RelayConfig, SwapCapacity},
+	anomaly::{AnomalyPolicy, SwapAnomaly},
+	assets::AssetMapping,
+	call_limit::{CallLimitConfig, CallLimiter, SharedCallLimiter},
+	circuit_breaker::{
+		BreakerScope, CircuitBreaker, CircuitBreakerConfig, CircuitBreakerEvent, TripReason,
+	},
+	direction::{Direction, EnabledDirections},
+	events::{CapacityEvent, Event},
+	hash_lock_index::{HashLockIndex, HashLockReuseConfig},
+	netting::{LiquidityPlanner, NettingConfig, SharedLiquidityPlanner},
+	quote::{Quote, QuoteConfig, QuoteId, Reservation},
+	time_lock::{TimeLockKind, TimeLockPolicy},
 };
 
+/// Settings describing one of the bridged chains.
+#[derive(Debug, Clone)]
+pub struct ChainConfig {
+	/// Hash function the chain's HTLC contracts use to derive hash locks.
+	pub hash_lock_scheme: Arc<dyn HashLockScheme>,
+	/// Pre-images the chain's HTLC contracts accept.
+	pub secret_constraints: SecretConstraints,
+	pub time_lock_kind: TimeLockKind,
+	/// Required to translate absolute time locks from or to the chain, and to count relative time
+	/// locks from initiation rather than from when the bridge locks.
+	pub clock: Option<Arc<dyn ChainClock>>,
+	/// Validates recipients on the chain before anything is locked. Without a codec any recipient
+	/// the address type converts from is accepted.
+	pub address_codec: Option<Arc<dyn AddressCodec>>,
+	/// Applies to the calls the bridge makes to the chain's contracts in both directions.
+	pub call_limit: CallLimitConfig,
+}
+
+impl Default for ChainConfig {
+	fn default() -> Self {
+		Self {
+			hash_lock_scheme: Arc::new(Sha256),
+			secret_constraints: SecretConstraints::default(),
+			time_lock_kind: TimeLockKind::default(),
+			clock: None,
+			address_codec: None,
+			call_limit: CallLimitConfig::default(),
+		}
+	}
+}
+
+/// Id of blockchain 1 in the liquidity planner of a `BridgeService`.
+pub const BLOCKCHAIN_1: ChainId = ChainId(1);
+/// Id of blockchain 2 in the liquidity planner of a `BridgeService`.
+pub const BLOCKCHAIN_2: ChainId = ChainId(2);
+
+#[derive(Debug, Clone, Default)]
 pub struct BridgeServiceConfig {
 	pub active_swap: ActiveSwapConfig,
+	/// Assets that can be bridged from blockchain 1 to blockchain 2.
+	pub assets_b1_to_b2: AssetMapping,
+	/// Assets that can be bridged from blockchain 2 to blockchain 1.
+	pub assets_b2_to_b1: AssetMapping,
+	pub blockchain_1: ChainConfig,
+	pub blockchain_2: ChainConfig,
+	/// Applied in both directions when deriving the counterparty time lock.
+	pub time_lock_policy: TimeLockPolicy,
+	/// Hash locks are checked for reuse across both directions.
+	pub hash_lock_reuse: HashLockReuseConfig,
+	/// Applied in both directions to counterparty events that do not match the requested lock.
+	pub anomaly_policy: AnomalyPolicy,
+	pub circuit_breaker: CircuitBreakerConfig,
+	/// Swaps from blockchain 1 to blockchain 2 in flight at most.
+	pub max_active_swaps_b1_to_b2: Option<usize>,
+	/// Swaps from blockchain 2 to blockchain 1 in flight at most.
+	pub max_active_swaps_b2_to_b1: Option<usize>,
+	pub directions: EnabledDirections,
+	/// Lets locks draw on the funds due from swaps in the opposite direction.
+	pub netting: NettingConfig,
+	/// Applied in both directions to quotes given ahead of initiation.
+	pub quotes: QuoteConfig,
+	/// Applied in both directions to secrets relayed by recipients.
+	pub relay: RelayConfig,
 }
 
 pub struct BridgeService<B1, B2>
@@ -35,6 +120,26 @@ where
 
 	pub active_swaps_b1_to_b2: ActiveSwapMap<B1, B2>,
 	pub active_swaps_b2_to_b1: ActiveSwapMap<B2, B1>,
+
+	/// Contract calls queued and in flight on each chain.
+	pub calls_b1: SharedCallLimiter,
+	pub calls_b2: SharedCallLimiter,
+
+	/// Pending flows of both directions, under `BLOCKCHAIN_1` and `BLOCKCHAIN_2`.
+	pub liquidity_planner: SharedLiquidityPlanner,
+
+	pub directions: EnabledDirections,
+	pub circuit_breaker: CircuitBreaker,
+	anomaly_policy: AnomalyPolicy,
+	/// Circuit breaker and capacity events, returned before polling anything else.
+	pending_events: VecDeque<Event<B1, B2>>,
+	/// Capacity of each direction as last reported.
+	at_capacity_b1_to_b2: bool,
+	at_capacity_b2_to_b1: bool,
+	/// `Initiated` events of new transfers held back while their direction is at capacity.
+	deferred_b1_to_b2: VecDeque<BridgeContractInitiatorEvent<B1::Address, B1::Hash>>,
+	deferred_b2_to_b1: VecDeque<BridgeContractInitiatorEvent<B2::Address, B2::Hash>>,
+	waker: AtomicWaker,
 }
 
 impl<B1, B2> BridgeService<B1, B2>
@@ -45,19 +150,291 @@ where
 	Vec<u8>: From<B2::Address>,
 {
 	pub fn new(blockchain_1: B1, blockchain_2: B2, config: BridgeServiceConfig) -> Self {
+		let hash_locks = HashLockIndex::shared(config.hash_lock_reuse);
+		let calls_b1 = CallLimiter::shared(config.blockchain_1.call_limit);
+		let calls_b2 = CallLimiter::shared(config.blockchain_2.call_limit);
+		let liquidity_planner = LiquidityPlanner::shared(config.netting);
 		Self {
 			active_swaps_b1_to_b2: ActiveSwapMap::build(
 				blockchain_1.initiator_contract().clone(),
 				blockchain_2.counterparty_contract().clone(),
 				config.active_swap.clone(),
-			),
+				config.assets_b1_to_b2,
+				config.time_lock_policy,
+				config.blockchain_1.clone(),
+				config.blockchain_2.clone(),
+			)
+			.with_hash_lock_index(hash_locks.clone())
+			.with_call_limiters(calls_b1.clone(), calls_b2.clone())
+			.with_liquidity_planner(liquidity_planner.clone(), BLOCKCHAIN_1, BLOCKCHAIN_2)
+			.with_quote_config(config.quotes)
+			.with_relay_config(config.relay.clone())
+			.with_max_active_swaps(config.max_active_swaps_b1_to_b2),
 			active_swaps_b2_to_b1: ActiveSwapMap::build(
 				blockchain_2.initiator_contract().clone(),
 				blockchain_1.counterparty_contract().clone(),
 				config.active_swap.clone(),
-			),
+				config.assets_b2_to_b1,
+				config.time_lock_policy,
+				config.blockchain_2,
+				config.blockchain_1,
+			)
+			.with_hash_lock_index(hash_locks)
+			.with_call_limiters(calls_b2.clone(), calls_b1.clone())
+			.with_liquidity_planner(liquidity_planner.clone(), BLOCKCHAIN_2, BLOCKCHAIN_1)
+			.with_quote_config(config.quotes)
+			.with_relay_config(config.relay)
+			.with_max_active_swaps(config.max_active_swaps_b2_to_b1),
+			calls_b1,
+			calls_b2,
+			liquidity_planner,
 			blockchain_1,
 			blockchain_2,
+			directions: config.directions,
+			circuit_breaker: CircuitBreaker::new(config.circuit_breaker),
+			anomaly_policy: config.anomaly_policy,
+			pending_events: VecDeque::new(),
+			at_capacity_b1_to_b2: false,
+			at_capacity_b2_to_b1: false,
+			deferred_b1_to_b2: VecDeque::new(),
+			deferred_b2_to_b1: VecDeque::new(),
+			waker: AtomicWaker::new(),
+		}
+	}
+
+	/// Stops new locks in `scope` until reset. Swaps already locked keep completing.
+	pub fn trip_circuit_breaker(&mut self, scope: BreakerScope, reason: impl Into<String>) {
+		let event = self.circuit_breaker.trip(scope, TripReason::Manual(reason.into()));
+		self.apply_circuit_breaker_event(event);
+	}
+
+	/// Resets `scope`. A direction stays stopped while the global breaker is tripped.
+	pub fn reset_circuit_breaker(&mut self, scope: BreakerScope) {
+		let event = self.circuit_breaker.reset(scope);
+		self.apply_circuit_breaker_event(event);
+	}
+
+	pub fn capacity(&self, direction: Direction) -> SwapCapacity {
+		match direction {
+			Direction::B1ToB2 => self.active_swaps_b1_to_b2.capacity(),
+			Direction::B2ToB1 => self.active_swaps_b2_to_b1.capacity(),
+		}
+	}
+
+	/// Quotes the terms a transfer of `amount` of `asset` to `recipient` in `direction` would be
+	/// locked on, in the units of the chain it is initiated on.
+	pub fn quote(
+		&mut self,
+		direction: Direction,
+		asset: &AssetId,
+		amount: Amount,
+		recipient: &[u8],
+	) -> Result<Quote, ActiveSwapMapError> {
+		if !self.directions.is_enabled(direction) {
+			return Err(ActiveSwapMapError::DirectionDisabled);
+		}
+		match direction {
+			Direction::B1ToB2 => self.active_swaps_b1_to_b2.quote(asset, amount, recipient),
+			Direction::B2ToB1 => self.active_swaps_b2_to_b1.quote(asset, amount, recipient),
+		}
+	}
+
+	/// Accepts a quote of `direction`, reserving its liquidity for the transfer the user initiates
+	/// under `hash_lock`. See `ActiveSwapMap::accept_quote`.
+	pub fn accept_quote(
+		&mut self,
+		direction: Direction,
+		quote: QuoteId,
+		hash_lock: &[u8],
+	) -> Result<Reservation, ActiveSwapMapError> {
+		if !self.directions.is_enabled(direction) {
+			return Err(ActiveSwapMapError::DirectionDisabled);
+		}
+		match direction {
+			Direction::B1ToB2 => self.active_swaps_b1_to_b2.accept_quote(quote, hash_lock),
+			Direction::B2ToB1 => self.active_swaps_b2_to_b1.accept_quote(quote, hash_lock),
+		}
+	}
+
+	/// Claims the counterparty lock of the swap in `direction` that `secret` unlocks, on behalf of
+	/// its recipient. See `ActiveSwapMap::relay_secret`.
+	pub fn relay_secret(
+		&mut self,
+		direction: Direction,
+		secret: HashLockPreImage,
+	) -> Result<(), ActiveSwapMapError> {
+		if !self.directions.is_enabled(direction) {
+			return Err(ActiveSwapMapError::DirectionDisabled);
+		}
+		match direction {
+			Direction::B1ToB2 => {
+				let bridge_transfer_id = self.active_swaps_b1_to_b2.relay_secret(secret)?;
+				trace!(
+					"BridgeService: Relaying secret for bridge transfer {:?}",
+					bridge_transfer_id
+				);
+			}
+			Direction::B2ToB1 => {
+				let bridge_transfer_id = self.active_swaps_b2_to_b1.relay_secret(secret)?;
+				trace!(
+					"BridgeService: Relaying secret for bridge transfer {:?}",
+					bridge_transfer_id
+				);
+			}
+		}
+		Ok(())
+	}
+
+	/// Reports directions that reached or freed capacity since the last call.
+	fn update_capacity(&mut self) {
+		for direction in [Direction::B1ToB2, Direction::B2ToB1] {
+			let capacity = self.capacity(direction);
+			let at_capacity = match direction {
+				Direction::B1ToB2 => &mut self.at_capacity_b1_to_b2,
+				Direction::B2ToB1 => &mut self.at_capacity_b2_to_b1,
+			};
+			if *at_capacity == capacity.is_reached() {
+				continue;
+			}
+			*at_capacity = capacity.is_reached();
+			let event = if capacity.is_reached() {
+				warn!("BridgeService: {:?} at capacity {:?}", direction, capacity);
+				CapacityEvent::Reached(direction, capacity)
+			} else {
+				trace!("BridgeService: {:?} capacity freed {:?}", direction, capacity);
+				CapacityEvent::Freed(direction, capacity)
+			};
+			self.pending_events.push_back(Event::Capacity(event));
+		}
+	}
+
+	fn apply_circuit_breaker_event(&mut self, event: Option<CircuitBreakerEvent>) {
+		let Some(event) = event else {
+			return;
+		};
+		match &event {
+			CircuitBreakerEvent::Tripped(scope, reason) => {
+				error!("BridgeService: Circuit breaker {:?} tripped: {:?}", scope, reason);
+			}
+			CircuitBreakerEvent::Reset(scope) => {
+				warn!("BridgeService: Circuit breaker {:?} reset", scope);
+			}
+		}
+		if self.circuit_breaker.is_tripped(Direction::B1ToB2) {
+			self.active_swaps_b1_to_b2.pause();
+		} else {
+			self.active_swaps_b1_to_b2.resume();
+		}
+		if self.circuit_breaker.is_tripped(Direction::B2ToB1) {
+			self.active_swaps_b2_to_b1.pause();
+		} else {
+			self.active_swaps_b2_to_b1.resume();
+		}
+		self.pending_events.push_back(Event::CircuitBreaker(event));
+		self.waker.wake();
+	}
+}
+
+/// What an event contributes to the circuit breaker of its direction.
+enum BreakerInput {
+	Error,
+	Anomaly,
+	InsufficientLiquidity(AssetId),
+}
+
+fn initiator_breaker_input<A, H>(event: &IEvent<A, H>) -> Option<BreakerInput> {
+	match event.warn()? {
+		IWarn::CompleteTransferError(_) | IWarn::CompletionAbortedTooManyAttempts(_) => {
+			Some(BreakerInput::Error)
+		}
+		IWarn::TransferRejected(_, ActiveSwapMapError::InsufficientLiquidity { asset, .. }) => {
+			Some(BreakerInput::InsufficientLiquidity(asset.clone()))
+		}
+		_ => None,
+	}
+}
+
+fn counterparty_breaker_input<A, H>(event: &CEvent<A, H>) -> Option<BreakerInput> {
+	match event.warn()? {
+		CWarn::BridgeAssetsLockingError(_) | CWarn::LockingAbortedTooManyAttempts(_) => {
+			Some(BreakerInput::Error)
+		}
+		CWarn::Anomaly(..) => Some(BreakerInput::Anomaly),
+		_ => None,
+	}
+}
+
+/// Whether `event` concerns a swap already taken on, which is seen through even once its
+/// direction is disabled.
+fn is_active_initiator_event<BFrom, BTo>(
+	event: &BridgeContractInitiatorEvent<BFrom::Address, BFrom::Hash>,
+	active_swaps: &ActiveSwapMap<BFrom, BTo>,
+) -> bool
+where
+	BFrom: BlockchainService + 'static,
+	BTo: BlockchainService + 'static,
+
+	Vec<u8>: From<BTo::Address>,
+	Vec<u8>: From<BFrom::Address>,
+{
+	match event {
+		BridgeContractInitiatorEvent::Initiated(_) => false,
+		BridgeContractInitiatorEvent::Completed(bridge_transfer_id)
+		| BridgeContractInitiatorEvent::Refunded(bridge_transfer_id) => {
+			active_swaps.get(bridge_transfer_id).is_some()
+		}
+	}
+}
+
+/// Whether `event` concerns the counterparty lock of a swap already taken on.
+fn is_active_counterparty_event<BFrom, BTo>(
+	event: &BridgeContractCounterpartyEvent<BTo::Address, BTo::Hash>,
+	active_swaps: &ActiveSwapMap<BFrom, BTo>,
+) -> bool
+where
+	BFrom: BlockchainService + 'static,
+	BTo: BlockchainService + 'static,
+
+	Vec<u8>: From<BTo::Address>,
+	Vec<u8>: From<BFrom::Address>,
+{
+	active_swaps.initiator_id_for_event(event).is_some()
+}
+
+/// Polls the next event of the chain `service`, out of which `active_swaps` takes transfers. While
+/// the direction is at capacity, `Initiated` events of new transfers are held back in `deferred`
+/// and replayed in order once it frees up. Other events keep flowing, so the swaps in flight still
+/// complete or are refunded.
+fn poll_chain_event<BFrom, BTo>(
+	service: &mut BFrom,
+	deferred: &mut VecDeque<BridgeContractInitiatorEvent<BFrom::Address, BFrom::Hash>>,
+	active_swaps: &ActiveSwapMap<BFrom, BTo>,
+	cx: &mut Context<'_>,
+) -> Poll<Option<ContractEvent<BFrom::Address, BFrom::Hash>>>
+where
+	BFrom: BlockchainService + 'static,
+	BTo: BlockchainService + 'static,
+
+	Vec<u8>: From<BTo::Address>,
+	Vec<u8>: From<BFrom::Address>,
+{
+	let at_capacity = active_swaps.capacity().is_reached();
+	if !at_capacity {
+		if let Some(event) = deferred.pop_front() {
+			// The service is not polled, so nothing else wakes the bridge for the next one
+			cx.waker().wake_by_ref();
+			return Poll::Ready(Some(ContractEvent::InitiatorEvent(event)));
+		}
+	}
+	loop {
+		match service.poll_next_unpin(cx) {
+			Poll::Ready(Some(ContractEvent::InitiatorEvent(
+				event @ BridgeContractInitiatorEvent::Initiated(_),
+			))) if at_capacity => {
+				trace!("BridgeService: Holding back {:?} until capacity frees up", event);
+				deferred.push_back(event);
+			}
+			event => return event,
 		}
 	}
 }
@@ -65,12 +442,11 @@ where
 fn handle_initiator_event<BFrom, BTo>(
 	initiator_event: BridgeContractInitiatorEvent<BFrom::Address, BFrom::Hash>,
 	active_swaps: &mut ActiveSwapMap<BFrom, BTo>,
-) -> Option<IEvent<BFrom::Address, BFrom::Hash>>
+) -> Option<HandleActiveSwapEvent<BFrom, BTo>>
 where
 	BFrom: BlockchainService + 'static,
 	BTo: BlockchainService + 'static,
-	BTo::Hash: From<BFrom::Hash>,
-	BTo::Address: From<Vec<u8>>,
+	BTo::Hash: TryConvert<BFrom::Hash> + TryConvert<Vec<u8>>,
 
 	Vec<u8>: From<BTo::Address>,
 	Vec<u8>: From<BFrom::Address>,
@@ -79,13 +455,79 @@ where
 		BridgeContractInitiatorEvent::Initiated(ref details) => {
 			if active_swaps.already_executing(&details.bridge_transfer_id) {
 				warn!("BridgeService: Bridge transfer {:?} already present, monitoring should only return event once", details.bridge_transfer_id);
-				return Some(IEvent::Warn(IWarn::AlreadyPresent(details.clone())));
+				return Some(HandleActiveSwapEvent::InitiatorEvent(IEvent::Warn(
+					IWarn::AlreadyPresent(details.clone()),
+				)));
 			}
-			active_swaps.start_bridge_transfer(details.clone());
-			Some(IEvent::ContractEvent(initiator_event))
+			match active_swaps.start_bridge_transfer(details.clone()) {
+				Ok(()) => Some(HandleActiveSwapEvent::InitiatorEvent(IEvent::ContractEvent(
+					initiator_event,
+				))),
+				Err(ActiveSwapMapError::Conversion { field, error }) => {
+					warn!(
+						"BridgeService: Bridge transfer {:?} cannot be locked on the counterparty chain, {:?}: {}",
+						details.bridge_transfer_id, field, error
+					);
+					Some(HandleActiveSwapEvent::InitiatorEvent(IEvent::Warn(
+						IWarn::CannotConvertTransfer {
+							bridge_transfer_id: details.bridge_transfer_id.clone(),
+							field,
+							error,
+						},
+					)))
+				}
+				Err(ActiveSwapMapError::HashLockReused { conflicting_transfer, held }) => {
+					error!(
+						"BridgeService: Bridge transfer {:?} reuses the hash lock of bridge transfer {:?}, held: {}",
+						details.bridge_transfer_id, conflicting_transfer, held
+					);
+					Some(HandleActiveSwapEvent::InitiatorEvent(IEvent::Warn(
+						IWarn::HashLockReused {
+							details: details.clone(),
+							conflicting_transfer,
+							held,
+						},
+					)))
+				}
+				Err(error) => {
+					warn!(
+						"BridgeService: Rejected bridge transfer {:?}: {}",
+						details.bridge_transfer_id, error
+					);
+					Some(HandleActiveSwapEvent::InitiatorEvent(IEvent::Warn(
+						IWarn::TransferRejected(details.clone(), error),
+					)))
+				}
+			}
+		}
+		BridgeContractInitiatorEvent::Completed(_) => {
+			Some(HandleActiveSwapEvent::InitiatorEvent(IEvent::ContractEvent(initiator_event)))
+		}
+		BridgeContractInitiatorEvent::Refunded(ref bridge_transfer_id) => {
+			match active_swaps.refund_bridge_transfer(bridge_transfer_id) {
+				Ok(()) => {
+					trace!("BridgeService: Bridge transfer {:?} refunded", bridge_transfer_id)
+				}
+				// Transfers the bridge did not take on are refunded without it
+				Err(ActiveSwapMapError::NonExistingSwap) => {}
+				Err(ActiveSwapMapError::ClaimedBeforeRefund) => {
+					error!(
+						"BridgeService: CRITICAL: Bridge transfer {:?} was refunded after its counterparty lock was claimed",
+						bridge_transfer_id
+					);
+					return Some(HandleActiveSwapEvent::InitiatorEvent(IEvent::Warn(
+						IWarn::RefundedAfterClaim(bridge_transfer_id.clone()),
+					)));
+				}
+				Err(error) => {
+					warn!(
+						"BridgeService: Error handling refund of {:?}: {}",
+						bridge_transfer_id, error
+					)
+				}
+			}
+			Some(HandleActiveSwapEvent::InitiatorEvent(IEvent::ContractEvent(initiator_event)))
 		}
-		BridgeContractInitiatorEvent::Completed(_) => Some(IEvent::ContractEvent(initiator_event)),
-		BridgeContractInitiatorEvent::Refunded(_) => todo!(),
 	}
 }
 
@@ -96,49 +538,113 @@ fn handle_counterparty_event<BFrom, BTo>(
 where
 	BFrom: BlockchainService + 'static,
 	BTo: BlockchainService + 'static,
-	BFrom::Hash: From<BTo::Hash>,
 
 	Vec<u8>: From<BTo::Address>,
 	Vec<u8>: From<BFrom::Address>,
 {
 	use BridgeContractCounterpartyEvent::*;
 	match event {
-		Locked(ref _details) => Some(CEvent::ContractEvent(event)),
-		Completed(ref details) => match active_swaps.complete_bridge_transfer(details.clone()) {
-			Ok(_) => {
-				trace!("BridgeService: Bridge transfer completed successfully");
-				Some(CEvent::ContractEvent(event))
+		Locked(ref details) => {
+			let anomalies = active_swaps.check_locked(details);
+			if !anomalies.is_empty() {
+				return Some(report_anomalies(event, anomalies));
+			}
+			trace!(
+				"BridgeService: Counterparty lock {:?} placed for bridge transfer {:?}",
+				details.bridge_transfer_id,
+				active_swaps.initiator_id_for_event(&event)
+			);
+			Some(CEvent::ContractEvent(event))
+		}
+		Completed(ref details) => {
+			// The completion is still attempted, it only succeeds with a secret that unlocks the
+			// initiator hash lock
+			let anomalies = active_swaps.check_completed(details);
+			let completion = active_swaps.complete_bridge_transfer(details.clone());
+			if !anomalies.is_empty() {
+				return Some(report_anomalies(event, anomalies));
 			}
-			Err(error) => {
-				warn!("BridgeService: Error completing bridge transfer: {:?}", error);
-				match error {
-					active_swap::ActiveSwapMapError::NonExistingSwap => {
-						Some(CEvent::Warn(CWarn::CannotCompleteUnexistingSwap(details.clone())))
+			match completion {
+				Ok(_) => {
+					trace!("BridgeService: Bridge transfer completed successfully");
+					Some(CEvent::ContractEvent(event))
+				}
+				Err(error) => {
+					warn!("BridgeService: Error completing bridge transfer: {:?}", error);
+					match error {
+						ActiveSwapMapError::NonExistingSwap => {
+							Some(CEvent::Warn(CWarn::CannotCompleteUnexistingSwap(details.clone())))
+						}
+						ActiveSwapMapError::InvalidHashLockPreImage => {
+							error!(
+								"BridgeService: Secret revealed for bridge transfer {:?} does not unlock the hash locks",
+								details.bridge_transfer_id
+							);
+							Some(CEvent::Warn(CWarn::InvalidHashLockPreImage(details.clone())))
+						}
+						ActiveSwapMapError::SecretNotReplayable(error) => {
+							error!(
+								"BridgeService: CRITICAL: Secret revealed for bridge transfer {:?} cannot be submitted on the initiator chain: {}",
+								details.bridge_transfer_id, error
+							);
+							Some(CEvent::Warn(CWarn::SecretNotReplayable(details.clone(), error)))
+						}
+						// Other errors are only raised when taking on, quoting, relaying,
+						// aborting or refunding a transfer
+						_ => None,
 					}
 				}
 			}
-		},
+		}
 	}
 }
 
-impl<B1, B2> Stream for BridgeService<B1, B2>
+fn report_anomalies<A: Debug, H: Debug>(
+	event: BridgeContractCounterpartyEvent<A, H>,
+	anomalies: Vec<SwapAnomaly>,
+) -> CEvent<A, H> {
+	error!("BridgeService: Anomalies {:?} in counterparty event {:?}", anomalies, event);
+	CEvent::Warn(CWarn::Anomaly(event, anomalies))
+}
+
+impl<B1, B2> BridgeService<B1, B2>
 where
 	B1: BlockchainService + 'static,
 	B2: BlockchainService + 'static,
 
-	B1::Hash: From<B2::Hash>,
-	B2::Hash: From<B1::Hash>,
-
-	B1::Address: From<Vec<u8>>,
-	B2::Address: From<Vec<u8>>,
+	B1::Hash: TryConvert<B2::Hash> + TryConvert<Vec<u8>>,
+	B2::Hash: TryConvert<B1::Hash> + TryConvert<Vec<u8>>,
 
 	Vec<u8>: From<B1::Address>,
 	Vec<u8>: From<B2::Address>,
 {
-	type Item = Event<B1, B2>;
+	/// Feeds an event to the circuit breaker of the direction of its swap.
+	fn observe(&mut self, event: &Event<B1, B2>) {
+		let (direction, input) = match event {
+			Event::B1I(event) => (Direction::B1ToB2, initiator_breaker_input(event)),
+			Event::B2C(event) => (Direction::B1ToB2, counterparty_breaker_input(event)),
+			Event::B2I(event) => (Direction::B2ToB1, initiator_breaker_input(event)),
+			Event::B1C(event) => (Direction::B2ToB1, counterparty_breaker_input(event)),
+			Event::CircuitBreaker(_) | Event::Capacity(_) => return,
+		};
+		let scope = BreakerScope::Direction(direction);
+		let event = match input {
+			Some(BreakerInput::Error) => self.circuit_breaker.record_error(direction),
+			Some(BreakerInput::Anomaly) if self.anomaly_policy.pause_on_anomaly => {
+				self.circuit_breaker.trip(scope, TripReason::Anomaly)
+			}
+			Some(BreakerInput::InsufficientLiquidity(asset))
+				if self.circuit_breaker.config.trip_on_insufficient_liquidity =>
+			{
+				self.circuit_breaker.trip(scope, TripReason::InsufficientLiquidity(asset))
+			}
+			_ => None,
+		};
+		self.apply_circuit_breaker_event(event);
+	}
 
-	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-		let this = self.get_mut();
+	fn poll_bridge(&mut self, cx: &mut Context<'_>) -> Poll<Option<Event<B1, B2>>> {
+		let this = self;
 
 		// Poll the active swaps in both directions and return the appropriate events
 		{
@@ -161,8 +667,20 @@ where
 			}
 		}
 
+		// Report capacity freed by finished swaps before taking on new transfers
+		this.update_capacity();
+		if let Some(event) = this.pending_events.pop_front() {
+			return Poll::Ready(Some(event));
+		}
+
 		// Poll the bridge services, handle the appropriate events, and return
-		match this.blockchain_1.poll_next_unpin(cx) {
+		let blockchain_event = poll_chain_event(
+			&mut this.blockchain_1,
+			&mut this.deferred_b1_to_b2,
+			&this.active_swaps_b1_to_b2,
+			cx,
+		);
+		match blockchain_event {
 			Poll::Ready(Some(blockchain_event)) => {
 				trace!(
 					"BridgeService: Received event from blockchain service 1: {:?}",
@@ -171,14 +689,48 @@ where
 				match blockchain_event {
 					ContractEvent::InitiatorEvent(initiator_event) => {
 						trace!("BridgeService: Initiator event from blockchain service 1");
-						if let Some(propagate_event) = handle_initiator_event::<B1, B2>(
+						if !this.directions.is_enabled(Direction::B1ToB2)
+							&& !is_active_initiator_event(
+								&initiator_event,
+								&this.active_swaps_b1_to_b2,
+							) {
+							warn!(
+								"BridgeService: Ignoring initiator event {:?}, direction {:?} is disabled",
+								initiator_event,
+								Direction::B1ToB2
+							);
+							return Poll::Ready(Some(Event::B1I(IEvent::Warn(
+								IWarn::DirectionDisabled(initiator_event),
+							))));
+						}
+						match handle_initiator_event::<B1, B2>(
 							initiator_event,
 							&mut this.active_swaps_b1_to_b2,
 						) {
-							return Poll::Ready(Some(Event::B1I(propagate_event)));
+							Some(HandleActiveSwapEvent::InitiatorEvent(event)) => {
+								return Poll::Ready(Some(Event::B1I(event)));
+							}
+							Some(HandleActiveSwapEvent::CounterpartyEvent(event)) => {
+								return Poll::Ready(Some(Event::B2C(event)));
+							}
+							None => {}
 						}
 					}
 					ContractEvent::CounterpartyEvent(counterparty_event) => {
+						if !this.directions.is_enabled(Direction::B2ToB1)
+							&& !is_active_counterparty_event(
+								&counterparty_event,
+								&this.active_swaps_b2_to_b1,
+							) {
+							warn!(
+								"BridgeService: Ignoring counterparty event {:?}, direction {:?} is disabled",
+								counterparty_event,
+								Direction::B2ToB1
+							);
+							return Poll::Ready(Some(Event::B1C(CEvent::Warn(
+								CWarn::DirectionDisabled(counterparty_event),
+							))));
+						}
 						if let Some(propagate_event) = handle_counterparty_event::<B2, B1>(
 							counterparty_event,
 							&mut this.active_swaps_b2_to_b1,
@@ -197,7 +749,13 @@ where
 			}
 		}
 
-		match this.blockchain_2.poll_next_unpin(cx) {
+		let blockchain_event = poll_chain_event(
+			&mut this.blockchain_2,
+			&mut this.deferred_b2_to_b1,
+			&this.active_swaps_b2_to_b1,
+			cx,
+		);
+		match blockchain_event {
 			Poll::Ready(Some(blockchain_event)) => {
 				trace!(
 					"BridgeService: Received event from blockchain service 2: {:?}",
@@ -206,15 +764,49 @@ where
 				match blockchain_event {
 					ContractEvent::InitiatorEvent(initiator_event) => {
 						trace!("BridgeService: Initiator event from blockchain service 2");
-						if let Some(propagate_event) = handle_initiator_event::<B2, B1>(
+						if !this.directions.is_enabled(Direction::B2ToB1)
+							&& !is_active_initiator_event(
+								&initiator_event,
+								&this.active_swaps_b2_to_b1,
+							) {
+							warn!(
+								"BridgeService: Ignoring initiator event {:?}, direction {:?} is disabled",
+								initiator_event,
+								Direction::B2ToB1
+							);
+							return Poll::Ready(Some(Event::B2I(IEvent::Warn(
+								IWarn::DirectionDisabled(initiator_event),
+							))));
+						}
+						match handle_initiator_event::<B2, B1>(
 							initiator_event,
 							&mut this.active_swaps_b2_to_b1,
 						) {
-							return Poll::Ready(Some(Event::B2I(propagate_event)));
+							Some(HandleActiveSwapEvent::InitiatorEvent(event)) => {
+								return Poll::Ready(Some(Event::B2I(event)));
+							}
+							Some(HandleActiveSwapEvent::CounterpartyEvent(event)) => {
+								return Poll::Ready(Some(Event::B1C(event)));
+							}
+							None => {}
 						}
 					}
 					ContractEvent::CounterpartyEvent(counterparty_event) => {
 						trace!("BridgeService: Counterparty event from blockchain service 2");
+						if !this.directions.is_enabled(Direction::B1ToB2)
+							&& !is_active_counterparty_event(
+								&counterparty_event,
+								&this.active_swaps_b1_to_b2,
+							) {
+							warn!(
+								"BridgeService: Ignoring counterparty event {:?}, direction {:?} is disabled",
+								counterparty_event,
+								Direction::B1ToB2
+							);
+							return Poll::Ready(Some(Event::B2C(CEvent::Warn(
+								CWarn::DirectionDisabled(counterparty_event),
+							))));
+						}
 						if let Some(propagate_event) = handle_counterparty_event::<B1, B2>(
 							counterparty_event,
 							&mut this.active_swaps_b1_to_b2,
@@ -236,6 +828,41 @@ where
 	}
 }
 
+impl<B1, B2> Stream for BridgeService<B1, B2>
+where
+	B1: BlockchainService + 'static,
+	B2: BlockchainService + 'static,
+
+	B1::Hash: TryConvert<B2::Hash> + TryConvert<Vec<u8>>,
+	B2::Hash: TryConvert<B1::Hash> + TryConvert<Vec<u8>>,
+
+	Vec<u8>: From<B1::Address>,
+	Vec<u8>: From<B2::Address>,
+{
+	type Item = Event<B1, B2>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		this.waker.register(cx.waker());
+		if let Some(event) = this.pending_events.pop_front() {
+			return Poll::Ready(Some(event));
+		}
+
+		let event = this.poll_bridge(cx);
+		if let Poll::Ready(Some(event)) = &event {
+			this.observe(event);
+		}
+		this.update_capacity();
+		match event {
+			Poll::Pending => match this.pending_events.pop_front() {
+				Some(event) => Poll::Ready(Some(event)),
+				None => Poll::Pending,
+			},
+			event => event,
+		}
+	}
+}
+
 // Initiator events pertain to the initiator contract, while counterparty events are associated
 // with the counterparty contract.
 enum HandleActiveSwapEvent<BFrom, BTo>
@@ -248,12 +875,11 @@ where
 }
 
 fn handle_active_swap_event<BFrom, BTo>(
-	active_swap_event: Poll<Option<ActiveSwapEvent<BFrom::Hash>>>,
+	active_swap_event: Poll<Option<ActiveSwapEvent<BFrom::Hash, BTo::Hash>>>,
 ) -> Option<HandleActiveSwapEvent<BFrom, BTo>>
 where
 	BFrom: BlockchainService + 'static,
 	BTo: BlockchainService + 'static,
-	BTo::Hash: From<BFrom::Hash>,
 {
 	use ActiveSwapEvent::*;
 	match active_swap_event {
@@ -282,9 +908,7 @@ where
 						bridge_transfer_id
 					);
 					return Some(HandleActiveSwapEvent::CounterpartyEvent(
-						CEvent::RetryLockingAssets(BridgeTransferId(From::from(
-							bridge_transfer_id.0,
-						))),
+						CEvent::RetryLockingAssets(bridge_transfer_id),
 					));
 				}
 				BridgeAssetsLockingAbortedTooManyAttempts(bride_transfer_id) => {
@@ -293,9 +917,7 @@ where
 						bride_transfer_id
 					);
 					return Some(HandleActiveSwapEvent::CounterpartyEvent(CEvent::Warn(
-						CWarn::LockingAbortedTooManyAttempts(BridgeTransferId(From::from(
-							bride_transfer_id.0,
-						))),
+						CWarn::LockingAbortedTooManyAttempts(bride_transfer_id),
 					)));
 				}
 
@@ -332,6 +954,81 @@ where
 						IWarn::CompletionAbortedTooManyAttempts(bridge_transfer_id),
 					)));
 				}
+
+				// Aborting
+				BridgeAssetsLockAborted(bridge_transfer_id) => {
+					trace!("BridgeService: Bridge assets lock aborted {:?}", bridge_transfer_id);
+					return Some(HandleActiveSwapEvent::CounterpartyEvent(CEvent::LockAborted(
+						bridge_transfer_id,
+					)));
+				}
+				BridgeAssetsAbortingError(bridge_transfer_id, error) => {
+					warn!("BridgeService: Error aborting bridge assets lock: {:?}", error);
+					return Some(HandleActiveSwapEvent::CounterpartyEvent(CEvent::Warn(
+						CWarn::AbortingLockError(bridge_transfer_id, error),
+					)));
+				}
+				BridgeAssetsAbortingAbortedTooManyAttempts(bridge_transfer_id) => {
+					warn!(
+						"BridgeService: Gave up aborting bridge assets lock due to too many errors: {:?}",
+						bridge_transfer_id
+					);
+					return Some(HandleActiveSwapEvent::CounterpartyEvent(CEvent::Warn(
+						CWarn::AbortingAbortedTooManyAttempts(bridge_transfer_id),
+					)));
+				}
+
+				// Relaying
+				BridgeAssetsSecretRelayed(bridge_transfer_id) => {
+					trace!("BridgeService: Relayed secret claimed lock {:?}", bridge_transfer_id);
+					return Some(HandleActiveSwapEvent::CounterpartyEvent(CEvent::SecretRelayed(
+						bridge_transfer_id,
+					)));
+				}
+				BridgeAssetsRelayingError(bridge_transfer_id, error) => {
+					warn!("BridgeService: Error claiming lock with relayed secret: {:?}", error);
+					return Some(HandleActiveSwapEvent::CounterpartyEvent(CEvent::Warn(
+						CWarn::RelayingSecretError(bridge_transfer_id, error),
+					)));
+				}
+				BridgeAssetsRelayingAborted(bridge_transfer_id) => {
+					warn!(
+						"BridgeService: Gave up claiming lock with relayed secret: {:?}",
+						bridge_transfer_id
+					);
+					return Some(HandleActiveSwapEvent::CounterpartyEvent(CEvent::Warn(
+						CWarn::RelayingAborted(bridge_transfer_id),
+					)));
+				}
+
+				// Refunds
+				BridgeAssetsLockingCancelled(bridge_transfer_id) => {
+					trace!(
+						"BridgeService: Locking cancelled by the refund of the initiator: {:?}",
+						bridge_transfer_id
+					);
+					return Some(HandleActiveSwapEvent::CounterpartyEvent(
+						CEvent::LockingCancelled(bridge_transfer_id),
+					));
+				}
+				BridgeAssetsLockingExpired(bridge_transfer_id, error) => {
+					warn!(
+						"BridgeService: Gave up bridge assets lock {:?} before placing it: {}",
+						bridge_transfer_id, error
+					);
+					return Some(HandleActiveSwapEvent::CounterpartyEvent(CEvent::Warn(
+						CWarn::LockingExpired(bridge_transfer_id, error),
+					)));
+				}
+				BridgeAssetsRelayingCancelled(bridge_transfer_id) => {
+					trace!(
+						"BridgeService: Relayed claim cancelled by the refund of the initiator: {:?}",
+						bridge_transfer_id
+					);
+					return Some(HandleActiveSwapEvent::CounterpartyEvent(
+						CEvent::RelayingCancelled(bridge_transfer_id),
+					));
+				}
 			}
 		}
 		Poll::Ready(None) => {
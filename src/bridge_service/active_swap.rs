@@ -1,9 +1,9 @@
 use std::{
-	collections::HashMap,
+	collections::{HashMap, VecDeque},
 	convert::From,
 	pin::Pin,
 	task::{Context, Poll},
-	time::Duration,
+	time::{Duration, Instant},
 };
 
 use futures::{task::AtomicWaker, Future, FutureExt, Stream};
@@ -12,11 +12,27 @@ use futures_timer::Delay;
 use thiserror::Error;
 
 use crate::{
+	address_codec::AddressCodecError,
 	blockchain_service::BlockchainService,
 	bridge_contracts::{BridgeContractCounterpartyError, BridgeContractInitiatorError},
+	bridge_monitoring::BridgeContractCounterpartyEvent,
+	bridge_service::{
+		anomaly::{lock_anomalies, SwapAnomaly},
+		assets::{AssetConfig, AssetMapping},
+		call_limit::{CallKind, CallLimiter, CallTicket, SharedCallLimiter},
+		hash_lock_index::{
+			HashLockIndex, HashLockReuseConfig, HashLockReusePolicy, SharedHashLockIndex,
+		},
+		netting::{PlannerChains, SharedLiquidityPlanner},
+		quote::{Quote, QuoteConfig, QuoteId, Quotes, Reservation, TimeLockRange},
+		time_lock::{TimeLockError, TimeLockPolicy},
+		ChainConfig,
+	},
+	hash_lock::{SecretConstraintError, SecretConstraints},
 	types::{
-		convert_bridge_transfer_id, BridgeTransferDetails, BridgeTransferId,
-		CounterpartyCompletedDetails, HashLock, InitiatorAddress,
+		Amount, AmountConversionError, AssetId, BridgeTransferDetails, BridgeTransferId, ChainId,
+		ConversionError, CounterpartyCompletedDetails, HashLock, HashLockPreImage,
+		InitiatorAddress, LockDetails, TimeLock, TryConvert,
 	},
 };
 use crate::{
@@ -26,12 +42,31 @@ use crate::{
 
 pub type BoxedFuture<R, E> = Timeout<Pin<Box<dyn Future<Output = Result<R, E>> + Send>>, Delay>;
 
+type HeldTransfers<B> = HashMap<
+	BridgeTransferId<<B as BlockchainService>::Hash>,
+	BridgeTransferDetails<<B as BlockchainService>::Address, <B as BlockchainService>::Hash>,
+>;
+
+type SwapEntry<'a, BFrom, BTo> =
+	(&'a BridgeTransferId<<BFrom as BlockchainService>::Hash>, &'a ActiveSwap<BFrom, BTo>);
+
 pub struct ActiveSwap<BFrom, BTo>
 where
 	BFrom: BlockchainService,
 	BTo: BlockchainService,
 {
 	pub details: BridgeTransferDetails<BFrom::Address, BFrom::Hash>,
+	pub lock_details: LockDetails<BTo::Address, BTo::Hash>,
+	/// Id the counterparty contract placed the lock under, known once the lock succeeded.
+	pub counterparty_bridge_transfer_id: Option<BridgeTransferId<BTo::Hash>>,
+	/// Initiator time lock, resolved against the initiator clock when the transfer was reported.
+	pub initiator_time_lock: TimeLock,
+	/// Counterparty time lock, resolved against the counterparty clock when the lock was placed.
+	/// Unknown without a counterparty clock, in which case unclaimed locks are never taken back.
+	pub counterparty_expiry: Option<TimeLock>,
+	/// The initiator transfer was refunded while its lock call was in flight, so a placed lock is
+	/// given back right away.
+	pub refunded: bool,
 	pub state: ActiveSwapState<BTo>,
 }
 
@@ -43,19 +78,45 @@ where
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		f.debug_struct("ActiveSwap")
 			.field("details", &self.details)
+			.field("lock_details", &self.lock_details)
+			.field("counterparty_bridge_transfer_id", &self.counterparty_bridge_transfer_id)
+			.field("initiator_time_lock", &self.initiator_time_lock)
+			.field("counterparty_expiry", &self.counterparty_expiry)
+			.field("refunded", &self.refunded)
 			.field("state", &self.state)
 			.finish()
 	}
 }
 
+/// Swaps in flight in one direction. Once the maximum is reached the bridge service holds back
+/// `Initiated` events from the initiator chain until swaps finish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapCapacity {
+	pub active: usize,
+	pub max: Option<usize>,
+}
+
+impl SwapCapacity {
+	pub fn is_reached(&self) -> bool {
+		self.max.is_some_and(|max| self.active >= max)
+	}
+}
+
 type Attempts = usize;
 
 pub enum ActiveSwapState<BTo>
 where
 	BTo: BlockchainService,
 {
-	LockingTokens(BoxedFuture<(), LockBridgeTransferAssetsError>, Attempts),
+	LockingTokens(
+		BoxedFuture<BridgeTransferId<BTo::Hash>, LockBridgeTransferAssetsError>,
+		Attempts,
+	),
 	LockingTokensError(Delay, Attempts),
+	/// Waiting for a call slot on the counterparty chain to lock.
+	QueuedLocking(CallTicket, Attempts),
+	/// Lock taken out of the call queue while the direction is paused, queued again on resume.
+	PausedLocking(Attempts),
 	WaitingForUnlockedEvent,
 	CompletingBridging(
 		BoxedFuture<(), CompleteBridgeTransferError>,
@@ -63,6 +124,25 @@ where
 		Attempts,
 	),
 	CompletingBridgingError(Delay, CounterpartyCompletedDetails<BTo::Address, BTo::Hash>, Attempts),
+	/// Waiting for a call slot on the initiator chain to complete.
+	QueuedCompleting(CallTicket, CounterpartyCompletedDetails<BTo::Address, BTo::Hash>, Attempts),
+	/// Giving the counterparty lock back, see `ActiveSwapMap::abort_bridge_transfer`.
+	AbortingLock(BoxedFuture<(), AbortBridgeTransferError>, Attempts),
+	AbortingLockError(Delay, Attempts),
+	/// Claiming the counterparty lock with a secret the recipient handed to the bridge, see
+	/// `ActiveSwapMap::relay_secret`. Attempts stop at the deadline.
+	RelayingSecret(
+		BoxedFuture<(), RelaySecretError>,
+		CounterpartyCompletedDetails<BTo::Address, BTo::Hash>,
+		Instant,
+		Attempts,
+	),
+	RelayingSecretError(
+		Delay,
+		CounterpartyCompletedDetails<BTo::Address, BTo::Hash>,
+		Instant,
+		Attempts,
+	),
 	Completed,
 	Aborted,
 }
@@ -79,6 +159,12 @@ where
 			ActiveSwapState::LockingTokensError(_, attempts) => {
 				f.debug_struct("LockingTokensError").field("attempts", attempts).finish()
 			}
+			ActiveSwapState::QueuedLocking(_, attempts) => {
+				f.debug_struct("QueuedLocking").field("attempts", attempts).finish()
+			}
+			ActiveSwapState::PausedLocking(attempts) => {
+				f.debug_struct("PausedLocking").field("attempts", attempts).finish()
+			}
 			ActiveSwapState::WaitingForUnlockedEvent => {
 				f.debug_tuple("WaitingForUnlockedEvent").finish()
 			}
@@ -88,6 +174,25 @@ where
 			ActiveSwapState::CompletingBridgingError(_, _, attempts) => {
 				f.debug_struct("CompletingBridgingError").field("attempts", attempts).finish()
 			}
+			ActiveSwapState::QueuedCompleting(_, _, attempts) => {
+				f.debug_struct("QueuedCompleting").field("attempts", attempts).finish()
+			}
+			ActiveSwapState::AbortingLock(_, attempts) => {
+				f.debug_struct("AbortingLock").field("attempts", attempts).finish()
+			}
+			ActiveSwapState::AbortingLockError(_, attempts) => {
+				f.debug_struct("AbortingLockError").field("attempts", attempts).finish()
+			}
+			ActiveSwapState::RelayingSecret(_, _, deadline, attempts) => f
+				.debug_struct("RelayingSecret")
+				.field("deadline", deadline)
+				.field("attempts", attempts)
+				.finish(),
+			ActiveSwapState::RelayingSecretError(_, _, deadline, attempts) => f
+				.debug_struct("RelayingSecretError")
+				.field("deadline", deadline)
+				.field("attempts", attempts)
+				.finish(),
 			ActiveSwapState::Completed => f.debug_tuple("Completed").finish(),
 			ActiveSwapState::Aborted => f.debug_tuple("Aborted").finish(),
 		}
@@ -110,15 +215,65 @@ impl Default for ActiveSwapConfig {
 	}
 }
 
+/// Retries of the counterparty claims the bridge makes with secrets relayed by recipients.
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+	pub error_attempts: usize,
+	pub error_delay: Duration,
+	pub contract_call_timeout: Duration,
+	/// No attempt is started once this long has passed since the secret was relayed.
+	pub timeout: Duration,
+}
+
+impl Default for RelayConfig {
+	fn default() -> Self {
+		Self {
+			error_attempts: 5,
+			error_delay: Duration::from_secs(2),
+			contract_call_timeout: Duration::from_secs(30),
+			timeout: Duration::from_secs(5 * 60),
+		}
+	}
+}
+
 pub struct ActiveSwapMap<BFrom, BTo>
 where
 	BFrom: BlockchainService,
 	BTo: BlockchainService,
 {
 	pub config: ActiveSwapConfig,
+	pub assets: AssetMapping,
+	pub time_lock_policy: TimeLockPolicy,
+	pub initiator_chain: ChainConfig,
+	pub counterparty_chain: ChainConfig,
 	pub initiator_contract: BFrom::InitiatorContract,
 	pub counterparty_contract: BTo::CounterpartyContract,
 	swaps: HashMap<BridgeTransferId<BFrom::Hash>, ActiveSwap<BFrom, BTo>>,
+	/// Maps counterparty lock ids back to the initiator id of their swap.
+	counterparty_ids: HashMap<BridgeTransferId<BTo::Hash>, BridgeTransferId<BFrom::Hash>>,
+	/// Transfers put aside by the hash lock reuse policy, waiting for an operator decision.
+	held: HeldTransfers<BFrom>,
+	hash_locks: SharedHashLockIndex,
+	/// Contract calls to the initiator chain, which complete swaps.
+	initiator_calls: SharedCallLimiter,
+	/// Contract calls to the counterparty chain, which lock assets.
+	counterparty_calls: SharedCallLimiter,
+	/// Active swaps the map takes on at most, see `SwapCapacity`.
+	pub max_active_swaps: Option<usize>,
+	/// No new locks are placed while paused, see the circuit breaker of the bridge service.
+	paused: bool,
+	liquidity: HashMap<AssetId, Amount>,
+	/// Reports the flows of the map and lends it credit against opposite flows.
+	planner: Option<PlannerChains>,
+	pub quote_config: QuoteConfig,
+	pub relay_config: RelayConfig,
+	quotes: Quotes,
+	/// Wakes the map when the next reservation expires.
+	reservation_timer: Option<Delay>,
+	/// Wakes the map when the next unclaimed counterparty lock expires.
+	expiry_timer: Option<Delay>,
+	/// Events raised outside of polling, returned before polling the swaps.
+	pending_events: VecDeque<ActiveSwapEvent<BFrom::Hash, BTo::Hash>>,
 	waker: AtomicWaker,
 }
 
@@ -130,15 +285,97 @@ where
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		f.debug_struct("ActiveSwapMap")
 			.field("swaps", &self.swaps)
+			.field("counterparty_ids", &self.counterparty_ids)
+			.field("held", &self.held)
+			.field("hash_locks", &self.hash_locks)
+			.field("initiator_calls", &self.initiator_calls)
+			.field("counterparty_calls", &self.counterparty_calls)
 			.field("config", &self.config)
+			.field("assets", &self.assets)
+			.field("time_lock_policy", &self.time_lock_policy)
+			.field("initiator_chain", &self.initiator_chain)
+			.field("counterparty_chain", &self.counterparty_chain)
+			.field("max_active_swaps", &self.max_active_swaps)
+			.field("paused", &self.paused)
+			.field("liquidity", &self.liquidity)
+			.field("planner", &self.planner)
+			.field("quote_config", &self.quote_config)
+			.field("relay_config", &self.relay_config)
+			.field("quotes", &self.quotes)
 			.finish()
 	}
 }
 
-#[derive(Debug, Error)]
+/// Part of an initiated transfer that is converted to the counterparty chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferField {
+	BridgeTransferId,
+	RecipientAddress,
+	HashLock,
+}
+
+impl TransferField {
+	fn conversion(self) -> impl FnOnce(ConversionError) -> ActiveSwapMapError {
+		move |error| ActiveSwapMapError::Conversion { field: self, error }
+	}
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
 pub enum ActiveSwapMapError {
 	#[error("Non existing swap")]
 	NonExistingSwap,
+	#[error("Asset {0:?} is not mapped to a counterparty asset")]
+	UnmappedAsset(AssetId),
+	#[error("Amount {amount:?} is below the minimum {min:?}")]
+	AmountBelowMinimum { amount: Amount, min: Amount },
+	#[error("Amount {amount:?} is above the maximum {max:?}")]
+	AmountAboveMaximum { amount: Amount, max: Amount },
+	#[error("Fee {fee:?} does not leave anything to bridge from amount {amount:?}")]
+	FeeExceedsAmount { amount: Amount, fee: Amount },
+	#[error("Insufficient liquidity for asset {asset:?}: required {required:?}, available {available:?}")]
+	InsufficientLiquidity { asset: AssetId, required: Amount, available: Amount },
+	#[error(transparent)]
+	AmountConversion(#[from] AmountConversionError),
+	#[error(transparent)]
+	TimeLock(#[from] TimeLockError),
+	#[error("{field:?} cannot be represented on the counterparty chain: {error}")]
+	Conversion { field: TransferField, error: ConversionError },
+	#[error("Invalid recipient: {0}")]
+	InvalidRecipient(AddressCodecError),
+	#[error("Secret does not unlock the initiator and counterparty hash locks")]
+	InvalidHashLockPreImage,
+	#[error("Chains hash with {initiator} and {counterparty} but no counterparty hash lock was committed to")]
+	MissingCounterpartyHashLock { initiator: &'static str, counterparty: &'static str },
+	#[error("Counterparty hash lock is inconsistent with the initiator hash lock")]
+	InconsistentHashLocks,
+	#[error("Hash lock is already used by bridge transfer {conflicting_transfer:?}")]
+	HashLockReused { conflicting_transfer: Vec<u8>, held: bool },
+	#[error("Bridge transfer is not held")]
+	NotHeld,
+	#[error("Transfers in this direction are paused")]
+	Paused,
+	#[error("The maximum number of active swaps is reached")]
+	AtCapacity,
+	#[error("The counterparty chain accepts pre-images the initiator chain rejects")]
+	IncompatibleSecretConstraints,
+	#[error("Secret cannot be submitted on the initiator chain: {0}")]
+	SecretNotReplayable(SecretConstraintError),
+	#[error("Only swaps whose counterparty lock is placed and not completing can be aborted")]
+	NotAbortable,
+	#[error("Transfers in this direction are disabled")]
+	DirectionDisabled,
+	#[error("Quote is unknown, expired or already accepted")]
+	UnknownQuote,
+	#[error("Hash lock is already reserved by an accepted quote")]
+	HashLockReserved,
+	#[error("Secret cannot be submitted on the counterparty chain: {0}")]
+	SecretRejected(SecretConstraintError),
+	#[error(
+		"Only swaps whose counterparty lock is placed and not claimed can take a relayed secret"
+	)]
+	NotRelayable,
+	#[error("The initiator transfer was refunded after the counterparty lock was claimed")]
+	ClaimedBeforeRefund,
 }
 
 impl<BTo, BFrom> ActiveSwapMap<BFrom, BTo>
@@ -152,16 +389,115 @@ where
 		initiator_contract: BFrom::InitiatorContract,
 		counterparty_contract: BTo::CounterpartyContract,
 		config: ActiveSwapConfig,
+		assets: AssetMapping,
+		time_lock_policy: TimeLockPolicy,
+		initiator_chain: ChainConfig,
+		counterparty_chain: ChainConfig,
 	) -> Self {
+		let liquidity = assets
+			.iter()
+			.filter_map(|(asset, config)| Some((asset.clone(), config.liquidity?)))
+			.collect();
 		Self {
 			initiator_contract,
 			counterparty_contract,
 			swaps: HashMap::new(),
+			counterparty_ids: HashMap::new(),
+			held: HashMap::new(),
+			hash_locks: HashLockIndex::shared(HashLockReuseConfig::default()),
+			initiator_calls: CallLimiter::shared(initiator_chain.call_limit),
+			counterparty_calls: CallLimiter::shared(counterparty_chain.call_limit),
+			max_active_swaps: None,
+			paused: false,
 			config,
+			assets,
+			time_lock_policy,
+			liquidity,
+			planner: None,
+			quote_config: QuoteConfig::default(),
+			relay_config: RelayConfig::default(),
+			quotes: Quotes::default(),
+			reservation_timer: None,
+			expiry_timer: None,
+			pending_events: VecDeque::new(),
+			initiator_chain,
+			counterparty_chain,
 			waker: AtomicWaker::new(),
 		}
 	}
 
+	/// Shares the hash lock index with the swap map of the opposite direction, so reuse is detected
+	/// across both.
+	pub fn with_hash_lock_index(mut self, hash_locks: SharedHashLockIndex) -> Self {
+		self.hash_locks = hash_locks;
+		self
+	}
+
+	/// Shares the call limiters of both chains with the swap map of the opposite direction, so the
+	/// limits hold for all calls to a chain.
+	pub fn with_call_limiters(
+		mut self,
+		initiator_calls: SharedCallLimiter,
+		counterparty_calls: SharedCallLimiter,
+	) -> Self {
+		self.initiator_calls = initiator_calls;
+		self.counterparty_calls = counterparty_calls;
+		self
+	}
+
+	/// Shares the liquidity planner with the swap map of the opposite direction, which nets their
+	/// flows. The map swaps from chain `initiator` to chain `counterparty` of the planner.
+	pub fn with_liquidity_planner(
+		mut self,
+		planner: SharedLiquidityPlanner,
+		initiator: ChainId,
+		counterparty: ChainId,
+	) -> Self {
+		self.planner = Some(PlannerChains { planner, initiator, counterparty });
+		self
+	}
+
+	pub fn with_quote_config(mut self, quote_config: QuoteConfig) -> Self {
+		self.quote_config = quote_config;
+		self
+	}
+
+	pub fn with_relay_config(mut self, relay_config: RelayConfig) -> Self {
+		self.relay_config = relay_config;
+		self
+	}
+
+	pub fn with_max_active_swaps(mut self, max_active_swaps: Option<usize>) -> Self {
+		self.max_active_swaps = max_active_swaps;
+		self
+	}
+
+	pub fn capacity(&self) -> SwapCapacity {
+		let active = self
+			.swaps
+			.values()
+			.filter(|swap| {
+				!matches!(swap.state, ActiveSwapState::Completed | ActiveSwapState::Aborted)
+			})
+			.count();
+		SwapCapacity { active, max: self.max_active_swaps }
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.paused
+	}
+
+	/// Stops placing new locks. Swaps already locked carry on, so they can still complete.
+	pub fn pause(&mut self) {
+		self.paused = true;
+	}
+
+	pub fn resume(&mut self) {
+		self.paused = false;
+		// Lock retries held back while paused
+		self.waker.wake();
+	}
+
 	pub fn get(&self, key: &BridgeTransferId<BFrom::Hash>) -> Option<&ActiveSwap<BFrom, BTo>> {
 		self.swaps.get(key)
 	}
@@ -173,33 +509,550 @@ where
 		self.swaps.get_mut(key)
 	}
 
+	/// Initiator id of the swap whose counterparty lock was placed under `counterparty_id`.
+	pub fn initiator_bridge_transfer_id(
+		&self,
+		counterparty_id: &BridgeTransferId<BTo::Hash>,
+	) -> Option<&BridgeTransferId<BFrom::Hash>> {
+		self.swap_for_counterparty_id(counterparty_id)
+			.map(|(bridge_transfer_id, _)| bridge_transfer_id)
+	}
+
+	/// Counterparty id of a swap, `None` until its lock has been placed.
+	pub fn counterparty_bridge_transfer_id(
+		&self,
+		key: &BridgeTransferId<BFrom::Hash>,
+	) -> Option<&BridgeTransferId<BTo::Hash>> {
+		self.swaps.get(key)?.counterparty_bridge_transfer_id.as_ref()
+	}
+
+	fn swap_for_counterparty_id(
+		&self,
+		counterparty_id: &BridgeTransferId<BTo::Hash>,
+	) -> Option<SwapEntry<'_, BFrom, BTo>> {
+		match self.counterparty_ids.get(counterparty_id) {
+			Some(bridge_transfer_id) => self.swaps.get_key_value(bridge_transfer_id),
+			// The lock may be reported before the lock call returned its id
+			None => self.swaps.iter().find(|(_, swap)| {
+				swap.counterparty_bridge_transfer_id.is_none()
+					&& swap.lock_details.bridge_transfer_id == *counterparty_id
+			}),
+		}
+	}
+
+	/// Finds the swap a counterparty lock belongs to. A lock reported before its call returned may
+	/// be placed under an id the counterparty contract assigned, so it is matched on its hash lock,
+	/// which is unique among open swaps.
+	fn swap_for_lock(
+		&self,
+		details: &LockDetails<BTo::Address, BTo::Hash>,
+	) -> Option<SwapEntry<'_, BFrom, BTo>> {
+		self.swap_for_counterparty_id(&details.bridge_transfer_id).or_else(|| {
+			self.swaps.iter().find(|(_, swap)| {
+				swap.counterparty_bridge_transfer_id.is_none()
+					&& matches!(
+						swap.state,
+						ActiveSwapState::LockingTokens(..)
+							| ActiveSwapState::LockingTokensError(..)
+							| ActiveSwapState::QueuedLocking(..)
+					) && swap.lock_details.hash_lock == details.hash_lock
+			})
+		})
+	}
+
+	/// Initiator id of the swap a counterparty event belongs to.
+	pub fn initiator_id_for_event(
+		&self,
+		event: &BridgeContractCounterpartyEvent<BTo::Address, BTo::Hash>,
+	) -> Option<&BridgeTransferId<BFrom::Hash>> {
+		match event {
+			BridgeContractCounterpartyEvent::Locked(details) => self.swap_for_lock(details),
+			BridgeContractCounterpartyEvent::Completed(details) => {
+				self.swap_for_counterparty_id(&details.bridge_transfer_id)
+			}
+		}
+		.map(|(bridge_transfer_id, _)| bridge_transfer_id)
+	}
+
+	/// Cross-checks a counterparty lock against the lock the bridge requested.
+	pub fn check_locked(&self, details: &LockDetails<BTo::Address, BTo::Hash>) -> Vec<SwapAnomaly> {
+		let Some((_, swap)) = self.swap_for_lock(details) else {
+			return vec![SwapAnomaly::UnknownTransfer];
+		};
+		let expected = &swap.lock_details;
+		let mut anomalies = lock_anomalies(
+			expected,
+			&details.recipient_address,
+			&details.hash_lock,
+			&details.asset,
+			details.amount,
+		);
+		if details.time_lock != expected.time_lock {
+			anomalies.push(SwapAnomaly::TimeLockMismatch {
+				expected: expected.time_lock.clone(),
+				actual: details.time_lock.clone(),
+			});
+		}
+		anomalies
+	}
+
+	/// Cross-checks a counterparty completion against the lock the bridge requested. Completions
+	/// of unknown swaps are reported when completing.
+	pub fn check_completed(
+		&self,
+		details: &CounterpartyCompletedDetails<BTo::Address, BTo::Hash>,
+	) -> Vec<SwapAnomaly> {
+		let Some((_, swap)) = self.swap_for_counterparty_id(&details.bridge_transfer_id) else {
+			return Vec::new();
+		};
+		lock_anomalies(
+			&swap.lock_details,
+			&details.recipient_address,
+			&details.hash_lock,
+			&details.asset,
+			details.amount,
+		)
+	}
+
 	pub fn already_executing(&self, key: &BridgeTransferId<BFrom::Hash>) -> bool {
-		self.swaps.contains_key(key)
+		self.swaps.contains_key(key) || self.held.contains_key(key)
+	}
+
+	pub fn held_transfers(
+		&self,
+	) -> impl Iterator<Item = &BridgeTransferDetails<BFrom::Address, BFrom::Hash>> {
+		self.held.values()
+	}
+
+	/// Drops a held transfer. Its initiator lock is left to expire and be refunded.
+	pub fn discard_held_transfer(
+		&mut self,
+		key: &BridgeTransferId<BFrom::Hash>,
+	) -> Option<BridgeTransferDetails<BFrom::Address, BFrom::Hash>> {
+		self.held.remove(key)
+	}
+
+	/// Starts a held transfer despite its hash lock being reused. The admission checks still apply,
+	/// a transfer failing them stays held.
+	pub fn release_held_transfer(
+		&mut self,
+		key: &BridgeTransferId<BFrom::Hash>,
+	) -> Result<(), ActiveSwapMapError>
+	where
+		BTo::Hash: TryConvert<BFrom::Hash> + TryConvert<Vec<u8>>,
+	{
+		let details = self.held.get(key).ok_or(ActiveSwapMapError::NotHeld)?;
+		if self.paused {
+			return Err(ActiveSwapMapError::Paused);
+		}
+		if self.capacity().is_reached() {
+			return Err(ActiveSwapMapError::AtCapacity);
+		}
+		let lock_details = self.lock_details(details)?;
+		let details = self.held.remove(key).expect("checked above");
+		tracing::warn!("Releasing held bridge transfer {:?}", key);
+		self.lock_hash_locks()
+			.insert(&hash_locks_of(&details, &lock_details), details.bridge_transfer_id.0.as_ref());
+		self.start_locking(details, lock_details);
+		Ok(())
+	}
+
+	fn lock_hash_locks(&self) -> std::sync::MutexGuard<'_, HashLockIndex> {
+		self.hash_locks.lock().expect("hash lock index poisoned")
+	}
+
+	/// Time left before the initiator lock of a swap expires. `None` when the swap is unknown or
+	/// the initiator chain has no clock.
+	pub fn time_remaining(&self, key: &BridgeTransferId<BFrom::Hash>) -> Option<Duration> {
+		let swap = self.swaps.get(key)?;
+		let clock = self.initiator_chain.clock.as_deref()?;
+		Some(swap.initiator_time_lock.time_remaining(&clock.now(), clock.block_time()))
+	}
+
+	/// Counterparty liquidity still available for `asset`, `None` when it is not tracked.
+	pub fn available_liquidity(&self, asset: &AssetId) -> Option<Amount> {
+		self.liquidity.get(asset).copied()
+	}
+
+	/// Adds counterparty liquidity for a tracked asset, e.g. after a top-up of the bridge account.
+	pub fn add_liquidity(&mut self, asset: &AssetId, amount: Amount) {
+		let Some(available) = self.liquidity.get_mut(asset) else {
+			return;
+		};
+		match (&self.planner, self.assets.get(asset)) {
+			(Some(planner), Some(config)) => planner.lock().top_up(
+				planner.counterparty,
+				&config.counterparty_asset,
+				amount,
+				available,
+			),
+			_ => **available = available.saturating_add(*amount),
+		}
+	}
+
+	/// Liquidity for `asset` new locks may use, including the credit the liquidity planner lends
+	/// against opposite flows. `None` when liquidity is not tracked.
+	pub fn usable_liquidity(&self, asset: &AssetId) -> Option<Amount> {
+		let available = self.available_liquidity(asset)?;
+		let credit = match (&self.planner, self.assets.get(asset)) {
+			(Some(planner), Some(config)) => {
+				planner.lock().credit(planner.counterparty, &config.counterparty_asset)
+			}
+			_ => Amount(0),
+		};
+		Some(Amount(available.saturating_add(*credit)))
+	}
+
+	/// Pre-images the initiator contract accepts, as it reports them or as configured.
+	fn initiator_secret_constraints(&self) -> SecretConstraints {
+		self.initiator_contract
+			.secret_constraints()
+			.unwrap_or(self.initiator_chain.secret_constraints)
+	}
+
+	/// Pre-images the counterparty contract accepts, as it reports them or as configured.
+	fn counterparty_secret_constraints(&self) -> SecretConstraints {
+		self.counterparty_contract
+			.secret_constraints()
+			.unwrap_or(self.counterparty_chain.secret_constraints)
+	}
+
+	/// Derives the counterparty hash lock. Chains sharing a hash lock scheme reuse the initiator
+	/// hash lock, otherwise the initiator has to commit to the counterparty digest up front.
+	pub fn counterparty_hash_lock(
+		&self,
+		details: &BridgeTransferDetails<BFrom::Address, BFrom::Hash>,
+	) -> Result<HashLock<BTo::Hash>, ActiveSwapMapError>
+	where
+		BTo::Hash: TryConvert<BFrom::Hash> + TryConvert<Vec<u8>>,
+	{
+		let initiator = self.initiator_chain.hash_lock_scheme.name();
+		let counterparty = self.counterparty_chain.hash_lock_scheme.name();
+		match &details.counterparty_hash_lock {
+			None if initiator == counterparty => Ok(HashLock(
+				<BTo::Hash as TryConvert<_>>::try_convert(details.hash_lock.0.clone())
+					.map_err(TransferField::HashLock.conversion())?,
+			)),
+			None => {
+				Err(ActiveSwapMapError::MissingCounterpartyHashLock { initiator, counterparty })
+			}
+			Some(hash_lock)
+				if initiator == counterparty && hash_lock.0 != details.hash_lock.0.as_ref() =>
+			{
+				Err(ActiveSwapMapError::InconsistentHashLocks)
+			}
+			Some(hash_lock) => <BTo::Hash as TryConvert<_>>::try_convert(hash_lock.0.clone())
+				.map(HashLock)
+				.map_err(|_| ActiveSwapMapError::InconsistentHashLocks),
+		}
+	}
+
+	/// Derives the lock that has to be placed on the counterparty chain for an initiated transfer.
+	/// Fails when the transfer cannot be represented on the counterparty chain.
+	pub fn lock_details(
+		&self,
+		details: &BridgeTransferDetails<BFrom::Address, BFrom::Hash>,
+	) -> Result<LockDetails<BTo::Address, BTo::Hash>, ActiveSwapMapError>
+	where
+		BTo::Hash: TryConvert<BFrom::Hash> + TryConvert<Vec<u8>>,
+	{
+		if let Some(codec) = &self.counterparty_chain.address_codec {
+			codec
+				.validate(&details.recipient_address.0)
+				.map_err(ActiveSwapMapError::InvalidRecipient)?;
+		}
+		// A secret revealed on the counterparty chain has to be accepted on the initiator chain
+		if !self.counterparty_secret_constraints().is_within(&self.initiator_secret_constraints()) {
+			return Err(ActiveSwapMapError::IncompatibleSecretConstraints);
+		}
+		let bridge_transfer_id = BridgeTransferId(
+			TryConvert::try_convert(details.bridge_transfer_id.0.clone())
+				.map_err(TransferField::BridgeTransferId.conversion())?,
+		);
+		let recipient_address = RecipientAddress(
+			TryConvert::try_convert(details.recipient_address.0.clone())
+				.map_err(TransferField::RecipientAddress.conversion())?,
+		);
+		let hash_lock = self.counterparty_hash_lock(details)?;
+		let time_lock = self.time_lock_policy.counterparty_time_lock(
+			&details.time_lock,
+			&self.initiator_chain,
+			&self.counterparty_chain,
+		)?;
+
+		let (asset, _, amount) = self.locked_amount(&details.asset, details.amount)?;
+
+		Ok(LockDetails {
+			bridge_transfer_id,
+			initiator_address: InitiatorAddress(From::from(details.initiator_address.0.clone())),
+			recipient_address,
+			hash_lock,
+			time_lock,
+			asset: asset.counterparty_asset.clone(),
+			amount,
+		})
+	}
+
+	/// Checks `amount` of `asset` against the limits and liquidity of the map. Returns the asset
+	/// settings, the fee and the amount to lock on the counterparty chain.
+	fn locked_amount(
+		&self,
+		asset_id: &AssetId,
+		amount: Amount,
+	) -> Result<(&AssetConfig, Amount, Amount), ActiveSwapMapError> {
+		let asset = self
+			.assets
+			.get(asset_id)
+			.ok_or_else(|| ActiveSwapMapError::UnmappedAsset(asset_id.clone()))?;
+
+		if amount < asset.limits.min {
+			return Err(ActiveSwapMapError::AmountBelowMinimum { amount, min: asset.limits.min });
+		}
+		if amount > asset.limits.max {
+			return Err(ActiveSwapMapError::AmountAboveMaximum { amount, max: asset.limits.max });
+		}
+
+		let fee = asset.fee.fee_for(amount).unwrap_or(Amount(u128::MAX));
+		if fee >= amount {
+			return Err(ActiveSwapMapError::FeeExceedsAmount { amount, fee });
+		}
+		let locked = asset.amount_scaling.convert(Amount(*amount - *fee))?;
+
+		if let Some(available) = self.usable_liquidity(asset_id) {
+			if locked > available {
+				return Err(ActiveSwapMapError::InsufficientLiquidity {
+					asset: asset_id.clone(),
+					required: locked,
+					available,
+				});
+			}
+		}
+
+		Ok((asset, fee, locked))
+	}
+
+	/// Quotes the terms a transfer of `amount` of `asset` to `recipient` would be locked on. The
+	/// quote may be accepted within `QuoteConfig::validity`, see `accept_quote`.
+	pub fn quote(
+		&mut self,
+		asset: &AssetId,
+		amount: Amount,
+		recipient: &[u8],
+	) -> Result<Quote, ActiveSwapMapError> {
+		self.expire_quotes();
+		if self.paused {
+			return Err(ActiveSwapMapError::Paused);
+		}
+		if let Some(codec) = &self.counterparty_chain.address_codec {
+			codec.validate(recipient).map_err(ActiveSwapMapError::InvalidRecipient)?;
+		}
+		<BTo::Address as TryConvert<Vec<u8>>>::try_convert(recipient.to_vec())
+			.map_err(TransferField::RecipientAddress.conversion())?;
+		let (config, fee, locked_amount) = self.locked_amount(asset, amount)?;
+
+		let quote = Quote {
+			id: QuoteId(0),
+			asset: asset.clone(),
+			amount,
+			fee,
+			counterparty_asset: config.counterparty_asset.clone(),
+			locked_amount,
+			recipient: recipient.to_vec(),
+			time_lock: TimeLockRange {
+				min: self.time_lock_policy.safety_margin
+					+ self.quote_config.min_counterparty_time_lock,
+				max: self.quote_config.max_time_lock,
+			},
+			expires_at: Instant::now() + self.quote_config.validity,
+		};
+		let quote = Quote { id: self.quotes.next_id(), ..quote };
+		tracing::trace!("Quoted {:?}", quote);
+		self.quotes.insert(quote.clone());
+		Ok(quote)
+	}
+
+	/// Accepts a quote, reserving its liquidity for `QuoteConfig::reservation`. The transfer
+	/// initiated under `hash_lock` takes the reservation, and is locked if it matches the quote
+	/// while the direction is not paused. Other transfers are admitted as if they were not quoted.
+	pub fn accept_quote(
+		&mut self,
+		id: QuoteId,
+		hash_lock: &[u8],
+	) -> Result<Reservation, ActiveSwapMapError> {
+		self.expire_quotes();
+		// The quote stays open until every check passed, so a failed acceptance can be retried
+		let quote = self.quotes.get(id).ok_or(ActiveSwapMapError::UnknownQuote)?;
+		if self.quotes.reservation(hash_lock).is_some() {
+			return Err(ActiveSwapMapError::HashLockReserved);
+		}
+		if let Some(conflicting_transfer) =
+			self.lock_hash_locks().conflicting_transfer(&[hash_lock])
+		{
+			return Err(ActiveSwapMapError::HashLockReused { conflicting_transfer, held: false });
+		}
+		// Liquidity may have been taken since the quote was given
+		if let Some(available) = self.usable_liquidity(&quote.asset) {
+			if quote.locked_amount > available {
+				return Err(ActiveSwapMapError::InsufficientLiquidity {
+					asset: quote.asset.clone(),
+					required: quote.locked_amount,
+					available,
+				});
+			}
+		}
+
+		let quote = self.quotes.take(id).expect("checked above");
+		reserve_liquidity(
+			&mut self.liquidity,
+			&self.planner,
+			&quote.asset,
+			&quote.counterparty_asset,
+			quote.locked_amount,
+		);
+		let reservation =
+			Reservation { quote, expires_at: Instant::now() + self.quote_config.reservation };
+		tracing::trace!("Reserved {:?} for hash lock {:?}", reservation, hash_lock);
+		self.quotes.reserve(hash_lock, reservation.clone());
+		self.waker.wake();
+		Ok(reservation)
+	}
+
+	/// Reservation of an accepted quote under `hash_lock`, until its transfer is initiated.
+	pub fn reservation(&self, hash_lock: &[u8]) -> Option<&Reservation> {
+		self.quotes.reservation(hash_lock)
+	}
+
+	fn expire_quotes(&mut self) {
+		expire_quotes(&mut self.quotes, &mut self.liquidity, &self.planner);
+	}
+
+	/// Gives the liquidity reserved for a transfer back ahead of its admission, where it is taken
+	/// again if the transfer matches the quote.
+	fn claim_reservation(&mut self, details: &BridgeTransferDetails<BFrom::Address, BFrom::Hash>) {
+		self.expire_quotes();
+		let Some(reservation) = self.quotes.take_reservation(details.hash_lock.0.as_ref()) else {
+			return;
+		};
+		release_reservation(&mut self.liquidity, &self.planner, &reservation);
+		if !reservation.is_matched_by(
+			&details.asset,
+			details.amount,
+			&details.recipient_address.0,
+			&details.time_lock,
+			&self.initiator_chain,
+		) {
+			tracing::warn!(
+				"Bridge transfer {:?} does not match its quote {:?}, admitted as unquoted",
+				details.bridge_transfer_id,
+				reservation.quote
+			);
+		}
 	}
 
 	pub fn start_bridge_transfer(
 		&mut self,
 		details: BridgeTransferDetails<BFrom::Address, BFrom::Hash>,
-	) where
-		BTo::Hash: From<BFrom::Hash>,
+	) -> Result<(), ActiveSwapMapError>
+	where
+		BTo::Hash: TryConvert<BFrom::Hash> + TryConvert<Vec<u8>>,
+	{
+		self.start(details, None)
+	}
+
+	/// Starts the next leg of a multi-hop swap. It shares the hash lock of the previous leg, given
+	/// by the raw initiator id of that leg, which is therefore not reported as reused.
+	pub fn start_next_hop(
+		&mut self,
+		details: BridgeTransferDetails<BFrom::Address, BFrom::Hash>,
+		previous_hop: &[u8],
+	) -> Result<(), ActiveSwapMapError>
+	where
+		BTo::Hash: TryConvert<BFrom::Hash> + TryConvert<Vec<u8>>,
+	{
+		self.start(details, Some(previous_hop))
+	}
+
+	fn start(
+		&mut self,
+		details: BridgeTransferDetails<BFrom::Address, BFrom::Hash>,
+		previous_hop: Option<&[u8]>,
+	) -> Result<(), ActiveSwapMapError>
+	where
+		BTo::Hash: TryConvert<BFrom::Hash> + TryConvert<Vec<u8>>,
 	{
 		assert!(!self.swaps.contains_key(&details.bridge_transfer_id));
 
-		let counterparty_contract = self.counterparty_contract.clone();
+		// Relative time locks count from initiation, not from when the transfer is locked
+		let time_lock = match &self.initiator_chain.clock {
+			Some(clock) => details.time_lock.resolve(&clock.now()),
+			None => details.time_lock.clone(),
+		};
+		let details = BridgeTransferDetails { time_lock, ..details };
+
+		self.claim_reservation(&details);
+		if self.paused {
+			return Err(ActiveSwapMapError::Paused);
+		}
+		let lock_details = self.lock_details(&details)?;
+
+		let hash_locks = hash_locks_of(&details, &lock_details);
+		let mut index = self.lock_hash_locks();
+		let conflicting_transfer = hash_locks.iter().find_map(|hash_lock| {
+			index.conflicting_transfer(&[hash_lock]).filter(|conflicting_transfer| {
+				Some(conflicting_transfer.as_slice()) != previous_hop
+			})
+		});
+		if let Some(conflicting_transfer) = conflicting_transfer {
+			let held = index.config.policy == HashLockReusePolicy::Hold;
+			drop(index);
+			if held {
+				self.held.insert(details.bridge_transfer_id.clone(), details);
+			}
+			return Err(ActiveSwapMapError::HashLockReused { conflicting_transfer, held });
+		}
+		index.insert(&hash_locks, details.bridge_transfer_id.0.as_ref());
+		drop(index);
+
+		self.start_locking(details, lock_details);
+		Ok(())
+	}
+
+	fn start_locking(
+		&mut self,
+		details: BridgeTransferDetails<BFrom::Address, BFrom::Hash>,
+		lock_details: LockDetails<BTo::Address, BTo::Hash>,
+	) {
+		reserve_liquidity(
+			&mut self.liquidity,
+			&self.planner,
+			&details.asset,
+			&lock_details.asset,
+			lock_details.amount,
+		);
+
 		let bridge_transfer_id = details.bridge_transfer_id.clone();
+		let initiator_time_lock = details.time_lock.clone();
 
 		tracing::trace!("Starting active swap for bridge transfer {:?}", bridge_transfer_id);
 
 		self.swaps.insert(
 			bridge_transfer_id,
 			ActiveSwap {
-				details: details.clone(),
-				state: ActiveSwapState::LockingTokens(
-					call_lock_bridge_transfer_assets::<BFrom, BTo>(counterparty_contract, details)
-						.boxed()
-						.timeout(Delay::new(self.config.contract_call_timeout)),
+				details,
+				lock_details: lock_details.clone(),
+				counterparty_bridge_transfer_id: None,
+				counterparty_expiry: None,
+				refunded: false,
+				state: ActiveSwapState::QueuedLocking(
+					enqueue(
+						&self.counterparty_calls,
+						CallKind::Lock,
+						&self.initiator_chain,
+						&initiator_time_lock,
+					),
 					0,
 				),
+				initiator_time_lock,
 			},
 		);
 
@@ -209,28 +1062,90 @@ where
 	pub fn complete_bridge_transfer(
 		&mut self,
 		details: CounterpartyCompletedDetails<BTo::Address, BTo::Hash>,
-	) -> Result<(), ActiveSwapMapError>
-	where
-		BFrom::Hash: From<BTo::Hash>,
-	{
-		let active_swap = self
-			.swaps
-			.get_mut(&convert_bridge_transfer_id(details.bridge_transfer_id.clone()))
+	) -> Result<(), ActiveSwapMapError> {
+		let bridge_transfer_id = self
+			.swap_for_counterparty_id(&details.bridge_transfer_id)
+			.map(|(bridge_transfer_id, _)| bridge_transfer_id.clone())
 			.ok_or(ActiveSwapMapError::NonExistingSwap)?;
+		let initiator_constraints = self.initiator_secret_constraints();
+		let active_swap =
+			self.swaps.get_mut(&bridge_transfer_id).ok_or(ActiveSwapMapError::NonExistingSwap)?;
 
-		debug_assert!(matches!(active_swap.state, ActiveSwapState::WaitingForUnlockedEvent));
+		// The secret is checked locally against both locks, so no gas is spent on a completion
+		// that cannot succeed
+		let unlocks_initiator = self
+			.initiator_chain
+			.hash_lock_scheme
+			.verify(&details.secret, active_swap.details.hash_lock.0.as_ref());
+		let unlocks_counterparty = self
+			.counterparty_chain
+			.hash_lock_scheme
+			.verify(&details.secret, active_swap.lock_details.hash_lock.0.as_ref());
+		if !(unlocks_initiator && unlocks_counterparty) {
+			return Err(ActiveSwapMapError::InvalidHashLockPreImage);
+		}
+		initiator_constraints
+			.check(&details.secret)
+			.map_err(ActiveSwapMapError::SecretNotReplayable)?;
 
-		let initiator_contract = self.initiator_contract.clone();
+		// A claim the bridge relayed already queued the completion
+		if matches!(
+			active_swap.state,
+			ActiveSwapState::QueuedCompleting(..)
+				| ActiveSwapState::CompletingBridging(..)
+				| ActiveSwapState::CompletingBridgingError(..)
+				| ActiveSwapState::Completed
+		) {
+			return Ok(());
+		}
+
+		// The lock is claimed before the lock call returned, so it went through
+		if active_swap.counterparty_bridge_transfer_id.is_none() {
+			match &active_swap.state {
+				ActiveSwapState::QueuedLocking(ticket, _) => {
+					self.counterparty_calls.lock().expect("call limiter poisoned").cancel(*ticket)
+				}
+				ActiveSwapState::LockingTokens(..) => {
+					self.counterparty_calls.lock().expect("call limiter poisoned").release()
+				}
+				_ => {}
+			}
+			self.counterparty_ids
+				.insert(details.bridge_transfer_id.clone(), bridge_transfer_id.clone());
+			active_swap.counterparty_bridge_transfer_id = Some(details.bridge_transfer_id.clone());
+			if let Some(planner) = &self.planner {
+				planner.lock().expect_incoming(
+					planner.initiator,
+					&active_swap.details.asset,
+					active_swap.details.amount,
+				);
+			}
+		}
+		debug_assert!(matches!(
+			active_swap.state,
+			ActiveSwapState::LockingTokens(..)
+				| ActiveSwapState::LockingTokensError(..)
+				| ActiveSwapState::QueuedLocking(..)
+				| ActiveSwapState::PausedLocking(..)
+				| ActiveSwapState::WaitingForUnlockedEvent
+				| ActiveSwapState::AbortingLock(..)
+				| ActiveSwapState::AbortingLockError(..)
+				| ActiveSwapState::RelayingSecret(..)
+				| ActiveSwapState::RelayingSecretError(..)
+		));
 
 		tracing::trace!(
 			"Completing active swap for bridge transfer {:?}",
 			details.bridge_transfer_id
 		);
 
-		active_swap.state = ActiveSwapState::CompletingBridging(
-			call_complete_bridge_transfer::<BFrom, BTo>(initiator_contract, details.clone())
-				.boxed()
-				.timeout(Delay::new(self.config.contract_call_timeout)),
+		active_swap.state = ActiveSwapState::QueuedCompleting(
+			enqueue(
+				&self.initiator_calls,
+				CallKind::Complete,
+				&self.initiator_chain,
+				&active_swap.initiator_time_lock,
+			),
 			details.clone(),
 			0,
 		);
@@ -239,18 +1154,319 @@ where
 
 		Ok(())
 	}
+
+	/// Gives back the counterparty lock of a swap whose recipient has not claimed it yet, e.g. when
+	/// a later leg of a multi-hop swap failed. The call bypasses the call queue, as it returns
+	/// liquidity rather than committing it.
+	pub fn abort_bridge_transfer(
+		&mut self,
+		key: &BridgeTransferId<BFrom::Hash>,
+	) -> Result<(), ActiveSwapMapError> {
+		let active_swap = self.swaps.get_mut(key).ok_or(ActiveSwapMapError::NonExistingSwap)?;
+		let (ActiveSwapState::WaitingForUnlockedEvent, Some(counterparty_id)) =
+			(&active_swap.state, &active_swap.counterparty_bridge_transfer_id)
+		else {
+			return Err(ActiveSwapMapError::NotAbortable);
+		};
+
+		tracing::trace!(
+			"Aborting counterparty lock {:?} of bridge transfer {:?}",
+			counterparty_id,
+			key
+		);
+
+		active_swap.state = ActiveSwapState::AbortingLock(
+			call_abort_bridge_transfer::<BTo>(
+				self.counterparty_contract.clone(),
+				counterparty_id.clone(),
+			)
+			.boxed()
+			.timeout(Delay::new(self.config.contract_call_timeout)),
+			0,
+		);
+
+		self.waker.wake();
+
+		Ok(())
+	}
+
+	/// Follows the refund of an initiated transfer on the initiator chain. Locks not placed yet are
+	/// dropped and placed locks given back, as the initiator funds no longer back them. Fails with
+	/// `ClaimedBeforeRefund` when the counterparty lock was already claimed.
+	pub fn refund_bridge_transfer(
+		&mut self,
+		key: &BridgeTransferId<BFrom::Hash>,
+	) -> Result<(), ActiveSwapMapError> {
+		if self.held.remove(key).is_some() {
+			return Ok(());
+		}
+		let active_swap = self.swaps.get_mut(key).ok_or(ActiveSwapMapError::NonExistingSwap)?;
+
+		tracing::trace!("Bridge transfer {:?} refunded in state {:?}", key, active_swap.state);
+
+		use ActiveSwapState::*;
+		match &active_swap.state {
+			QueuedLocking(..) | PausedLocking(..) | LockingTokensError(..) => {
+				if let QueuedLocking(ticket, _) = &active_swap.state {
+					self.counterparty_calls.lock().expect("call limiter poisoned").cancel(*ticket);
+				}
+				active_swap.state = Aborted;
+				release_liquidity(
+					&mut self.liquidity,
+					&self.planner,
+					&active_swap.details.asset,
+					&active_swap.lock_details.asset,
+					active_swap.lock_details.amount,
+				);
+			}
+			// The outcome of the lock call decides
+			LockingTokens(..) => active_swap.refunded = true,
+			WaitingForUnlockedEvent | RelayingSecret(..) | RelayingSecretError(..) => {
+				let Some(counterparty_id) = &active_swap.counterparty_bridge_transfer_id else {
+					unreachable!("only placed locks are waited on");
+				};
+				if matches!(active_swap.state, RelayingSecret(..) | RelayingSecretError(..)) {
+					self.pending_events.push_back(ActiveSwapEvent::BridgeAssetsRelayingCancelled(
+						counterparty_id.clone(),
+					));
+				}
+				active_swap.state = AbortingLock(
+					call_abort_bridge_transfer::<BTo>(
+						self.counterparty_contract.clone(),
+						counterparty_id.clone(),
+					)
+					.boxed()
+					.timeout(Delay::new(self.config.contract_call_timeout)),
+					0,
+				);
+			}
+			QueuedCompleting(..) | CompletingBridging(..) | CompletingBridgingError(..) => {
+				match &active_swap.state {
+					QueuedCompleting(ticket, ..) => {
+						self.initiator_calls.lock().expect("call limiter poisoned").cancel(*ticket)
+					}
+					CompletingBridging(..) => {
+						self.initiator_calls.lock().expect("call limiter poisoned").release()
+					}
+					_ => {}
+				}
+				active_swap.state = Aborted;
+				// The lock was claimed, the initiator funds are gone
+				if let Some(planner) = &self.planner {
+					let mut planner_lock = planner.lock();
+					planner_lock.spend(
+						planner.counterparty,
+						&active_swap.lock_details.asset,
+						active_swap.lock_details.amount,
+					);
+					planner_lock.cancel_incoming(
+						planner.initiator,
+						&active_swap.details.asset,
+						active_swap.details.amount,
+					);
+				}
+				self.waker.wake();
+				return Err(ActiveSwapMapError::ClaimedBeforeRefund);
+			}
+			AbortingLock(..) | AbortingLockError(..) | Completed | Aborted => {}
+		}
+
+		self.waker.wake();
+
+		Ok(())
+	}
+
+	/// Claims the counterparty lock with a secret its recipient handed to the bridge, e.g. because
+	/// the recipient holds no gas on the counterparty chain. The initiator funds are claimed once
+	/// the counterparty claim succeeds. The call bypasses the call queue, like aborting a lock.
+	pub fn relay_secret(
+		&mut self,
+		secret: HashLockPreImage,
+	) -> Result<BridgeTransferId<BFrom::Hash>, ActiveSwapMapError> {
+		let hash_lock = self.counterparty_chain.hash_lock_scheme.digest(&secret);
+		let counterparty_constraints = self.counterparty_secret_constraints();
+		let initiator_constraints = self.initiator_secret_constraints();
+		let (bridge_transfer_id, active_swap) = self
+			.swaps
+			.iter_mut()
+			.find(|(_, swap)| swap.lock_details.hash_lock.0.as_ref() == hash_lock.as_slice())
+			.ok_or(ActiveSwapMapError::NonExistingSwap)?;
+
+		if !self
+			.initiator_chain
+			.hash_lock_scheme
+			.verify(&secret, active_swap.details.hash_lock.0.as_ref())
+		{
+			return Err(ActiveSwapMapError::InvalidHashLockPreImage);
+		}
+		counterparty_constraints.check(&secret).map_err(ActiveSwapMapError::SecretRejected)?;
+		initiator_constraints.check(&secret).map_err(ActiveSwapMapError::SecretNotReplayable)?;
+
+		let (ActiveSwapState::WaitingForUnlockedEvent, Some(counterparty_id)) =
+			(&active_swap.state, &active_swap.counterparty_bridge_transfer_id)
+		else {
+			return Err(ActiveSwapMapError::NotRelayable);
+		};
+
+		tracing::trace!(
+			"Relaying secret to counterparty lock {:?} of bridge transfer {:?}",
+			counterparty_id,
+			bridge_transfer_id
+		);
+
+		let lock_details = active_swap.lock_details.clone();
+		let completed_details = CounterpartyCompletedDetails {
+			bridge_transfer_id: counterparty_id.clone(),
+			initiator_address: lock_details.initiator_address,
+			recipient_address: lock_details.recipient_address,
+			hash_lock: lock_details.hash_lock,
+			secret,
+			asset: lock_details.asset,
+			amount: lock_details.amount,
+		};
+		active_swap.state = ActiveSwapState::RelayingSecret(
+			call_relay_secret::<BTo>(
+				self.counterparty_contract.clone(),
+				counterparty_id.clone(),
+				completed_details.secret.clone(),
+			)
+			.boxed()
+			.timeout(Delay::new(self.relay_config.contract_call_timeout)),
+			completed_details,
+			Instant::now() + self.relay_config.timeout,
+			0,
+		);
+
+		self.waker.wake();
+
+		Ok(bridge_transfer_id.clone())
+	}
+}
+
+/// Queues a contract call of a swap, which is more urgent the sooner its initiator lock expires.
+fn enqueue(
+	calls: &SharedCallLimiter,
+	kind: CallKind,
+	initiator_chain: &ChainConfig,
+	initiator_time_lock: &TimeLock,
+) -> CallTicket {
+	let deadline = initiator_chain.clock.as_deref().map(|clock| {
+		Instant::now() + initiator_time_lock.time_remaining(&clock.now(), clock.block_time())
+	});
+	calls.lock().expect("call limiter poisoned").enqueue(kind, deadline)
+}
+
+/// Takes `amount` of `counterparty_asset` from the liquidity of the initiator `asset`, or from the
+/// credit of the liquidity planner where the liquidity falls short.
+fn reserve_liquidity(
+	liquidity: &mut HashMap<AssetId, Amount>,
+	planner: &Option<PlannerChains>,
+	asset: &AssetId,
+	counterparty_asset: &AssetId,
+	amount: Amount,
+) {
+	let available = liquidity.get_mut(asset);
+	match planner {
+		Some(planner) => {
+			planner.lock().lock(planner.counterparty, counterparty_asset, amount, available)
+		}
+		None => {
+			if let Some(available) = available {
+				**available -= *amount;
+			}
+		}
+	}
+}
+
+/// Gives an amount that was not locked or was aborted back, see `reserve_liquidity`.
+fn release_liquidity(
+	liquidity: &mut HashMap<AssetId, Amount>,
+	planner: &Option<PlannerChains>,
+	asset: &AssetId,
+	counterparty_asset: &AssetId,
+	amount: Amount,
+) {
+	let available = liquidity.get_mut(asset);
+	match planner {
+		Some(planner) => {
+			planner.lock().unlock(planner.counterparty, counterparty_asset, amount, available)
+		}
+		None => {
+			if let Some(available) = available {
+				**available += *amount;
+			}
+		}
+	}
+}
+
+/// Drops expired quotes and releases the liquidity of expired reservations.
+fn expire_quotes(
+	quotes: &mut Quotes,
+	liquidity: &mut HashMap<AssetId, Amount>,
+	planner: &Option<PlannerChains>,
+) {
+	for reservation in quotes.expire(Instant::now()) {
+		tracing::trace!("Reservation of quote {:?} expired", reservation.quote.id);
+		release_reservation(liquidity, planner, &reservation);
+	}
+}
+
+fn release_reservation(
+	liquidity: &mut HashMap<AssetId, Amount>,
+	planner: &Option<PlannerChains>,
+	reservation: &Reservation,
+) {
+	let quote = &reservation.quote;
+	release_liquidity(
+		liquidity,
+		planner,
+		&quote.asset,
+		&quote.counterparty_asset,
+		quote.locked_amount,
+	);
+}
+
+/// Raw hash locks a swap commits to on both chains.
+fn hash_locks_of<'a, A, H: AsRef<[u8]>, CA, CH: AsRef<[u8]>>(
+	details: &'a BridgeTransferDetails<A, H>,
+	lock_details: &'a LockDetails<CA, CH>,
+) -> Vec<&'a [u8]> {
+	let mut hash_locks = vec![details.hash_lock.0.as_ref(), lock_details.hash_lock.0.as_ref()];
+	hash_locks.dedup();
+	hash_locks
 }
 
 #[derive(Debug)]
-pub enum ActiveSwapEvent<H> {
+pub enum ActiveSwapEvent<H, C> {
 	BridgeAssetsLocked(BridgeTransferId<H>),
 	BridgeAssetsLockingError(LockBridgeTransferAssetsError),
-	BridgeAssetsRetryLocking(BridgeTransferId<H>),
+	/// Carries the counterparty id of the lock that is retried.
+	BridgeAssetsRetryLocking(BridgeTransferId<C>),
 	BridgeAssetsCompleted(BridgeTransferId<H>),
 	BridgeAssetsCompletingError(BridgeTransferId<H>, CompleteBridgeTransferError),
 	BridgeAssetsRetryCompleting(BridgeTransferId<H>),
-	BridgeAssetsLockingAbortedTooManyAttempts(BridgeTransferId<H>),
+	/// Carries the counterparty id of the lock that was given up on.
+	BridgeAssetsLockingAbortedTooManyAttempts(BridgeTransferId<C>),
 	BridgeAssetsCompletingAbortedTooManyAttempts(BridgeTransferId<H>),
+	/// Carries the counterparty id of the lock that was given back.
+	BridgeAssetsLockAborted(BridgeTransferId<C>),
+	BridgeAssetsAbortingError(BridgeTransferId<C>, AbortBridgeTransferError),
+	/// The lock is left in place, waiting for the recipient as before.
+	BridgeAssetsAbortingAbortedTooManyAttempts(BridgeTransferId<C>),
+	/// Carries the counterparty id of the lock claimed with a relayed secret.
+	BridgeAssetsSecretRelayed(BridgeTransferId<C>),
+	BridgeAssetsRelayingError(BridgeTransferId<C>, RelaySecretError),
+	/// The lock is left in place, the recipient may still claim it or relay the secret again.
+	BridgeAssetsRelayingAborted(BridgeTransferId<C>),
+	/// The initiator refunded while the lock call was in flight and the call failed, so nothing is
+	/// locked. Carries the counterparty id of the lock.
+	BridgeAssetsLockingCancelled(BridgeTransferId<C>),
+	/// The initiator refunded before the lock was claimed with a relayed secret, so the claim is
+	/// dropped and the lock given back.
+	BridgeAssetsRelayingCancelled(BridgeTransferId<C>),
+	/// The initiator lock no longer covers the safety margin when the lock call is due, so nothing
+	/// is locked. Carries the counterparty id of the lock.
+	BridgeAssetsLockingExpired(BridgeTransferId<C>, TimeLockError),
 }
 
 fn catch_timeout_error<T, E: HasTimeoutError>(
@@ -268,33 +1484,122 @@ where
 	BFrom: BlockchainService + 'static,
 	BTo: BlockchainService + 'static,
 
-	BFrom::Hash: From<BTo::Hash>,
-	BTo::Hash: From<BFrom::Hash>,
-
 	Vec<u8>: From<BFrom::Address>,
 {
-	type Item = ActiveSwapEvent<BFrom::Hash>;
+	type Item = ActiveSwapEvent<BFrom::Hash, BTo::Hash>;
 
 	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
 		let this = self.get_mut();
 
 		tracing::trace!("Polling active swap map");
 
+		if let Some(event) = this.pending_events.pop_front() {
+			return Poll::Ready(Some(event));
+		}
+
+		expire_quotes(&mut this.quotes, &mut this.liquidity, &this.planner);
+		this.reservation_timer = this
+			.quotes
+			.next_expiry()
+			.map(|expiry| Delay::new(expiry.saturating_duration_since(Instant::now())));
+		if let Some(timer) = &mut this.reservation_timer {
+			let _ = timer.poll_unpin(cx);
+		}
+		this.expiry_timer = this.counterparty_chain.clock.as_ref().and_then(|clock| {
+			let now = clock.now();
+			this.swaps
+				.values()
+				.filter(|swap| matches!(swap.state, ActiveSwapState::WaitingForUnlockedEvent))
+				.filter_map(|swap| swap.counterparty_expiry.as_ref())
+				.map(|expiry| expiry.time_remaining(&now, clock.block_time()))
+				.min()
+				.map(Delay::new)
+		});
+		if let Some(timer) = &mut this.expiry_timer {
+			let _ = timer.poll_unpin(cx);
+		}
+
 		// remove all swaps that are completed or aborted
-		this.swaps.retain(|_, swap| {
-			!matches!(swap.state, ActiveSwapState::Completed | ActiveSwapState::Aborted)
+		let counterparty_ids = &mut this.counterparty_ids;
+		let mut hash_locks = this.hash_locks.lock().expect("hash lock index poisoned");
+		this.swaps.retain(|bridge_transfer_id, swap| {
+			let finished =
+				matches!(swap.state, ActiveSwapState::Completed | ActiveSwapState::Aborted);
+			if finished {
+				if let Some(counterparty_id) = &swap.counterparty_bridge_transfer_id {
+					counterparty_ids.remove(counterparty_id);
+				}
+				hash_locks.release(
+					&hash_locks_of(&swap.details, &swap.lock_details),
+					bridge_transfer_id.0.as_ref(),
+				);
+			}
+			!finished
 		});
+		drop(hash_locks);
 
-		for (bridge_transfer_id, ActiveSwap { details: bridge_transfer, state, .. }) in
-			this.swaps.iter_mut()
+		for (
+			bridge_transfer_id,
+			ActiveSwap {
+				details,
+				lock_details,
+				counterparty_bridge_transfer_id,
+				initiator_time_lock,
+				counterparty_expiry,
+				refunded,
+				state,
+			},
+		) in this.swaps.iter_mut()
 		{
 			use ActiveSwapState::*;
 			match state {
 				LockingTokens(future, attempts) => {
 					tracing::trace!("Polling locking_tokens {:?}", bridge_transfer_id);
-					match catch_timeout_error(future.poll_unpin(cx)) {
-						Poll::Ready(Ok(())) => {
+					let result = catch_timeout_error(future.poll_unpin(cx));
+					if result.is_ready() {
+						this.counterparty_calls.lock().expect("call limiter poisoned").release();
+					}
+					match result {
+						Poll::Ready(Ok(counterparty_id)) => {
 							*state = ActiveSwapState::WaitingForUnlockedEvent;
+							*counterparty_expiry = this
+								.counterparty_chain
+								.clock
+								.as_ref()
+								.map(|clock| lock_details.time_lock.resolve(&clock.now()));
+							if counterparty_id != lock_details.bridge_transfer_id {
+								tracing::trace!(
+									"Counterparty assigned id {:?} to bridge transfer {:?}",
+									counterparty_id,
+									bridge_transfer_id
+								);
+							}
+							this.counterparty_ids
+								.insert(counterparty_id.clone(), bridge_transfer_id.clone());
+							if let Some(planner) = &this.planner {
+								planner.lock().expect_incoming(
+									planner.initiator,
+									&details.asset,
+									details.amount,
+								);
+							}
+							if *refunded {
+								tracing::trace!(
+									"Bridge transfer {:?} was refunded while locking, aborting the lock",
+									bridge_transfer_id
+								);
+								*state = ActiveSwapState::AbortingLock(
+									call_abort_bridge_transfer::<BTo>(
+										this.counterparty_contract.clone(),
+										counterparty_id.clone(),
+									)
+									.boxed()
+									.timeout(Delay::new(this.config.contract_call_timeout)),
+									0,
+								);
+								cx.waker().wake_by_ref();
+							}
+							*counterparty_bridge_transfer_id = Some(counterparty_id);
 
 							return Poll::Ready(Some(ActiveSwapEvent::BridgeAssetsLocked(
 								bridge_transfer_id.clone(),
@@ -307,13 +1612,24 @@ where
 								error,
 								attempts
 							);
-							if *attempts >= this.config.error_attempts {
+							if *refunded || *attempts >= this.config.error_attempts {
 								*state = ActiveSwapState::Aborted;
-								return Poll::Ready(Some(
+								// Nothing got locked, so the reserved liquidity is available again
+								release_liquidity(
+									&mut this.liquidity,
+									&this.planner,
+									&details.asset,
+									&lock_details.asset,
+									lock_details.amount,
+								);
+								let counterparty_id = lock_details.bridge_transfer_id.clone();
+								return Poll::Ready(Some(if *refunded {
+									ActiveSwapEvent::BridgeAssetsLockingCancelled(counterparty_id)
+								} else {
 									ActiveSwapEvent::BridgeAssetsLockingAbortedTooManyAttempts(
-										bridge_transfer_id.clone(),
-									),
-								));
+										counterparty_id,
+									)
+								}));
 							}
 							// Locking tokens failed
 							// Transition to the next state
@@ -330,33 +1646,158 @@ where
 				}
 				LockingTokensError(delay, attempts) => {
 					// test if the delay has expired
-					// if it has, retry the lock
+					// if it has, queue the lock again, unless new locks are paused
 					if let Poll::Ready(()) = delay.poll_unpin(cx) {
+						if this.paused {
+							continue;
+						}
+						*state = ActiveSwapState::QueuedLocking(
+							enqueue(
+								&this.counterparty_calls,
+								CallKind::Lock,
+								&this.initiator_chain,
+								initiator_time_lock,
+							),
+							*attempts + 1,
+						);
+						cx.waker().wake_by_ref();
+					}
+				}
+				QueuedLocking(ticket, attempts) => {
+					let attempts = *attempts;
+					let mut calls = this.counterparty_calls.lock().expect("call limiter poisoned");
+					if this.paused {
+						// Out of the queue, so it does not hold up the calls queued behind it
+						calls.cancel(*ticket);
+						*state = ActiveSwapState::PausedLocking(attempts);
+						continue;
+					}
+					if calls.poll_acquire(*ticket, cx).is_pending() {
+						tracing::trace!(
+							"Lock for bridge transfer {:?} queued behind {} calls",
+							bridge_transfer_id,
+							calls.queue_depth()
+						);
+						continue;
+					}
+					drop(calls);
+					// Time passed while queued, paused or retrying, so the counterparty time lock
+					// is derived again from the initiator lock
+					match this.time_lock_policy.counterparty_time_lock(
+						initiator_time_lock,
+						&this.initiator_chain,
+						&this.counterparty_chain,
+					) {
+						Ok(time_lock) => lock_details.time_lock = time_lock,
+						Err(error) => {
+							tracing::trace!(
+								"Giving up lock for bridge transfer {:?}: {}",
+								bridge_transfer_id,
+								error
+							);
+							this.counterparty_calls
+								.lock()
+								.expect("call limiter poisoned")
+								.release();
+							*state = ActiveSwapState::Aborted;
+							release_liquidity(
+								&mut this.liquidity,
+								&this.planner,
+								&details.asset,
+								&lock_details.asset,
+								lock_details.amount,
+							);
+							return Poll::Ready(Some(ActiveSwapEvent::BridgeAssetsLockingExpired(
+								lock_details.bridge_transfer_id.clone(),
+								error,
+							)));
+						}
+					}
+					*state = ActiveSwapState::LockingTokens(
+						call_lock_bridge_transfer_assets::<BTo>(
+							this.counterparty_contract.clone(),
+							lock_details.clone(),
+						)
+						.boxed()
+						.timeout(Delay::new(this.config.contract_call_timeout)),
+						attempts,
+					);
+					cx.waker().wake_by_ref();
+					if attempts > 0 {
 						tracing::trace!(
 							"Retrying lock for bridge transfer {:?}",
 							bridge_transfer_id
 						);
-						*state = ActiveSwapState::LockingTokens(
-							call_lock_bridge_transfer_assets::<BFrom, BTo>(
-								this.counterparty_contract.clone(),
-								bridge_transfer.clone(),
-							)
-							.boxed()
-							.timeout(Delay::new(this.config.contract_call_timeout)),
-							*attempts + 1,
-						);
 						return Poll::Ready(Some(ActiveSwapEvent::BridgeAssetsRetryLocking(
-							bridge_transfer_id.clone(),
+							lock_details.bridge_transfer_id.clone(),
 						)));
 					}
 				}
+				PausedLocking(attempts) => {
+					if this.paused {
+						continue;
+					}
+					*state = ActiveSwapState::QueuedLocking(
+						enqueue(
+							&this.counterparty_calls,
+							CallKind::Lock,
+							&this.initiator_chain,
+							initiator_time_lock,
+						),
+						*attempts,
+					);
+					cx.waker().wake_by_ref();
+				}
 				WaitingForUnlockedEvent => {
-					continue;
+					// An expired lock can no longer be claimed, so it is taken back to release the
+					// swap
+					let (Some(expiry), Some(clock), Some(counterparty_id)) = (
+						&*counterparty_expiry,
+						&this.counterparty_chain.clock,
+						&*counterparty_bridge_transfer_id,
+					) else {
+						continue;
+					};
+					if !expiry.is_expired(&clock.now()) {
+						continue;
+					}
+					tracing::trace!(
+						"Counterparty lock {:?} of bridge transfer {:?} expired unclaimed, aborting it",
+						counterparty_id,
+						bridge_transfer_id
+					);
+					*state = ActiveSwapState::AbortingLock(
+						call_abort_bridge_transfer::<BTo>(
+							this.counterparty_contract.clone(),
+							counterparty_id.clone(),
+						)
+						.boxed()
+						.timeout(Delay::new(this.config.contract_call_timeout)),
+						0,
+					);
+					cx.waker().wake_by_ref();
 				}
-				CompletingBridging(future, details, attempts) => {
-					match catch_timeout_error(future.poll_unpin(cx)) {
+				CompletingBridging(future, completed_details, attempts) => {
+					let result = catch_timeout_error(future.poll_unpin(cx));
+					if result.is_ready() {
+						this.initiator_calls.lock().expect("call limiter poisoned").release();
+					}
+					match result {
 						Poll::Ready(Ok(())) => {
 							*state = ActiveSwapState::Completed;
+							if let Some(planner) = &this.planner {
+								let mut planner_lock = planner.lock();
+								planner_lock.spend(
+									planner.counterparty,
+									&lock_details.asset,
+									lock_details.amount,
+								);
+								planner_lock.receive_incoming(
+									planner.initiator,
+									&details.asset,
+									details.amount,
+								);
+							}
 
 							return Poll::Ready(Some(ActiveSwapEvent::BridgeAssetsCompleted(
 								bridge_transfer_id.clone(),
@@ -371,6 +1812,20 @@ where
 							);
 							if *attempts >= this.config.error_attempts {
 								*state = ActiveSwapState::Aborted;
+								// The lock was claimed, the initiator funds are left unclaimed
+								if let Some(planner) = &this.planner {
+									let mut planner_lock = planner.lock();
+									planner_lock.spend(
+										planner.counterparty,
+										&lock_details.asset,
+										lock_details.amount,
+									);
+									planner_lock.cancel_incoming(
+										planner.initiator,
+										&details.asset,
+										details.amount,
+									);
+								}
 								return Poll::Ready(Some(
 									ActiveSwapEvent::BridgeAssetsCompletingAbortedTooManyAttempts(
 										bridge_transfer_id.clone(),
@@ -382,7 +1837,7 @@ where
 							// Transition to the next state
 							*state = ActiveSwapState::CompletingBridgingError(
 								Delay::new(this.config.error_delay),
-								details.clone(),
+								completed_details.clone(),
 								*attempts + 1,
 							);
 
@@ -403,23 +1858,187 @@ where
 					);
 
 					// test if the delay has expired
-					// if it has, retry the lock
+					// if it has, queue the completion again
 					if let Poll::Ready(()) = delay.poll_unpin(cx) {
-						*state = ActiveSwapState::CompletingBridging(
-							call_complete_bridge_transfer::<BFrom, BTo>(
-								this.initiator_contract.clone(),
-								details.clone(),
-							)
-							.boxed()
-							.timeout(Delay::new(this.config.contract_call_timeout)),
+						*state = ActiveSwapState::QueuedCompleting(
+							enqueue(
+								&this.initiator_calls,
+								CallKind::Complete,
+								&this.initiator_chain,
+								initiator_time_lock,
+							),
 							details.clone(),
 							*attempts + 1,
 						);
+						cx.waker().wake_by_ref();
+					}
+				}
+				QueuedCompleting(ticket, details, attempts) => {
+					let attempts = *attempts;
+					let mut calls = this.initiator_calls.lock().expect("call limiter poisoned");
+					if calls.poll_acquire(*ticket, cx).is_pending() {
+						tracing::trace!(
+							"Completion of bridge transfer {:?} queued behind {} calls",
+							bridge_transfer_id,
+							calls.queue_depth()
+						);
+						continue;
+					}
+					drop(calls);
+					*state = ActiveSwapState::CompletingBridging(
+						call_complete_bridge_transfer::<BFrom>(
+							this.initiator_contract.clone(),
+							bridge_transfer_id.clone(),
+							details.secret.clone(),
+						)
+						.boxed()
+						.timeout(Delay::new(this.config.contract_call_timeout)),
+						details.clone(),
+						attempts,
+					);
+					cx.waker().wake_by_ref();
+					if attempts > 0 {
 						return Poll::Ready(Some(ActiveSwapEvent::BridgeAssetsRetryCompleting(
 							bridge_transfer_id.clone(),
 						)));
 					}
 				}
+				AbortingLock(future, attempts) => {
+					let Some(counterparty_id) = counterparty_bridge_transfer_id else {
+						unreachable!("only placed locks are aborted");
+					};
+					match catch_timeout_error(future.poll_unpin(cx)) {
+						Poll::Ready(Ok(())) => {
+							*state = ActiveSwapState::Aborted;
+							release_liquidity(
+								&mut this.liquidity,
+								&this.planner,
+								&details.asset,
+								&lock_details.asset,
+								lock_details.amount,
+							);
+							if let Some(planner) = &this.planner {
+								planner.lock().cancel_incoming(
+									planner.initiator,
+									&details.asset,
+									details.amount,
+								);
+							}
+							return Poll::Ready(Some(ActiveSwapEvent::BridgeAssetsLockAborted(
+								counterparty_id.clone(),
+							)));
+						}
+						Poll::Ready(Err(error)) => {
+							tracing::trace!(
+								"Aborting lock {:?} failed: {:?} attempts: {}",
+								counterparty_id,
+								error,
+								attempts
+							);
+							if *attempts >= this.config.error_attempts {
+								*state = ActiveSwapState::WaitingForUnlockedEvent;
+								return Poll::Ready(Some(
+									ActiveSwapEvent::BridgeAssetsAbortingAbortedTooManyAttempts(
+										counterparty_id.clone(),
+									),
+								));
+							}
+							*state = ActiveSwapState::AbortingLockError(
+								Delay::new(this.config.error_delay),
+								*attempts + 1,
+							);
+							return Poll::Ready(Some(ActiveSwapEvent::BridgeAssetsAbortingError(
+								counterparty_id.clone(),
+								error,
+							)));
+						}
+						Poll::Pending => {}
+					}
+				}
+				AbortingLockError(delay, attempts) => {
+					if let Poll::Ready(()) = delay.poll_unpin(cx) {
+						let Some(counterparty_id) = counterparty_bridge_transfer_id else {
+							unreachable!("only placed locks are aborted");
+						};
+						*state = ActiveSwapState::AbortingLock(
+							call_abort_bridge_transfer::<BTo>(
+								this.counterparty_contract.clone(),
+								counterparty_id.clone(),
+							)
+							.boxed()
+							.timeout(Delay::new(this.config.contract_call_timeout)),
+							*attempts,
+						);
+						cx.waker().wake_by_ref();
+					}
+				}
+				RelayingSecret(future, completed_details, deadline, attempts) => {
+					let counterparty_id = completed_details.bridge_transfer_id.clone();
+					match catch_timeout_error(future.poll_unpin(cx)) {
+						Poll::Ready(Ok(())) => {
+							// The claim revealed the secret, the initiator funds are claimed
+							// without waiting for the counterparty event
+							*state = ActiveSwapState::QueuedCompleting(
+								enqueue(
+									&this.initiator_calls,
+									CallKind::Complete,
+									&this.initiator_chain,
+									initiator_time_lock,
+								),
+								completed_details.clone(),
+								0,
+							);
+							cx.waker().wake_by_ref();
+							return Poll::Ready(Some(ActiveSwapEvent::BridgeAssetsSecretRelayed(
+								counterparty_id,
+							)));
+						}
+						Poll::Ready(Err(error)) => {
+							tracing::trace!(
+								"Relaying secret to lock {:?} failed: {:?} attempts: {}",
+								counterparty_id,
+								error,
+								attempts
+							);
+							if *attempts >= this.relay_config.error_attempts
+								|| Instant::now() + this.relay_config.error_delay >= *deadline
+							{
+								*state = ActiveSwapState::WaitingForUnlockedEvent;
+								return Poll::Ready(Some(
+									ActiveSwapEvent::BridgeAssetsRelayingAborted(counterparty_id),
+								));
+							}
+							*state = ActiveSwapState::RelayingSecretError(
+								Delay::new(this.relay_config.error_delay),
+								completed_details.clone(),
+								*deadline,
+								*attempts + 1,
+							);
+							return Poll::Ready(Some(ActiveSwapEvent::BridgeAssetsRelayingError(
+								counterparty_id,
+								error,
+							)));
+						}
+						Poll::Pending => {}
+					}
+				}
+				RelayingSecretError(delay, completed_details, deadline, attempts) => {
+					if let Poll::Ready(()) = delay.poll_unpin(cx) {
+						*state = ActiveSwapState::RelayingSecret(
+							call_relay_secret::<BTo>(
+								this.counterparty_contract.clone(),
+								completed_details.bridge_transfer_id.clone(),
+								completed_details.secret.clone(),
+							)
+							.boxed()
+							.timeout(Delay::new(this.relay_config.contract_call_timeout)),
+							completed_details.clone(),
+							*deadline,
+							*attempts,
+						);
+						cx.waker().wake_by_ref();
+					}
+				}
 				Completed => {
 					tracing::trace!(
 						"Bridge transfer {:?} completed, marked for cleanup",
@@ -462,42 +2081,36 @@ impl HasTimeoutError for LockBridgeTransferAssetsError {
 	}
 }
 
-async fn call_lock_bridge_transfer_assets<BFrom: BlockchainService, BTo: BlockchainService>(
+async fn call_lock_bridge_transfer_assets<BTo: BlockchainService>(
 	mut counterparty_contract: BTo::CounterpartyContract,
-	BridgeTransferDetails {
+	LockDetails {
 		bridge_transfer_id,
 		hash_lock,
 		time_lock,
 		recipient_address,
 		initiator_address,
+		asset,
 		amount,
-		..
-	}: BridgeTransferDetails<BFrom::Address, BFrom::Hash>,
-) -> Result<(), LockBridgeTransferAssetsError>
-where
-	BTo::Hash: From<BFrom::Hash>,
-	Vec<u8>: From<BFrom::Address>,
-{
-	let bridge_transfer_id = BridgeTransferId(From::from(bridge_transfer_id.0));
-	let hash_lock = HashLock(From::from(hash_lock.0));
-
+	}: LockDetails<BTo::Address, BTo::Hash>,
+) -> Result<BridgeTransferId<BTo::Hash>, LockBridgeTransferAssetsError> {
 	tracing::trace!(
 		"Calling lock_bridge_transfer_assets on counterparty contract for bridge transfer {:?}",
 		bridge_transfer_id
 	);
 
-	counterparty_contract
+	let bridge_transfer_id = counterparty_contract
 		.lock_bridge_transfer_assets(
 			bridge_transfer_id,
 			hash_lock,
 			time_lock,
-			InitiatorAddress(From::from(initiator_address.0)),
-			RecipientAddress(From::from(recipient_address.0)),
+			initiator_address,
+			recipient_address,
+			asset,
 			amount,
 		)
 		.await?;
 
-	Ok(())
+	Ok(bridge_transfer_id)
 }
 
 #[derive(Debug, Error)]
@@ -516,24 +2129,74 @@ impl HasTimeoutError for CompleteBridgeTransferError {
 	}
 }
 
-async fn call_complete_bridge_transfer<BFrom: BlockchainService, BTo: BlockchainService>(
+async fn call_complete_bridge_transfer<BFrom: BlockchainService>(
 	mut initiator_contract: BFrom::InitiatorContract,
-	CounterpartyCompletedDetails { bridge_transfer_id, secret, .. }: CounterpartyCompletedDetails<
-		BTo::Address,
-		BTo::Hash,
-	>,
-) -> Result<(), CompleteBridgeTransferError>
-where
-	BFrom::Hash: From<BTo::Hash>,
-{
+	bridge_transfer_id: BridgeTransferId<BFrom::Hash>,
+	secret: HashLockPreImage,
+) -> Result<(), CompleteBridgeTransferError> {
 	tracing::trace!(
 		"Calling complete bridge transfer on initiator contract for bridge transfer {:?}",
 		bridge_transfer_id
 	);
 
-	initiator_contract
-		.complete_bridge_transfer(convert_bridge_transfer_id(bridge_transfer_id), secret)
-		.await?;
+	initiator_contract.complete_bridge_transfer(bridge_transfer_id, secret).await?;
+
+	Ok(())
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AbortBridgeTransferError {
+	#[error("Timeout while performing contract call")]
+	ContractCallTimeoutError,
+	#[error(transparent)]
+	ContractCallError(#[from] BridgeContractCounterpartyError),
+}
+
+impl HasTimeoutError for AbortBridgeTransferError {
+	fn timeout_error() -> Self {
+		AbortBridgeTransferError::ContractCallTimeoutError
+	}
+}
+
+async fn call_abort_bridge_transfer<BTo: BlockchainService>(
+	mut counterparty_contract: BTo::CounterpartyContract,
+	bridge_transfer_id: BridgeTransferId<BTo::Hash>,
+) -> Result<(), AbortBridgeTransferError> {
+	tracing::trace!(
+		"Calling abort bridge transfer on counterparty contract for bridge transfer {:?}",
+		bridge_transfer_id
+	);
+
+	counterparty_contract.abort_bridge_transfer(bridge_transfer_id).await?;
+
+	Ok(())
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RelaySecretError {
+	#[error("Timeout while performing contract call")]
+	ContractCallTimeoutError,
+	#[error(transparent)]
+	ContractCallError(#[from] BridgeContractCounterpartyError),
+}
+
+impl HasTimeoutError for RelaySecretError {
+	fn timeout_error() -> Self {
+		RelaySecretError::ContractCallTimeoutError
+	}
+}
+
+async fn call_relay_secret<BTo: BlockchainService>(
+	mut counterparty_contract: BTo::CounterpartyContract,
+	bridge_transfer_id: BridgeTransferId<BTo::Hash>,
+	secret: HashLockPreImage,
+) -> Result<(), RelaySecretError> {
+	tracing::trace!(
+		"Calling complete bridge transfer on counterparty contract for bridge transfer {:?}",
+		bridge_transfer_id
+	);
+
+	counterparty_contract.complete_bridge_transfer(bridge_transfer_id, secret).await?;
 
 	Ok(())
 }
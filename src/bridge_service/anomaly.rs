@@ -0,0 +1,55 @@
+use crate::types::{Amount, AssetId, HashLock, LockDetails, RecipientAddress, TimeLock};
+
+/// Difference between a counterparty contract event and the lock the bridge requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwapAnomaly {
+	/// The event refers to a lock the bridge never placed.
+	UnknownTransfer,
+	AmountMismatch {
+		expected: Amount,
+		actual: Amount,
+	},
+	AssetMismatch {
+		expected: AssetId,
+		actual: AssetId,
+	},
+	RecipientMismatch,
+	HashLockMismatch,
+	TimeLockMismatch {
+		expected: TimeLock,
+		actual: TimeLock,
+	},
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AnomalyPolicy {
+	/// Stops accepting new transfers in the direction an anomaly was seen in, until resumed.
+	pub pause_on_anomaly: bool,
+}
+
+/// Compares the fields every counterparty event carries against the requested lock.
+pub(crate) fn lock_anomalies<A: PartialEq, H: PartialEq>(
+	expected: &LockDetails<A, H>,
+	recipient_address: &RecipientAddress<A>,
+	hash_lock: &HashLock<H>,
+	asset: &AssetId,
+	amount: Amount,
+) -> Vec<SwapAnomaly> {
+	let mut anomalies = Vec::new();
+	if amount != expected.amount {
+		anomalies.push(SwapAnomaly::AmountMismatch { expected: expected.amount, actual: amount });
+	}
+	if *asset != expected.asset {
+		anomalies.push(SwapAnomaly::AssetMismatch {
+			expected: expected.asset.clone(),
+			actual: asset.clone(),
+		});
+	}
+	if *recipient_address != expected.recipient_address {
+		anomalies.push(SwapAnomaly::RecipientMismatch);
+	}
+	if *hash_lock != expected.hash_lock {
+		anomalies.push(SwapAnomaly::HashLockMismatch);
+	}
+	anomalies
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use crate::types::{Amount, AmountScaling, AssetId};
+
+/// Bounds on the amount of a single transfer, expressed in initiator chain units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferLimits {
+	pub min: Amount,
+	pub max: Amount,
+}
+
+impl Default for TransferLimits {
+	fn default() -> Self {
+		Self { min: Amount(0), max: Amount(u128::MAX) }
+	}
+}
+
+/// Fee retained by the bridge, expressed in initiator chain units. The remainder of the transfer
+/// is locked on the counterparty chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AssetFee {
+	pub flat: Amount,
+	pub basis_points: u32,
+}
+
+impl AssetFee {
+	pub fn fee_for(&self, amount: Amount) -> Option<Amount> {
+		let bps = u128::from(self.basis_points);
+		// Split the multiplication so large amounts do not overflow
+		let proportional =
+			(amount.0 / 10_000).checked_mul(bps)?.checked_add(amount.0 % 10_000 * bps / 10_000)?;
+		proportional.checked_add(self.flat.0).map(Amount)
+	}
+}
+
+/// Settings for bridging one asset in one direction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetConfig {
+	/// The asset that is locked on the counterparty chain.
+	pub counterparty_asset: AssetId,
+	pub amount_scaling: AmountScaling,
+	pub limits: TransferLimits,
+	pub fee: AssetFee,
+	/// Liquidity available on the counterparty chain, in counterparty chain units. `None` leaves
+	/// liquidity unchecked.
+	pub liquidity: Option<Amount>,
+}
+
+impl AssetConfig {
+	pub fn new(counterparty_asset: impl Into<AssetId>) -> Self {
+		Self {
+			counterparty_asset: counterparty_asset.into(),
+			amount_scaling: AmountScaling::default(),
+			limits: TransferLimits::default(),
+			fee: AssetFee::default(),
+			liquidity: None,
+		}
+	}
+
+	pub fn with_amount_scaling(mut self, amount_scaling: AmountScaling) -> Self {
+		self.amount_scaling = amount_scaling;
+		self
+	}
+
+	pub fn with_limits(mut self, limits: TransferLimits) -> Self {
+		self.limits = limits;
+		self
+	}
+
+	pub fn with_fee(mut self, fee: AssetFee) -> Self {
+		self.fee = fee;
+		self
+	}
+
+	pub fn with_liquidity(mut self, liquidity: Amount) -> Self {
+		self.liquidity = Some(liquidity);
+		self
+	}
+}
+
+/// Maps the assets accepted on the initiator chain to their counterparty chain settings.
+/// Transfers of assets missing from the mapping are rejected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetMapping {
+	assets: HashMap<AssetId, AssetConfig>,
+}
+
+impl AssetMapping {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_asset(mut self, asset: impl Into<AssetId>, config: AssetConfig) -> Self {
+		self.insert(asset, config);
+		self
+	}
+
+	pub fn insert(&mut self, asset: impl Into<AssetId>, config: AssetConfig) {
+		self.assets.insert(asset.into(), config);
+	}
+
+	pub fn get(&self, asset: &AssetId) -> Option<&AssetConfig> {
+		self.assets.get(asset)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&AssetId, &AssetConfig)> {
+		self.assets.iter()
+	}
+}
@@ -0,0 +1,263 @@
+use std::{
+	collections::BTreeMap,
+	future::Future,
+	pin::Pin,
+	sync::{Arc, Mutex},
+	task::{Context, Poll, Waker},
+	time::{Duration, Instant},
+};
+
+use futures_timer::Delay;
+
+/// Token bucket: up to `capacity` calls in a burst, refilled by one every `refill_interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+	pub capacity: u32,
+	pub refill_interval: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CallQueueOrder {
+	#[default]
+	Fifo,
+	/// Completions whose initiator lock expires within `urgent_within` go first, as the bridge
+	/// loses the funds it locked if they miss it. Other completions follow, then new locks. Calls
+	/// of the same priority go by the expiry of their initiator lock.
+	Priority,
+}
+
+/// Limits the contract calls the bridge makes to one chain. Without limits calls are made right
+/// away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallLimitConfig {
+	pub max_in_flight: Option<usize>,
+	pub rate_limit: Option<RateLimit>,
+	pub queue_order: CallQueueOrder,
+	pub urgent_within: Duration,
+	/// Calls waiting longer go first whatever their priority, so locks are not starved.
+	pub max_queue_wait: Option<Duration>,
+}
+
+impl Default for CallLimitConfig {
+	fn default() -> Self {
+		Self {
+			max_in_flight: None,
+			rate_limit: None,
+			queue_order: CallQueueOrder::default(),
+			urgent_within: Duration::from_secs(10 * 60),
+			max_queue_wait: Some(Duration::from_secs(60)),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+	Lock,
+	Complete,
+}
+
+/// Place of a queued contract call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CallTicket(u64);
+
+#[derive(Debug)]
+struct QueuedCall {
+	kind: CallKind,
+	/// Expiry of the initiator lock of the swap, when known.
+	deadline: Option<Instant>,
+	enqueued_at: Instant,
+}
+
+/// Queue of the contract calls to one chain, shared by the swap maps of both directions.
+pub struct CallLimiter {
+	pub config: CallLimitConfig,
+	in_flight: usize,
+	tokens: u32,
+	refilled_at: Instant,
+	refill: Option<Delay>,
+	next_seq: u64,
+	queue: BTreeMap<CallTicket, QueuedCall>,
+	/// Tasks waiting on a queued call, woken once their call is next in line and may go.
+	wakers: BTreeMap<CallTicket, Waker>,
+}
+
+pub type SharedCallLimiter = Arc<Mutex<CallLimiter>>;
+
+impl std::fmt::Debug for CallLimiter {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("CallLimiter")
+			.field("config", &self.config)
+			.field("in_flight", &self.in_flight)
+			.field("tokens", &self.tokens)
+			.field("queue", &self.queue)
+			.finish()
+	}
+}
+
+impl CallLimiter {
+	pub fn new(config: CallLimitConfig) -> Self {
+		Self {
+			config,
+			in_flight: 0,
+			tokens: config.rate_limit.map_or(0, |rate_limit| rate_limit.capacity),
+			refilled_at: Instant::now(),
+			refill: None,
+			next_seq: 0,
+			queue: BTreeMap::new(),
+			wakers: BTreeMap::new(),
+		}
+	}
+
+	pub fn shared(config: CallLimitConfig) -> SharedCallLimiter {
+		Arc::new(Mutex::new(Self::new(config)))
+	}
+
+	/// Calls waiting for their turn.
+	pub fn queue_depth(&self) -> usize {
+		self.queue.len()
+	}
+
+	pub fn in_flight(&self) -> usize {
+		self.in_flight
+	}
+
+	pub fn enqueue(&mut self, kind: CallKind, deadline: Option<Instant>) -> CallTicket {
+		let ticket = CallTicket(self.next_seq);
+		self.next_seq += 1;
+		self.queue.insert(ticket, QueuedCall { kind, deadline, enqueued_at: Instant::now() });
+		ticket
+	}
+
+	/// The call allowed to go next.
+	pub fn next_in_line(&self) -> Option<CallTicket> {
+		let now = Instant::now();
+		let (oldest, call) = self.queue.first_key_value()?;
+		let starved = self
+			.config
+			.max_queue_wait
+			.is_some_and(|max_wait| now.duration_since(call.enqueued_at) >= max_wait);
+		if self.config.queue_order == CallQueueOrder::Fifo || starved {
+			return Some(*oldest);
+		}
+		self.queue
+			.iter()
+			.min_by_key(|(ticket, call)| {
+				(self.priority(call, now), call.deadline.is_none(), call.deadline, **ticket)
+			})
+			.map(|(ticket, _)| *ticket)
+	}
+
+	fn priority(&self, call: &QueuedCall, now: Instant) -> u8 {
+		match call.kind {
+			CallKind::Complete
+				if call
+					.deadline
+					.is_some_and(|deadline| deadline <= now + self.config.urgent_within) =>
+			{
+				0
+			}
+			CallKind::Complete => 1,
+			CallKind::Lock => 2,
+		}
+	}
+
+	/// Lets the call of `ticket` go once it is next in line and both a call slot and a rate token
+	/// are free. The slot is given back with `release` when the call returns.
+	pub fn poll_acquire(&mut self, ticket: CallTicket, cx: &mut Context<'_>) -> Poll<()> {
+		self.refill_tokens();
+		let token_free = self.token_free();
+		if self.slot_free() && token_free {
+			if self.next_in_line() == Some(ticket) {
+				self.queue.remove(&ticket);
+				self.wakers.remove(&ticket);
+				self.in_flight += 1;
+				if self.config.rate_limit.is_some() {
+					self.tokens -= 1;
+				}
+				// The next call in line may go as well
+				if self.slot_free() && self.token_free() {
+					self.wake_next();
+				}
+				return Poll::Ready(());
+			}
+			// Whoever is next in line takes it
+			self.wake_next();
+		}
+		if !self.wakers.get(&ticket).is_some_and(|waker| waker.will_wake(cx.waker())) {
+			self.wakers.insert(ticket, cx.waker().clone());
+		}
+		if !token_free {
+			self.poll_refill(cx);
+		}
+		Poll::Pending
+	}
+
+	/// Takes a call out of the queue that will not be made anymore.
+	pub fn cancel(&mut self, ticket: CallTicket) {
+		self.queue.remove(&ticket);
+		self.wakers.remove(&ticket);
+		self.wake_next();
+	}
+
+	pub fn release(&mut self) {
+		self.in_flight -= 1;
+		self.wake_next();
+	}
+
+	fn slot_free(&self) -> bool {
+		self.config.max_in_flight.map_or(true, |max| self.in_flight < max)
+	}
+
+	fn token_free(&self) -> bool {
+		self.config.rate_limit.is_none() || self.tokens > 0
+	}
+
+	fn wake_next(&mut self) {
+		if let Some(waker) = self.next_in_line().and_then(|ticket| self.wakers.remove(&ticket)) {
+			waker.wake();
+		}
+	}
+
+	fn refill_tokens(&mut self) {
+		let Some(RateLimit { capacity, refill_interval }) = self.config.rate_limit else {
+			return;
+		};
+		let now = Instant::now();
+		let refills = (now.duration_since(self.refilled_at).as_nanos()
+			/ refill_interval.as_nanos().max(1)) as u32;
+		if refills == 0 {
+			return;
+		}
+		self.tokens = self.tokens.saturating_add(refills).min(capacity);
+		self.refilled_at = if self.tokens == capacity {
+			now
+		} else {
+			self.refilled_at + refill_interval * refills
+		};
+	}
+
+	/// Waits for the next token, then wakes the call next in line.
+	fn poll_refill(&mut self, cx: &mut Context<'_>) {
+		let Some(rate_limit) = self.config.rate_limit else {
+			return;
+		};
+		loop {
+			let refilled_at = self.refilled_at;
+			let refill = self.refill.get_or_insert_with(|| {
+				Delay::new(
+					(refilled_at + rate_limit.refill_interval)
+						.saturating_duration_since(Instant::now()),
+				)
+			});
+			if Pin::new(refill).poll(cx).is_pending() {
+				return;
+			}
+			self.refill = None;
+			self.refill_tokens();
+			if self.tokens > 0 {
+				self.wake_next();
+				return;
+			}
+		}
+	}
+}
@@ -0,0 +1,134 @@
+use std::{
+	collections::VecDeque,
+	time::{Duration, Instant},
+};
+
+use crate::{bridge_service::direction::Direction, types::AssetId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerScope {
+	Global,
+	Direction(Direction),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TripReason {
+	Manual(String),
+	/// `errors` contract call errors were seen within `window`.
+	ErrorRate {
+		errors: usize,
+		window: Duration,
+	},
+	Anomaly,
+	InsufficientLiquidity(AssetId),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CircuitBreakerEvent {
+	Tripped(BreakerScope, TripReason),
+	Reset(BreakerScope),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+	/// Trips a direction once this many contract call errors happen within `error_window`.
+	pub max_errors: Option<usize>,
+	pub error_window: Duration,
+	/// Trips a direction when a transfer is rejected for lack of liquidity.
+	pub trip_on_insufficient_liquidity: bool,
+}
+
+impl Default for CircuitBreakerConfig {
+	fn default() -> Self {
+		Self {
+			max_errors: None,
+			error_window: Duration::from_secs(60),
+			trip_on_insufficient_liquidity: false,
+		}
+	}
+}
+
+#[derive(Debug, Default)]
+struct DirectionState {
+	tripped: Option<TripReason>,
+	errors: VecDeque<Instant>,
+}
+
+/// Kill switch of the bridge. While tripped, no new locks are placed in the affected directions.
+/// Swaps already in flight keep completing, so no funds are stranded.
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+	pub config: CircuitBreakerConfig,
+	global: Option<TripReason>,
+	b1_to_b2: DirectionState,
+	b2_to_b1: DirectionState,
+}
+
+impl CircuitBreaker {
+	pub fn new(config: CircuitBreakerConfig) -> Self {
+		Self { config, ..Default::default() }
+	}
+
+	fn direction(&mut self, direction: Direction) -> &mut DirectionState {
+		match direction {
+			Direction::B1ToB2 => &mut self.b1_to_b2,
+			Direction::B2ToB1 => &mut self.b2_to_b1,
+		}
+	}
+
+	fn state(&mut self, scope: BreakerScope) -> &mut Option<TripReason> {
+		match scope {
+			BreakerScope::Global => &mut self.global,
+			BreakerScope::Direction(direction) => &mut self.direction(direction).tripped,
+		}
+	}
+
+	pub fn trip_reason(&self, scope: BreakerScope) -> Option<&TripReason> {
+		match scope {
+			BreakerScope::Global => self.global.as_ref(),
+			BreakerScope::Direction(Direction::B1ToB2) => self.b1_to_b2.tripped.as_ref(),
+			BreakerScope::Direction(Direction::B2ToB1) => self.b2_to_b1.tripped.as_ref(),
+		}
+	}
+
+	/// Whether new locks are stopped in `direction`, by its own breaker or the global one.
+	pub fn is_tripped(&self, direction: Direction) -> bool {
+		self.global.is_some() || self.trip_reason(BreakerScope::Direction(direction)).is_some()
+	}
+
+	/// Returns the event to emit, `None` when the scope was already tripped.
+	pub fn trip(&mut self, scope: BreakerScope, reason: TripReason) -> Option<CircuitBreakerEvent> {
+		let state = self.state(scope);
+		if state.is_some() {
+			return None;
+		}
+		*state = Some(reason.clone());
+		Some(CircuitBreakerEvent::Tripped(scope, reason))
+	}
+
+	/// Returns the event to emit, `None` when the scope was not tripped.
+	pub fn reset(&mut self, scope: BreakerScope) -> Option<CircuitBreakerEvent> {
+		self.state(scope).take()?;
+		if let BreakerScope::Direction(direction) = scope {
+			self.direction(direction).errors.clear();
+		}
+		Some(CircuitBreakerEvent::Reset(scope))
+	}
+
+	/// Records a contract call error, tripping the direction when the error rate is exceeded.
+	pub fn record_error(&mut self, direction: Direction) -> Option<CircuitBreakerEvent> {
+		let max_errors = self.config.max_errors?;
+		let window = self.config.error_window;
+		let now = Instant::now();
+		let errors = &mut self.direction(direction).errors;
+		errors.push_back(now);
+		while errors.front().is_some_and(|error| now.duration_since(*error) > window) {
+			errors.pop_front();
+		}
+		if errors.len() < max_errors {
+			return None;
+		}
+		let errors = errors.len();
+		self.trip(BreakerScope::Direction(direction), TripReason::ErrorRate { errors, window })
+	}
+}
@@ -0,0 +1,32 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+	B1ToB2,
+	B2ToB1,
+}
+
+/// Directions the bridge swaps in. Events of a disabled direction are reported, not processed,
+/// so a chain without a counterparty contract can only be swapped from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnabledDirections {
+	pub b1_to_b2: bool,
+	pub b2_to_b1: bool,
+}
+
+impl Default for EnabledDirections {
+	fn default() -> Self {
+		Self { b1_to_b2: true, b2_to_b1: true }
+	}
+}
+
+impl EnabledDirections {
+	pub fn only(direction: Direction) -> Self {
+		Self { b1_to_b2: direction == Direction::B1ToB2, b2_to_b1: direction == Direction::B2ToB1 }
+	}
+
+	pub fn is_enabled(&self, direction: Direction) -> bool {
+		match direction {
+			Direction::B1ToB2 => self.b1_to_b2,
+			Direction::B2ToB1 => self.b2_to_b1,
+		}
+	}
+}
@@ -1,16 +1,51 @@
 use crate::{
 	blockchain_service::BlockchainService,
 	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
-	types::{BridgeTransferDetails, BridgeTransferId, CounterpartyCompletedDetails},
+	hash_lock::SecretConstraintError,
+	types::{
+		BridgeTransferDetails, BridgeTransferId, ConversionError, CounterpartyCompletedDetails,
+	},
 };
 
-use super::active_swap::LockBridgeTransferAssetsError;
+use super::{
+	active_swap::{
+		AbortBridgeTransferError, ActiveSwapMapError, LockBridgeTransferAssetsError,
+		RelaySecretError, SwapCapacity, TransferField,
+	},
+	anomaly::SwapAnomaly,
+	circuit_breaker::CircuitBreakerEvent,
+	direction::Direction,
+	time_lock::TimeLockError,
+};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum IWarn<A, H> {
 	AlreadyPresent(BridgeTransferDetails<A, H>),
 	CompleteTransferError(BridgeTransferId<H>),
 	CompletionAbortedTooManyAttempts(BridgeTransferId<H>),
+	TransferRejected(BridgeTransferDetails<A, H>, ActiveSwapMapError),
+	/// Security warning: the hash lock is already used by an open or recent swap, given by its raw
+	/// id, in either direction. The transfer is not locked, it is held when `held` is set.
+	HashLockReused {
+		details: BridgeTransferDetails<A, H>,
+		conflicting_transfer: Vec<u8>,
+		held: bool,
+	},
+	/// The event belongs to a direction that is not enabled and was not processed.
+	DirectionDisabled(BridgeContractInitiatorEvent<A, H>),
+	/// The bridge router has no enabled route to the destination chain of the transfer, or no
+	/// destination was given and the chain has several routes. Nothing is locked.
+	NoRoute(BridgeTransferDetails<A, H>),
+	/// A field of the transfer cannot be represented on the counterparty chain, e.g. because the
+	/// recipient is malformed. Nothing is locked.
+	CannotConvertTransfer {
+		bridge_transfer_id: BridgeTransferId<H>,
+		field: TransferField,
+		error: ConversionError,
+	},
+	/// Critical: the transfer was refunded to the initiator after the recipient claimed the
+	/// counterparty lock, so the bridge cannot claim the initiator funds.
+	RefundedAfterClaim(BridgeTransferId<H>),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -40,11 +75,42 @@ pub enum CWarn<A, H> {
 	BridgeAssetsLockingError(LockBridgeTransferAssetsError),
 	CannotCompleteUnexistingSwap(CounterpartyCompletedDetails<A, H>),
 	LockingAbortedTooManyAttempts(BridgeTransferId<H>),
+	/// Security warning: the secret revealed on the counterparty chain does not unlock the hash
+	/// lock on the initiator chain. The completion is not attempted, nor retried.
+	InvalidHashLockPreImage(CounterpartyCompletedDetails<A, H>),
+	/// Critical: the secret revealed on the counterparty chain unlocks the hash locks but the
+	/// initiator chain does not accept it, so the bridge cannot claim the initiator funds.
+	SecretNotReplayable(CounterpartyCompletedDetails<A, H>, SecretConstraintError),
+	/// High severity: a counterparty contract event does not match the lock the bridge requested,
+	/// or refers to a lock it never placed.
+	Anomaly(BridgeContractCounterpartyEvent<A, H>, Vec<SwapAnomaly>),
+	/// The event belongs to a direction that is not enabled and was not processed.
+	DirectionDisabled(BridgeContractCounterpartyEvent<A, H>),
+	AbortingLockError(BridgeTransferId<H>, AbortBridgeTransferError),
+	/// The lock could not be given back and stays claimable by the recipient.
+	AbortingAbortedTooManyAttempts(BridgeTransferId<H>),
+	RelayingSecretError(BridgeTransferId<H>, RelaySecretError),
+	/// The bridge gave up claiming the lock with a relayed secret. The lock stays claimable.
+	RelayingAborted(BridgeTransferId<H>),
+	/// The lock was given up before it was placed, as the initiator time lock no longer covers the
+	/// safety margin. Nothing is locked.
+	LockingExpired(BridgeTransferId<H>, TimeLockError),
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum CEvent<A, H> {
 	RetryLockingAssets(BridgeTransferId<H>),
+	/// The bridge gave back its lock, see `ActiveSwapMap::abort_bridge_transfer`.
+	LockAborted(BridgeTransferId<H>),
+	/// The bridge claimed the lock with a secret its recipient relayed, see
+	/// `ActiveSwapMap::relay_secret`. The initiator funds are claimed next.
+	SecretRelayed(BridgeTransferId<H>),
+	/// The transfer was refunded to the initiator while the bridge was locking, and the lock call
+	/// failed. Nothing is locked.
+	LockingCancelled(BridgeTransferId<H>),
+	/// The transfer was refunded to the initiator before the bridge claimed the lock with a relayed
+	/// secret. The claim is dropped and the lock given back.
+	RelayingCancelled(BridgeTransferId<H>),
 	ContractEvent(BridgeContractCounterpartyEvent<A, H>),
 	Warn(CWarn<A, H>),
 }
@@ -75,6 +141,15 @@ where
 	B1C(CEvent<B1::Address, B1::Hash>),
 	B2I(IEvent<B2::Address, B2::Hash>),
 	B2C(CEvent<B2::Address, B2::Hash>),
+	CircuitBreaker(CircuitBreakerEvent),
+	Capacity(CapacityEvent),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CapacityEvent {
+	/// No more transfers are taken on in the direction until swaps finish.
+	Reached(Direction, SwapCapacity),
+	Freed(Direction, SwapCapacity),
 }
 
 #[allow(non_snake_case)]
@@ -128,4 +203,18 @@ impl<B1: BlockchainService, B2: BlockchainService> Event<B1, B2> {
 	) -> Option<&BridgeContractCounterpartyEvent<B2::Address, B2::Hash>> {
 		self.B2C()?.contract_event()
 	}
+
+	pub fn circuit_breaker(&self) -> Option<&CircuitBreakerEvent> {
+		match self {
+			Event::CircuitBreaker(event) => Some(event),
+			_ => None,
+		}
+	}
+
+	pub fn capacity(&self) -> Option<&CapacityEvent> {
+		match self {
+			Event::Capacity(event) => Some(event),
+			_ => None,
+		}
+	}
 }
@@ -0,0 +1,96 @@
+use std::{
+	collections::{HashMap, VecDeque},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+/// What happens to a transfer whose hash lock is used by an open or recently finished swap.
+/// Revealing the secret of one swap lets anyone claim the other, so neither is locked right away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashLockReusePolicy {
+	#[default]
+	Reject,
+	/// Keep the transfer aside until an operator releases or discards it.
+	Hold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashLockReuseConfig {
+	pub policy: HashLockReusePolicy,
+	/// How long the hash locks of finished swaps are remembered.
+	pub retention: Duration,
+}
+
+impl Default for HashLockReuseConfig {
+	fn default() -> Self {
+		Self {
+			policy: HashLockReusePolicy::default(),
+			retention: Duration::from_secs(24 * 60 * 60),
+		}
+	}
+}
+
+/// Hash locks of open and recently finished swaps, in raw bytes, together with the raw id of the
+/// transfer using them. Shared by the swap maps of both directions.
+#[derive(Debug)]
+pub struct HashLockIndex {
+	pub config: HashLockReuseConfig,
+	open: HashMap<Vec<u8>, Vec<u8>>,
+	recent: HashMap<Vec<u8>, (Instant, Vec<u8>)>,
+	expiries: VecDeque<(Instant, Vec<u8>)>,
+}
+
+pub type SharedHashLockIndex = Arc<Mutex<HashLockIndex>>;
+
+impl HashLockIndex {
+	pub fn new(config: HashLockReuseConfig) -> Self {
+		Self { config, open: HashMap::new(), recent: HashMap::new(), expiries: VecDeque::new() }
+	}
+
+	pub fn shared(config: HashLockReuseConfig) -> SharedHashLockIndex {
+		Arc::new(Mutex::new(Self::new(config)))
+	}
+
+	/// Raw id of the transfer already using one of `hash_locks`, if any.
+	pub fn conflicting_transfer(&mut self, hash_locks: &[&[u8]]) -> Option<Vec<u8>> {
+		self.prune();
+		hash_locks
+			.iter()
+			.find_map(|hash_lock| {
+				self.open.get(*hash_lock).or_else(|| Some(&self.recent.get(*hash_lock)?.1))
+			})
+			.cloned()
+	}
+
+	pub fn insert(&mut self, hash_locks: &[&[u8]], bridge_transfer_id: &[u8]) {
+		for hash_lock in hash_locks {
+			self.open.insert(hash_lock.to_vec(), bridge_transfer_id.to_vec());
+		}
+	}
+
+	/// Moves the hash locks of a finished swap to the recent ones.
+	pub fn release(&mut self, hash_locks: &[&[u8]], bridge_transfer_id: &[u8]) {
+		let expiry = Instant::now() + self.config.retention;
+		for hash_lock in hash_locks {
+			if self.open.get(*hash_lock).is_some_and(|owner| owner == bridge_transfer_id) {
+				let owner = self.open.remove(*hash_lock).expect("checked above");
+				self.recent.insert(hash_lock.to_vec(), (expiry, owner));
+				self.expiries.push_back((expiry, hash_lock.to_vec()));
+			}
+		}
+	}
+
+	fn prune(&mut self) {
+		let now = Instant::now();
+		while let Some((expiry, _)) = self.expiries.front() {
+			if *expiry > now {
+				break;
+			}
+			let (expiry, hash_lock) = self.expiries.pop_front().expect("checked above");
+			// The hash lock may have been released again since
+			if self.recent.get(&hash_lock).is_some_and(|(latest, _)| *latest == expiry) {
+				self.recent.remove(&hash_lock);
+			}
+		}
+	}
+}
@@ -0,0 +1,176 @@
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+};
+
+use crate::types::{Amount, AssetId, ChainId};
+
+/// Risk limits on netting opposite flows. Locks on a chain may draw on the funds the bridge is
+/// due to claim on that chain from pending swaps out of it, on top of its own liquidity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NettingConfig {
+	/// Share of the incoming funds that may back new locks, in basis points. Zero keeps admission
+	/// on gross liquidity.
+	pub incoming_share_bps: u32,
+	/// Credit drawn per chain and asset at most, `None` leaves it bounded by the share only.
+	pub max_credit: Option<Amount>,
+}
+
+/// Exposure of the bridge to one asset on one chain, in chain units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Exposure {
+	/// Reserved or locked by the bridge for pending swaps into the chain.
+	pub outgoing: Amount,
+	/// Locked by initiators of pending swaps out of the chain whose counterparty lock is placed,
+	/// claimed by the bridge once they complete.
+	pub incoming: Amount,
+	/// Part of `outgoing` not covered by liquidity, backed by `incoming` instead.
+	pub drawn: Amount,
+}
+
+impl Exposure {
+	/// Outgoing funds left once the incoming funds are netted against them.
+	pub fn net_outgoing(&self) -> Amount {
+		Amount(self.outgoing.saturating_sub(*self.incoming))
+	}
+
+	/// Incoming funds left once the outgoing funds are netted against them.
+	pub fn net_incoming(&self) -> Amount {
+		Amount(self.incoming.saturating_sub(*self.outgoing))
+	}
+}
+
+/// Pending flows per chain and asset of the swap maps of both directions, which report to it as
+/// their swaps progress. Assets are identified as on the chain they are held on.
+#[derive(Debug, Default)]
+pub struct LiquidityPlanner {
+	pub config: NettingConfig,
+	exposures: HashMap<(ChainId, AssetId), Exposure>,
+}
+
+pub type SharedLiquidityPlanner = Arc<Mutex<LiquidityPlanner>>;
+
+impl LiquidityPlanner {
+	pub fn new(config: NettingConfig) -> Self {
+		Self { config, exposures: HashMap::new() }
+	}
+
+	pub fn shared(config: NettingConfig) -> SharedLiquidityPlanner {
+		Arc::new(Mutex::new(Self::new(config)))
+	}
+
+	pub fn exposure(&self, chain: ChainId, asset: &AssetId) -> Exposure {
+		self.exposures.get(&(chain, asset.clone())).copied().unwrap_or_default()
+	}
+
+	/// Exposure per asset on `chain`.
+	pub fn chain_exposure(&self, chain: ChainId) -> impl Iterator<Item = (&AssetId, &Exposure)> {
+		self.exposures
+			.iter()
+			.filter(move |((exposure_chain, _), _)| *exposure_chain == chain)
+			.map(|((_, asset), exposure)| (asset, exposure))
+	}
+
+	/// Credit still available to new locks of `asset` on `chain`, within the risk limits.
+	pub fn credit(&self, chain: ChainId, asset: &AssetId) -> Amount {
+		let exposure = self.exposure(chain, asset);
+		let bps = u128::from(self.config.incoming_share_bps);
+		let share =
+			exposure.incoming.0 / 10_000 * bps + exposure.incoming.0 % 10_000 * bps / 10_000;
+		let limit = match self.config.max_credit {
+			Some(max_credit) => share.min(max_credit.0),
+			None => share,
+		};
+		Amount(limit.saturating_sub(exposure.drawn.0))
+	}
+
+	fn entry(&mut self, chain: ChainId, asset: &AssetId) -> &mut Exposure {
+		self.exposures.entry((chain, asset.clone())).or_default()
+	}
+
+	/// Reserves `amount` for a lock, taking what `available` lacks from the credit.
+	pub fn lock(
+		&mut self,
+		chain: ChainId,
+		asset: &AssetId,
+		amount: Amount,
+		available: Option<&mut Amount>,
+	) {
+		let exposure = self.entry(chain, asset);
+		*exposure.outgoing += *amount;
+		if let Some(available) = available {
+			let covered = amount.min(*available);
+			**available -= *covered;
+			*exposure.drawn += *amount - *covered;
+		}
+	}
+
+	/// Returns `amount` of a lock that was not placed or was given back, repaying credit first.
+	pub fn unlock(
+		&mut self,
+		chain: ChainId,
+		asset: &AssetId,
+		amount: Amount,
+		available: Option<&mut Amount>,
+	) {
+		let exposure = self.entry(chain, asset);
+		*exposure.outgoing = exposure.outgoing.saturating_sub(*amount);
+		if let Some(available) = available {
+			let repaid = amount.min(exposure.drawn);
+			*exposure.drawn -= *repaid;
+			**available += *amount - *repaid;
+		}
+	}
+
+	/// Adds liquidity, e.g. after a top-up, repaying credit first.
+	pub fn top_up(
+		&mut self,
+		chain: ChainId,
+		asset: &AssetId,
+		amount: Amount,
+		available: &mut Amount,
+	) {
+		let exposure = self.entry(chain, asset);
+		let repaid = amount.min(exposure.drawn);
+		*exposure.drawn -= *repaid;
+		**available += *amount - *repaid;
+	}
+
+	/// A lock was claimed by its recipient, its funds are gone.
+	pub fn spend(&mut self, chain: ChainId, asset: &AssetId, amount: Amount) {
+		let exposure = self.entry(chain, asset);
+		*exposure.outgoing = exposure.outgoing.saturating_sub(*amount);
+	}
+
+	/// The counterparty lock of a swap out of `chain` is placed, its initiator funds are due.
+	pub fn expect_incoming(&mut self, chain: ChainId, asset: &AssetId, amount: Amount) {
+		*self.entry(chain, asset).incoming += *amount;
+	}
+
+	/// Initiator funds that will not be claimed after all.
+	pub fn cancel_incoming(&mut self, chain: ChainId, asset: &AssetId, amount: Amount) {
+		let exposure = self.entry(chain, asset);
+		*exposure.incoming = exposure.incoming.saturating_sub(*amount);
+	}
+
+	/// Initiator funds were claimed, they repay the credit drawn on the chain.
+	pub fn receive_incoming(&mut self, chain: ChainId, asset: &AssetId, amount: Amount) {
+		let exposure = self.entry(chain, asset);
+		*exposure.incoming = exposure.incoming.saturating_sub(*amount);
+		*exposure.drawn = exposure.drawn.saturating_sub(*amount);
+	}
+}
+
+/// Chains a swap map reports its flows to the planner under.
+#[derive(Debug, Clone)]
+pub struct PlannerChains {
+	pub planner: SharedLiquidityPlanner,
+	pub initiator: ChainId,
+	pub counterparty: ChainId,
+}
+
+impl PlannerChains {
+	pub fn lock(&self) -> std::sync::MutexGuard<'_, LiquidityPlanner> {
+		self.planner.lock().expect("liquidity planner poisoned")
+	}
+}
@@ -0,0 +1,156 @@
+use std::{
+	collections::HashMap,
+	time::{Duration, Instant},
+};
+
+use crate::{
+	bridge_service::ChainConfig,
+	types::{Amount, AssetId, TimeLock},
+};
+
+/// Terms of the quotes a swap map gives ahead of initiation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuoteConfig {
+	/// How long a quote may be accepted once given.
+	pub validity: Duration,
+	/// How long an accepted quote holds its liquidity for the transfer to be initiated.
+	pub reservation: Duration,
+	/// Counterparty lock the bridge asks for at least, on top of the safety margin.
+	pub min_counterparty_time_lock: Duration,
+	/// Longest initiator time lock quotes cover, `None` leaves it unbounded.
+	pub max_time_lock: Option<Duration>,
+}
+
+impl Default for QuoteConfig {
+	fn default() -> Self {
+		Self {
+			validity: Duration::from_secs(60),
+			reservation: Duration::from_secs(5 * 60),
+			min_counterparty_time_lock: Duration::from_secs(10 * 60),
+			max_time_lock: None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct QuoteId(pub u64);
+
+/// Initiator time locks a quote covers, as durations from initiation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLockRange {
+	pub min: Duration,
+	pub max: Option<Duration>,
+}
+
+impl TimeLockRange {
+	/// Whether `time_lock`, set on the initiator chain, is within the range. Absolute time locks
+	/// need the clock of the chain.
+	pub fn contains(&self, time_lock: &TimeLock, initiator_chain: &ChainConfig) -> bool {
+		let remaining = match (time_lock, &initiator_chain.clock) {
+			(TimeLock::Relative(duration), _) => *duration,
+			(time_lock, Some(clock)) => time_lock.time_remaining(&clock.now(), clock.block_time()),
+			(_, None) => return false,
+		};
+		remaining >= self.min && self.max.map_or(true, |max| remaining <= max)
+	}
+}
+
+/// Terms the bridge locks a transfer on, if it is initiated while the quote holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+	pub id: QuoteId,
+	/// Asset and amount to initiate, in initiator chain units.
+	pub asset: AssetId,
+	pub amount: Amount,
+	/// Retained by the bridge, in initiator chain units.
+	pub fee: Amount,
+	/// Asset and amount locked for the recipient, in counterparty chain units.
+	pub counterparty_asset: AssetId,
+	pub locked_amount: Amount,
+	pub recipient: Vec<u8>,
+	pub time_lock: TimeLockRange,
+	/// The quote can no longer be accepted after this instant.
+	pub expires_at: Instant,
+}
+
+/// Liquidity held for an accepted quote until a transfer under its hash lock is initiated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+	pub quote: Quote,
+	pub expires_at: Instant,
+}
+
+impl Reservation {
+	/// Whether an initiated transfer is the one quoted, so it may take the reserved liquidity.
+	pub fn is_matched_by(
+		&self,
+		asset: &AssetId,
+		amount: Amount,
+		recipient: &[u8],
+		time_lock: &TimeLock,
+		initiator_chain: &ChainConfig,
+	) -> bool {
+		&self.quote.asset == asset
+			&& self.quote.amount == amount
+			&& self.quote.recipient == recipient
+			&& self.quote.time_lock.contains(time_lock, initiator_chain)
+	}
+}
+
+/// Quotes given by a swap map and the reservations of those accepted, by raw hash lock.
+#[derive(Debug, Default)]
+pub struct Quotes {
+	next_id: u64,
+	open: HashMap<QuoteId, Quote>,
+	reservations: HashMap<Vec<u8>, Reservation>,
+}
+
+impl Quotes {
+	pub fn next_id(&mut self) -> QuoteId {
+		self.next_id += 1;
+		QuoteId(self.next_id)
+	}
+
+	pub fn insert(&mut self, quote: Quote) {
+		self.open.insert(quote.id, quote);
+	}
+
+	pub fn get(&self, id: QuoteId) -> Option<&Quote> {
+		self.open.get(&id)
+	}
+
+	/// Removes an open quote to accept it.
+	pub fn take(&mut self, id: QuoteId) -> Option<Quote> {
+		self.open.remove(&id)
+	}
+
+	pub fn reservation(&self, hash_lock: &[u8]) -> Option<&Reservation> {
+		self.reservations.get(hash_lock)
+	}
+
+	pub fn reserve(&mut self, hash_lock: &[u8], reservation: Reservation) {
+		self.reservations.insert(hash_lock.to_vec(), reservation);
+	}
+
+	pub fn take_reservation(&mut self, hash_lock: &[u8]) -> Option<Reservation> {
+		self.reservations.remove(hash_lock)
+	}
+
+	/// Drops the quotes and reservations expired at `now`, returning the reservations so their
+	/// liquidity can be released.
+	pub fn expire(&mut self, now: Instant) -> Vec<Reservation> {
+		self.open.retain(|_, quote| quote.expires_at > now);
+		let expired: Vec<_> = self
+			.reservations
+			.iter()
+			.filter(|(_, reservation)| reservation.expires_at <= now)
+			.map(|(hash_lock, _)| hash_lock.clone())
+			.collect();
+		expired.iter().filter_map(|hash_lock| self.reservations.remove(hash_lock)).collect()
+	}
+
+	/// When the next reservation expires.
+	pub fn next_expiry(&self) -> Option<Instant> {
+		self.reservations.values().map(|reservation| reservation.expires_at).min()
+	}
+}
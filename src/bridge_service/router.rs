@@ -0,0 +1,569 @@
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+use futures::{stream::FuturesUnordered, task::AtomicWaker, Stream, StreamExt};
+use thiserror::Error;
+use tracing::{trace, warn};
+
+use crate::{
+	blockchain_service::{BlockchainService, ContractEvent},
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::{ActiveSwapConfig, ActiveSwapMap, RelayConfig, SwapCapacity},
+		assets::AssetMapping,
+		call_limit::{CallLimiter, SharedCallLimiter},
+		events::{CEvent, CWarn, IEvent, IWarn},
+		handle_active_swap_event, handle_counterparty_event, handle_initiator_event,
+		hash_lock_index::{HashLockIndex, HashLockReuseConfig, SharedHashLockIndex},
+		netting::{LiquidityPlanner, NettingConfig, SharedLiquidityPlanner},
+		quote::QuoteConfig,
+		time_lock::TimeLockPolicy,
+		ChainConfig, HandleActiveSwapEvent,
+	},
+	types::{BridgeTransferDetails, BridgeTransferId, ChainId, TryConvert},
+};
+
+use self::multi_hop::{CompoundSwapStatus, CompoundSwaps, HopCallFuture};
+
+pub mod multi_hop;
+
+/// Ordered pair of chains the router swaps between, from the initiator to the counterparty chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Route {
+	pub from: ChainId,
+	pub to: ChainId,
+}
+
+impl Route {
+	pub fn new(from: impl Into<ChainId>, to: impl Into<ChainId>) -> Self {
+		Self { from: from.into(), to: to.into() }
+	}
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum BridgeRouterError {
+	#[error("Chain {0:?} is already registered")]
+	DuplicateChain(ChainId),
+	#[error("Chain {0:?} is not registered")]
+	UnknownChain(ChainId),
+	#[error("Route {0:?} is already enabled")]
+	DuplicateRoute(Route),
+	#[error("Route {0:?} starts and ends on the same chain")]
+	SameChain(Route),
+}
+
+/// Settings shared by all routes of a `BridgeRouter`.
+#[derive(Debug, Clone, Default)]
+pub struct BridgeRouterConfig {
+	pub active_swap: ActiveSwapConfig,
+	pub time_lock_policy: TimeLockPolicy,
+	/// Hash locks are checked for reuse across all routes.
+	pub hash_lock_reuse: HashLockReuseConfig,
+	/// Lets locks on a chain draw on the funds due from swaps out of it, along any route.
+	pub netting: NettingConfig,
+}
+
+/// Settings of one route.
+#[derive(Debug, Clone, Default)]
+pub struct RouteConfig {
+	/// Assets that can be bridged along the route, with the liquidity of the destination chain.
+	pub assets: AssetMapping,
+	/// Swaps along the route in flight at most. New transfers out of the initiator chain of the
+	/// route are left in its monitoring while the route is at capacity, whatever route they take.
+	pub max_active_swaps: Option<usize>,
+	pub quotes: QuoteConfig,
+	pub relay: RelayConfig,
+}
+
+struct RouterChain<B>
+where
+	B: BlockchainService,
+{
+	service: B,
+	config: ChainConfig,
+	/// Contract calls to the chain, shared by all routes from and to it.
+	calls: SharedCallLimiter,
+	/// `Initiated` events of new transfers held back while the chain is saturated.
+	deferred: VecDeque<BridgeContractInitiatorEvent<B::Address, B::Hash>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RouterEvent<A, H> {
+	/// Event of the initiator contract of the chain.
+	Initiator(ChainId, IEvent<A, H>),
+	/// Event of the counterparty contract of the chain.
+	Counterparty(ChainId, CEvent<A, H>),
+	/// Status change of the compound swap initiated on the chain under the given id.
+	CompoundSwap(ChainId, BridgeTransferId<H>, CompoundSwapStatus),
+	/// No more transfers are taken on from the initiator chain of the route until swaps finish.
+	CapacityReached(Route, SwapCapacity),
+	CapacityFreed(Route, SwapCapacity),
+}
+
+impl<A, H> RouterEvent<A, H> {
+	pub fn chain(&self) -> ChainId {
+		match self {
+			RouterEvent::Initiator(chain, _)
+			| RouterEvent::Counterparty(chain, _)
+			| RouterEvent::CompoundSwap(chain, _, _) => *chain,
+			RouterEvent::CapacityReached(route, _) | RouterEvent::CapacityFreed(route, _) => {
+				route.from
+			}
+		}
+	}
+
+	pub fn initiator(&self) -> Option<&IEvent<A, H>> {
+		match self {
+			RouterEvent::Initiator(_, event) => Some(event),
+			_ => None,
+		}
+	}
+
+	pub fn counterparty(&self) -> Option<&CEvent<A, H>> {
+		match self {
+			RouterEvent::Counterparty(_, event) => Some(event),
+			_ => None,
+		}
+	}
+
+	pub fn compound_swap(&self) -> Option<(&BridgeTransferId<H>, CompoundSwapStatus)> {
+		match self {
+			RouterEvent::CompoundSwap(_, bridge_transfer_id, status) => {
+				Some((bridge_transfer_id, *status))
+			}
+			_ => None,
+		}
+	}
+}
+
+/// Bridge between any number of chains of the same service type. Chains are registered under a
+/// chain id and swapped between along enabled routes, each with an `ActiveSwapMap` of its own.
+/// Initiated transfers take the route to their `destination_chain`, or the only route from their
+/// chain when none is given. Without a route to the destination, transfers are routed through an
+/// intermediate chain with a hop address, see `multi_hop`.
+///
+/// Every chain shares the service type `B`, and so its address and hash types, as routes pair any
+/// two chains and hash locks are indexed across all of them. Chains with different clients are
+/// bridged by wrapping them in one service type, e.g. an enum, that converts to common address and
+/// hash types; otherwise use a `BridgeService` per pair of chains.
+pub struct BridgeRouter<B>
+where
+	B: BlockchainService,
+{
+	pub config: BridgeRouterConfig,
+	chains: BTreeMap<ChainId, RouterChain<B>>,
+	routes: BTreeMap<Route, ActiveSwapMap<B, B>>,
+	hash_locks: SharedHashLockIndex,
+	liquidity_planner: SharedLiquidityPlanner,
+	hop_addresses: BTreeMap<ChainId, B::Address>,
+	/// Compound swaps in progress, by source transfer id.
+	compound_swaps: CompoundSwaps<B>,
+	hop_calls: FuturesUnordered<HopCallFuture<B::Hash>>,
+	pending_events: VecDeque<RouterEvent<B::Address, B::Hash>>,
+	/// Routes last reported at capacity.
+	at_capacity: BTreeSet<Route>,
+	waker: AtomicWaker,
+}
+
+impl<B> BridgeRouter<B>
+where
+	B: BlockchainService + 'static,
+	Vec<u8>: From<B::Address>,
+{
+	pub fn new(config: BridgeRouterConfig) -> Self {
+		Self {
+			hash_locks: HashLockIndex::shared(config.hash_lock_reuse),
+			liquidity_planner: LiquidityPlanner::shared(config.netting),
+			config,
+			chains: BTreeMap::new(),
+			routes: BTreeMap::new(),
+			hop_addresses: BTreeMap::new(),
+			compound_swaps: HashMap::new(),
+			hop_calls: FuturesUnordered::new(),
+			pending_events: VecDeque::new(),
+			at_capacity: BTreeSet::new(),
+			waker: AtomicWaker::new(),
+		}
+	}
+
+	pub fn add_chain(
+		&mut self,
+		chain: impl Into<ChainId>,
+		service: B,
+		config: ChainConfig,
+	) -> Result<(), BridgeRouterError> {
+		let chain = chain.into();
+		if self.chains.contains_key(&chain) {
+			return Err(BridgeRouterError::DuplicateChain(chain));
+		}
+		let calls = CallLimiter::shared(config.call_limit);
+		self.chains
+			.insert(chain, RouterChain { service, config, calls, deferred: VecDeque::new() });
+		self.waker.wake();
+		Ok(())
+	}
+
+	/// Starts swapping along `route`. Both chains must be registered first.
+	pub fn enable_route(
+		&mut self,
+		route: Route,
+		config: RouteConfig,
+	) -> Result<(), BridgeRouterError> {
+		if route.from == route.to {
+			return Err(BridgeRouterError::SameChain(route));
+		}
+		if self.routes.contains_key(&route) {
+			return Err(BridgeRouterError::DuplicateRoute(route));
+		}
+		let from =
+			self.chains.get(&route.from).ok_or(BridgeRouterError::UnknownChain(route.from))?;
+		let to = self.chains.get(&route.to).ok_or(BridgeRouterError::UnknownChain(route.to))?;
+		let active_swaps = ActiveSwapMap::build(
+			from.service.initiator_contract().clone(),
+			to.service.counterparty_contract().clone(),
+			self.config.active_swap.clone(),
+			config.assets,
+			self.config.time_lock_policy,
+			from.config.clone(),
+			to.config.clone(),
+		)
+		.with_hash_lock_index(self.hash_locks.clone())
+		.with_call_limiters(from.calls.clone(), to.calls.clone())
+		.with_liquidity_planner(self.liquidity_planner.clone(), route.from, route.to)
+		.with_quote_config(config.quotes)
+		.with_relay_config(config.relay)
+		.with_max_active_swaps(config.max_active_swaps);
+		self.routes.insert(route, active_swaps);
+		self.waker.wake();
+		Ok(())
+	}
+
+	pub fn chain(&self, chain: ChainId) -> Option<&B> {
+		self.chains.get(&chain).map(|chain| &chain.service)
+	}
+
+	pub fn chains(&self) -> impl Iterator<Item = ChainId> + '_ {
+		self.chains.keys().copied()
+	}
+
+	pub fn routes(&self) -> impl Iterator<Item = Route> + '_ {
+		self.routes.keys().copied()
+	}
+
+	pub fn active_swaps(&self, route: Route) -> Option<&ActiveSwapMap<B, B>> {
+		self.routes.get(&route)
+	}
+
+	pub fn active_swaps_mut(&mut self, route: Route) -> Option<&mut ActiveSwapMap<B, B>> {
+		self.routes.get_mut(&route)
+	}
+
+	/// Pending flows of all routes, by chain id.
+	pub fn liquidity_planner(&self) -> &SharedLiquidityPlanner {
+		&self.liquidity_planner
+	}
+
+	/// Contract calls queued and in flight on the chain.
+	pub fn calls(&self, chain: ChainId) -> Option<&SharedCallLimiter> {
+		self.chains.get(&chain).map(|chain| &chain.calls)
+	}
+
+	/// Whether new transfers out of `chain` are held back, as some route out of it is at capacity.
+	/// Their route is only known once they are taken.
+	fn is_saturated(&self, chain: ChainId) -> bool {
+		self.routes.iter().any(|(route, active_swaps)| {
+			route.from == chain && active_swaps.capacity().is_reached()
+		})
+	}
+
+	/// Reports routes that reached or freed capacity since the last call.
+	fn update_capacity(&mut self) {
+		for (route, active_swaps) in self.routes.iter() {
+			let capacity = active_swaps.capacity();
+			if self.at_capacity.contains(route) == capacity.is_reached() {
+				continue;
+			}
+			let event = if capacity.is_reached() {
+				warn!("BridgeRouter: Route {:?} at capacity {:?}", route, capacity);
+				self.at_capacity.insert(*route);
+				RouterEvent::CapacityReached(*route, capacity)
+			} else {
+				trace!("BridgeRouter: Route {:?} capacity freed {:?}", route, capacity);
+				self.at_capacity.remove(route);
+				RouterEvent::CapacityFreed(*route, capacity)
+			};
+			self.pending_events.push_back(event);
+		}
+	}
+
+	/// Route of a transfer initiated on `from`, `None` when it cannot be routed.
+	pub fn route_for(
+		&self,
+		from: ChainId,
+		details: &BridgeTransferDetails<B::Address, B::Hash>,
+	) -> Option<Route> {
+		match details.destination_chain {
+			Some(to) => Some(Route { from, to }).filter(|route| self.routes.contains_key(route)),
+			None => {
+				let mut routes = self.routes.keys().filter(|route| route.from == from);
+				match (routes.next(), routes.next()) {
+					(Some(route), None) => Some(*route),
+					_ => None,
+				}
+			}
+		}
+	}
+
+	/// Route whose swap took on the transfer `bridge_transfer_id` initiated on `from`.
+	fn route_for_initiator(
+		&self,
+		from: ChainId,
+		bridge_transfer_id: &BridgeTransferId<B::Hash>,
+	) -> Option<Route> {
+		self.routes
+			.iter()
+			.find(|(route, active_swaps)| {
+				route.from == from && active_swaps.already_executing(bridge_transfer_id)
+			})
+			.map(|(route, _)| *route)
+	}
+
+	/// Route whose swap placed the counterparty lock of `event` on `to`. Falls back to any route
+	/// into `to`, which then reports the lock as unknown.
+	fn route_for_counterparty(
+		&self,
+		to: ChainId,
+		event: &BridgeContractCounterpartyEvent<B::Address, B::Hash>,
+	) -> Option<Route> {
+		let mut routes = self.routes.iter().filter(|(route, _)| route.to == to);
+		let fallback = routes.clone().next().map(|(route, _)| *route);
+		routes
+			.find(|(_, active_swaps)| active_swaps.initiator_id_for_event(event).is_some())
+			.map(|(route, _)| *route)
+			.or(fallback)
+	}
+}
+
+type RouterPoll<B> =
+	Poll<Option<RouterEvent<<B as BlockchainService>::Address, <B as BlockchainService>::Hash>>>;
+
+impl<B> BridgeRouter<B>
+where
+	B: BlockchainService + 'static,
+	B::Hash: TryConvert<B::Hash> + TryConvert<Vec<u8>>,
+	Vec<u8>: From<B::Address>,
+{
+	fn handle_initiator_event(
+		&mut self,
+		chain: ChainId,
+		event: BridgeContractInitiatorEvent<B::Address, B::Hash>,
+	) -> Option<RouterEvent<B::Address, B::Hash>> {
+		let BridgeContractInitiatorEvent::Initiated(ref details) = event else {
+			self.on_initiator_event(chain, &event);
+			let Some(route) = self.route_for_initiator(chain, event.bridge_transfer_id()) else {
+				return Some(RouterEvent::Initiator(chain, IEvent::ContractEvent(event)));
+			};
+			let active_swaps = self.routes.get_mut(&route)?;
+			return match handle_initiator_event::<B, B>(event, active_swaps)? {
+				HandleActiveSwapEvent::InitiatorEvent(event) => {
+					Some(RouterEvent::Initiator(route.from, event))
+				}
+				HandleActiveSwapEvent::CounterpartyEvent(event) => {
+					Some(RouterEvent::Counterparty(route.to, event))
+				}
+			};
+		};
+		if let Some(source_id) = self.second_leg_of(chain, details) {
+			return self.start_second_leg(source_id, details.clone());
+		}
+		let via = details.destination_chain.and_then(|to| self.via_for(chain, to));
+		let Some(route) = self.route_for(chain, details) else {
+			if let Some(via) = via {
+				return self.start_compound_swap(chain, via, details.clone());
+			}
+			warn!(
+				"BridgeRouter: No route from chain {:?} for bridge transfer {:?} to {:?}",
+				chain, details.bridge_transfer_id, details.destination_chain
+			);
+			return Some(RouterEvent::Initiator(
+				chain,
+				IEvent::Warn(IWarn::NoRoute(details.clone())),
+			));
+		};
+		let active_swaps = self.routes.get_mut(&route)?;
+		match handle_initiator_event::<B, B>(event, active_swaps)? {
+			HandleActiveSwapEvent::InitiatorEvent(event) => {
+				Some(RouterEvent::Initiator(route.from, event))
+			}
+			HandleActiveSwapEvent::CounterpartyEvent(event) => {
+				Some(RouterEvent::Counterparty(route.to, event))
+			}
+		}
+	}
+
+	fn handle_counterparty_event(
+		&mut self,
+		chain: ChainId,
+		event: BridgeContractCounterpartyEvent<B::Address, B::Hash>,
+	) -> Option<RouterEvent<B::Address, B::Hash>> {
+		let Some(route) = self.route_for_counterparty(chain, &event) else {
+			warn!(
+				"BridgeRouter: Ignoring counterparty event {:?}, no route into chain {:?}",
+				event, chain
+			);
+			return Some(RouterEvent::Counterparty(
+				chain,
+				CEvent::Warn(CWarn::DirectionDisabled(event)),
+			));
+		};
+		let active_swaps = self.routes.get_mut(&route)?;
+		let initiator_id = active_swaps.initiator_id_for_event(&event).cloned();
+		let event = handle_counterparty_event::<B, B>(event, active_swaps)?;
+		if let (Some(initiator_id), CEvent::ContractEvent(event)) = (&initiator_id, &event) {
+			self.on_counterparty_event(route, initiator_id, event);
+		}
+		Some(RouterEvent::Counterparty(chain, event))
+	}
+
+	/// Follows an event of the active swaps of `route` that concerns compound swaps.
+	fn on_active_swap_event(&mut self, route: Route, event: &CEvent<B::Address, B::Hash>) {
+		match event {
+			CEvent::Warn(
+				CWarn::LockingAbortedTooManyAttempts(counterparty_id)
+				| CWarn::LockingExpired(counterparty_id, _),
+			)
+			| CEvent::LockingCancelled(counterparty_id) => self.on_locking_aborted(route, counterparty_id),
+			CEvent::LockAborted(counterparty_id) => {
+				self.on_lock_aborted(route, counterparty_id, true)
+			}
+			CEvent::Warn(CWarn::AbortingAbortedTooManyAttempts(counterparty_id)) => {
+				self.on_lock_aborted(route, counterparty_id, false)
+			}
+			_ => {}
+		}
+	}
+
+	/// Polls the next event of `chain`. While the chain is saturated, `Initiated` events of new
+	/// transfers are held back and replayed in order once it is not. Other events keep flowing,
+	/// so the swaps in flight still complete or are refunded.
+	fn poll_chain_event(
+		&mut self,
+		chain: ChainId,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<ContractEvent<B::Address, B::Hash>>> {
+		let saturated = self.is_saturated(chain);
+		let Some(router_chain) = self.chains.get_mut(&chain) else {
+			return Poll::Pending;
+		};
+		if !saturated {
+			if let Some(event) = router_chain.deferred.pop_front() {
+				// The service is not polled, so nothing else wakes the router for the next one
+				cx.waker().wake_by_ref();
+				return Poll::Ready(Some(ContractEvent::InitiatorEvent(event)));
+			}
+		}
+		loop {
+			match router_chain.service.poll_next_unpin(cx) {
+				Poll::Ready(Some(ContractEvent::InitiatorEvent(
+					event @ BridgeContractInitiatorEvent::Initiated(_),
+				))) if saturated => {
+					trace!(
+						"BridgeRouter: Holding back {:?} until chain {:?} is not saturated",
+						event,
+						chain
+					);
+					router_chain.deferred.push_back(event);
+				}
+				event => return event,
+			}
+		}
+	}
+
+	fn poll_router(&mut self, cx: &mut Context<'_>) -> RouterPoll<B> {
+		while let Poll::Ready(Some((source_id, call, result))) = self.hop_calls.poll_next_unpin(cx)
+		{
+			self.on_hop_call(source_id, call, result);
+		}
+		if let Some(event) = self.pending_events.pop_front() {
+			return Poll::Ready(Some(event));
+		}
+
+		let mut active_swap_event = None;
+		for (route, active_swaps) in self.routes.iter_mut() {
+			if let Some(event) = handle_active_swap_event::<B, B>(active_swaps.poll_next_unpin(cx))
+			{
+				active_swap_event = Some((*route, event));
+				break;
+			}
+		}
+		match active_swap_event {
+			Some((route, HandleActiveSwapEvent::InitiatorEvent(event))) => {
+				return Poll::Ready(Some(RouterEvent::Initiator(route.from, event)));
+			}
+			Some((route, HandleActiveSwapEvent::CounterpartyEvent(event))) => {
+				self.on_active_swap_event(route, &event);
+				return Poll::Ready(Some(RouterEvent::Counterparty(route.to, event)));
+			}
+			None => {}
+		}
+
+		// Report capacity freed by finished swaps before taking on new transfers
+		self.update_capacity();
+		if let Some(event) = self.pending_events.pop_front() {
+			return Poll::Ready(Some(event));
+		}
+
+		let chains: Vec<ChainId> = self.chains.keys().copied().collect();
+		for chain in chains {
+			let (event, ready) = match self.poll_chain_event(chain, cx) {
+				Poll::Ready(Some(ContractEvent::InitiatorEvent(event))) => {
+					trace!("BridgeRouter: Initiator event from chain {:?}: {:?}", chain, event);
+					(self.handle_initiator_event(chain, event), true)
+				}
+				Poll::Ready(Some(ContractEvent::CounterpartyEvent(event))) => {
+					trace!("BridgeRouter: Counterparty event from chain {:?}: {:?}", chain, event);
+					(self.handle_counterparty_event(chain, event), true)
+				}
+				Poll::Ready(None) => {
+					trace!("BridgeRouter: Chain {:?} has no more events", chain);
+					(None, false)
+				}
+				Poll::Pending => (None, false),
+			};
+			match event {
+				Some(event) => return Poll::Ready(Some(event)),
+				// The chain may have more events waiting
+				None if ready => cx.waker().wake_by_ref(),
+				None => {}
+			}
+		}
+
+		Poll::Pending
+	}
+}
+
+impl<B> Stream for BridgeRouter<B>
+where
+	B: BlockchainService + 'static,
+	B::Hash: TryConvert<B::Hash> + TryConvert<Vec<u8>>,
+	Vec<u8>: From<B::Address>,
+{
+	type Item = RouterEvent<B::Address, B::Hash>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		this.waker.register(cx.waker());
+		let event = this.poll_router(cx);
+		this.update_capacity();
+		match event {
+			Poll::Pending => match this.pending_events.pop_front() {
+				Some(event) => Poll::Ready(Some(event)),
+				None => Poll::Pending,
+			},
+			event => event,
+		}
+	}
+}
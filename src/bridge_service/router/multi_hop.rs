@@ -0,0 +1,612 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futures::{future::BoxFuture, Future, FutureExt};
+use futures_time::future::FutureExt as TimeoutFutureExt;
+use futures_timer::Delay;
+use thiserror::Error;
+use tracing::{trace, warn};
+
+use super::{BridgeRouter, Route, RouterEvent};
+use crate::{
+	blockchain_service::BlockchainService,
+	bridge_contracts::{
+		BridgeContractCounterparty, BridgeContractCounterpartyError, BridgeContractInitiator,
+		BridgeContractInitiatorError,
+	},
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::{ActiveSwapConfig, ActiveSwapMapError},
+		events::IEvent,
+		handle_initiator_event, HandleActiveSwapEvent,
+	},
+	chain_clock::ChainClock,
+	types::{
+		BridgeTransferDetails, BridgeTransferId, ChainId, HashLockPreImage, InitiatorAddress,
+		LockDetails, RecipientAddress, TimeLock, TryConvert,
+	},
+};
+
+/// Single status of a swap routed through an intermediate chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompoundSwapStatus {
+	/// The bridge locks the first leg on the intermediate chain, for the hop address.
+	LockingFirstLeg,
+	/// The hop address initiates the second leg on the intermediate chain.
+	InitiatingSecondLeg,
+	LockingSecondLeg,
+	/// Both legs are locked, the recipient can claim on the destination chain.
+	Locked,
+	/// The recipient claimed, the hop address claims the first leg with the revealed secret.
+	Completing,
+	Completed,
+	/// The second leg failed, the first leg lock is aborted and the second leg refunded once its
+	/// time lock expires.
+	Unwinding,
+	Unwound,
+	/// A contract call of the swap failed for good and needs manual intervention.
+	Failed,
+}
+
+impl CompoundSwapStatus {
+	pub fn is_final(&self) -> bool {
+		matches!(self, Self::Completed | Self::Unwound | Self::Failed)
+	}
+}
+
+/// Swap from `source` to `destination` made of two legs sharing one hash lock, from `source` to
+/// `via` and on from `via` to `destination`. The hop address of `via` receives the first leg and
+/// initiates the second one, so the time locks step down by the safety margin at each hop.
+#[derive(Debug, Clone)]
+pub struct CompoundSwap<A, H> {
+	pub source: ChainId,
+	pub via: ChainId,
+	pub destination: ChainId,
+	/// Transfer initiated on the source chain.
+	pub details: BridgeTransferDetails<A, H>,
+	/// Lock of the first leg on the intermediate chain, held for the hop address.
+	pub first_leg_lock: Option<LockDetails<A, H>>,
+	/// Second leg, initiated by the hop address on the intermediate chain.
+	pub second_leg: Option<BridgeTransferId<H>>,
+	pub status: CompoundSwapStatus,
+	/// Time lock of the second leg, resolved against the intermediate chain clock when there is
+	/// one. The second leg is refunded once it expires.
+	second_leg_time_lock: Option<TimeLock>,
+	first_leg_unwound: bool,
+	second_leg_unwound: bool,
+}
+
+#[derive(Debug, Error, Clone)]
+pub enum HopCallError {
+	#[error("Timeout while performing contract call")]
+	ContractCallTimeoutError,
+	#[error(transparent)]
+	InitiatorError(#[from] BridgeContractInitiatorError),
+	#[error(transparent)]
+	CounterpartyError(#[from] BridgeContractCounterpartyError),
+}
+
+/// Contract calls the router makes on behalf of a hop address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum HopCall {
+	/// Initiates the second leg.
+	Initiate,
+	/// Claims the first leg lock.
+	Claim,
+	/// Refunds the second leg.
+	Refund,
+}
+
+pub(super) type CompoundSwaps<B> = HashMap<
+	BridgeTransferId<<B as BlockchainService>::Hash>,
+	CompoundSwap<<B as BlockchainService>::Address, <B as BlockchainService>::Hash>,
+>;
+
+pub(super) type HopCallFuture<H> =
+	BoxFuture<'static, (BridgeTransferId<H>, HopCall, Result<(), HopCallError>)>;
+
+/// Leg of a compound swap an active swap belongs to.
+enum Leg {
+	First,
+	Second,
+}
+
+/// Runs a contract call, retrying it up to `error_attempts` times when `retry` is set.
+async fn call_with_retries<F, Fut, E>(
+	config: ActiveSwapConfig,
+	retry: bool,
+	mut call: F,
+) -> Result<(), HopCallError>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<(), E>>,
+	HopCallError: From<E>,
+{
+	let mut attempts = 0;
+	loop {
+		let error = match call().timeout(Delay::new(config.contract_call_timeout)).await {
+			Ok(Ok(())) => return Ok(()),
+			Ok(Err(error)) => HopCallError::from(error),
+			Err(_) => HopCallError::ContractCallTimeoutError,
+		};
+		if !retry || attempts >= config.error_attempts {
+			return Err(error);
+		}
+		warn!("BridgeRouter: Hop call failed: {}, attempts: {}", error, attempts);
+		attempts += 1;
+		Delay::new(config.error_delay).await;
+	}
+}
+
+/// Waits until `time_lock` expires on a chain, by its clock when it has one. Relative time locks
+/// are taken as set just now, and absolute ones are not waited for without a clock.
+async fn expiry(time_lock: TimeLock, clock: Option<Arc<dyn ChainClock>>) {
+	loop {
+		let remaining = match (&time_lock, &clock) {
+			(time_lock, Some(clock)) => {
+				let now = clock.now();
+				if time_lock.is_expired(&now) {
+					return;
+				}
+				time_lock.time_remaining(&now, clock.block_time())
+			}
+			(TimeLock::Relative(duration), None) => {
+				Delay::new(*duration).await;
+				return;
+			}
+			(_, None) => return,
+		};
+		// The chain clock may lag behind, so the expiry is checked again
+		Delay::new(remaining).await;
+	}
+}
+
+impl<B> BridgeRouter<B>
+where
+	B: BlockchainService + 'static,
+	B::Hash: TryConvert<B::Hash> + TryConvert<Vec<u8>>,
+	Vec<u8>: From<B::Address>,
+{
+	/// Lets transfers be routed through `chain`, which receives the first leg at `address` and
+	/// initiates the second leg from it. The bridge must control the address on `chain`.
+	pub fn set_hop_address(&mut self, chain: impl Into<ChainId>, address: B::Address) {
+		self.hop_addresses.insert(chain.into(), address);
+	}
+
+	pub fn compound_swap(
+		&self,
+		source_id: &BridgeTransferId<B::Hash>,
+	) -> Option<&CompoundSwap<B::Address, B::Hash>> {
+		self.compound_swaps.get(source_id)
+	}
+
+	/// Intermediate chain with a hop address and routes from `from` and to `to`.
+	pub fn via_for(&self, from: ChainId, to: ChainId) -> Option<ChainId> {
+		self.hop_addresses.keys().copied().find(|via| {
+			self.routes.contains_key(&Route { from, to: *via })
+				&& self.routes.contains_key(&Route { from: *via, to })
+		})
+	}
+
+	/// Compound swap, by source transfer id, of the active swap `initiator_id` of `route`.
+	fn compound_swap_of(
+		&self,
+		route: Route,
+		initiator_id: &BridgeTransferId<B::Hash>,
+	) -> Option<(BridgeTransferId<B::Hash>, Leg)> {
+		if let Some(swap) = self.compound_swaps.get(initiator_id) {
+			if route == (Route { from: swap.source, to: swap.via }) {
+				return Some((initiator_id.clone(), Leg::First));
+			}
+		}
+		self.compound_swaps
+			.iter()
+			.find(|(_, swap)| {
+				route == (Route { from: swap.via, to: swap.destination })
+					&& swap.second_leg.as_ref() == Some(initiator_id)
+			})
+			.map(|(source_id, _)| (source_id.clone(), Leg::Second))
+	}
+
+	fn set_compound_status(
+		&mut self,
+		source_id: &BridgeTransferId<B::Hash>,
+		status: CompoundSwapStatus,
+	) {
+		let Some(swap) = self.compound_swaps.get_mut(source_id) else {
+			return;
+		};
+		trace!("BridgeRouter: Compound swap {:?} is {:?}", source_id, status);
+		swap.status = status;
+		self.pending_events.push_back(RouterEvent::CompoundSwap(
+			swap.source,
+			source_id.clone(),
+			status,
+		));
+		if status.is_final() {
+			self.compound_swaps.remove(source_id);
+		}
+	}
+
+	/// Starts the first leg of a transfer initiated on `source` for `via`'s hop address.
+	pub(super) fn start_compound_swap(
+		&mut self,
+		source: ChainId,
+		via: ChainId,
+		details: BridgeTransferDetails<B::Address, B::Hash>,
+	) -> Option<RouterEvent<B::Address, B::Hash>> {
+		let destination = details.destination_chain?;
+		let hop_address = self.hop_addresses.get(&via)?.clone();
+		let first_leg = BridgeTransferDetails {
+			recipient_address: RecipientAddress(Vec::from(hop_address)),
+			destination_chain: Some(via),
+			..details.clone()
+		};
+		let active_swaps = self.routes.get_mut(&Route { from: source, to: via })?;
+		let event = handle_initiator_event::<B, B>(
+			BridgeContractInitiatorEvent::Initiated(first_leg),
+			active_swaps,
+		)?;
+		match event {
+			HandleActiveSwapEvent::InitiatorEvent(IEvent::ContractEvent(_)) => {
+				trace!(
+					"BridgeRouter: Routing bridge transfer {:?} from {:?} through {:?} to {:?}",
+					details.bridge_transfer_id,
+					source,
+					via,
+					destination
+				);
+				let source_id = details.bridge_transfer_id.clone();
+				self.compound_swaps.insert(
+					source_id.clone(),
+					CompoundSwap {
+						source,
+						via,
+						destination,
+						details: details.clone(),
+						first_leg_lock: None,
+						second_leg: None,
+						status: CompoundSwapStatus::LockingFirstLeg,
+						second_leg_time_lock: None,
+						first_leg_unwound: false,
+						second_leg_unwound: false,
+					},
+				);
+				self.set_compound_status(&source_id, CompoundSwapStatus::LockingFirstLeg);
+				Some(RouterEvent::Initiator(
+					source,
+					IEvent::ContractEvent(BridgeContractInitiatorEvent::Initiated(details)),
+				))
+			}
+			HandleActiveSwapEvent::InitiatorEvent(event) => {
+				Some(RouterEvent::Initiator(source, event))
+			}
+			HandleActiveSwapEvent::CounterpartyEvent(event) => {
+				Some(RouterEvent::Counterparty(via, event))
+			}
+		}
+	}
+
+	/// Source transfer id of the compound swap whose second leg `details` is, if any.
+	pub(super) fn second_leg_of(
+		&self,
+		chain: ChainId,
+		details: &BridgeTransferDetails<B::Address, B::Hash>,
+	) -> Option<BridgeTransferId<B::Hash>> {
+		if self.hop_addresses.get(&chain) != Some(&details.initiator_address.0) {
+			return None;
+		}
+		self.compound_swaps
+			.iter()
+			.find(|(_, swap)| {
+				swap.via == chain
+					&& swap.status == CompoundSwapStatus::InitiatingSecondLeg
+					&& swap
+						.first_leg_lock
+						.as_ref()
+						.is_some_and(|lock| lock.hash_lock == details.hash_lock)
+			})
+			.map(|(source_id, _)| source_id.clone())
+	}
+
+	/// Locks the second leg, initiated by the hop address, on the destination chain.
+	pub(super) fn start_second_leg(
+		&mut self,
+		source_id: BridgeTransferId<B::Hash>,
+		details: BridgeTransferDetails<B::Address, B::Hash>,
+	) -> Option<RouterEvent<B::Address, B::Hash>> {
+		let swap = self.compound_swaps.get_mut(&source_id)?;
+		let via = self.chains.get(&swap.via)?;
+		swap.second_leg = Some(details.bridge_transfer_id.clone());
+		swap.second_leg_time_lock = Some(match &via.config.clock {
+			Some(clock) => details.time_lock.resolve(&clock.now()),
+			None => details.time_lock.clone(),
+		});
+		let route = Route { from: swap.via, to: swap.destination };
+		let active_swaps = self.routes.get_mut(&route)?;
+		match active_swaps.start_next_hop(details.clone(), source_id.0.as_ref()) {
+			Ok(()) => self.set_compound_status(&source_id, CompoundSwapStatus::LockingSecondLeg),
+			Err(error) => {
+				warn!(
+					"BridgeRouter: Second leg {:?} of compound swap {:?} rejected: {}",
+					details.bridge_transfer_id, source_id, error
+				);
+				self.unwind(&source_id);
+			}
+		}
+		Some(RouterEvent::Initiator(
+			route.from,
+			IEvent::ContractEvent(BridgeContractInitiatorEvent::Initiated(details)),
+		))
+	}
+
+	/// Follows a counterparty event of `route`, already processed by its active swaps.
+	pub(super) fn on_counterparty_event(
+		&mut self,
+		route: Route,
+		initiator_id: &BridgeTransferId<B::Hash>,
+		event: &BridgeContractCounterpartyEvent<B::Address, B::Hash>,
+	) {
+		let Some((source_id, leg)) = self.compound_swap_of(route, initiator_id) else {
+			return;
+		};
+		match (leg, event) {
+			(Leg::First, BridgeContractCounterpartyEvent::Locked(lock)) => {
+				let Some(swap) = self.compound_swaps.get_mut(&source_id) else {
+					return;
+				};
+				swap.first_leg_lock = Some(lock.clone());
+				self.initiate_second_leg(&source_id);
+			}
+			(Leg::Second, BridgeContractCounterpartyEvent::Locked(_)) => {
+				self.set_compound_status(&source_id, CompoundSwapStatus::Locked);
+			}
+			(Leg::Second, BridgeContractCounterpartyEvent::Completed(details)) => {
+				self.set_compound_status(&source_id, CompoundSwapStatus::Completing);
+				self.claim_first_leg(&source_id, details.secret.clone());
+			}
+			// The hop address claimed the first leg, which completes on the source chain
+			(Leg::First, BridgeContractCounterpartyEvent::Completed(_)) => {}
+		}
+	}
+
+	/// Follows an initiator event of `chain` that is not an initiated transfer.
+	pub(super) fn on_initiator_event(
+		&mut self,
+		chain: ChainId,
+		event: &BridgeContractInitiatorEvent<B::Address, B::Hash>,
+	) {
+		if let BridgeContractInitiatorEvent::Completed(bridge_transfer_id) = event {
+			if self.compound_swaps.get(bridge_transfer_id).is_some_and(|swap| swap.source == chain)
+			{
+				self.set_compound_status(bridge_transfer_id, CompoundSwapStatus::Completed);
+			}
+		}
+	}
+
+	/// Follows the failure of a lock of `route`, reported under its proposed counterparty id.
+	pub(super) fn on_locking_aborted(
+		&mut self,
+		route: Route,
+		counterparty_id: &BridgeTransferId<B::Hash>,
+	) {
+		let Some(initiator_id) = self
+			.routes
+			.get(&route)
+			.and_then(|active_swaps| active_swaps.initiator_bridge_transfer_id(counterparty_id))
+			.cloned()
+		else {
+			return;
+		};
+		match self.compound_swap_of(route, &initiator_id) {
+			// Nothing is locked yet, the initiator refunds on the source chain
+			Some((source_id, Leg::First)) => {
+				self.set_compound_status(&source_id, CompoundSwapStatus::Failed)
+			}
+			Some((source_id, Leg::Second)) => self.unwind(&source_id),
+			None => {}
+		}
+	}
+
+	/// Follows the abort of a lock of `route`, `aborted` is unset when it was given up on.
+	pub(super) fn on_lock_aborted(
+		&mut self,
+		route: Route,
+		counterparty_id: &BridgeTransferId<B::Hash>,
+		aborted: bool,
+	) {
+		let Some(initiator_id) = self
+			.routes
+			.get(&route)
+			.and_then(|active_swaps| active_swaps.initiator_bridge_transfer_id(counterparty_id))
+			.cloned()
+		else {
+			return;
+		};
+		let Some((source_id, Leg::First)) = self.compound_swap_of(route, &initiator_id) else {
+			return;
+		};
+		if !aborted {
+			self.set_compound_status(&source_id, CompoundSwapStatus::Failed);
+			return;
+		}
+		if let Some(swap) = self.compound_swaps.get_mut(&source_id) {
+			swap.first_leg_unwound = true;
+		}
+		self.check_unwound(&source_id);
+	}
+
+	pub(super) fn on_hop_call(
+		&mut self,
+		source_id: BridgeTransferId<B::Hash>,
+		call: HopCall,
+		result: Result<(), HopCallError>,
+	) {
+		match (call, result) {
+			(HopCall::Initiate, Ok(())) | (HopCall::Claim, Ok(())) => {}
+			(HopCall::Refund, Ok(())) => {
+				if let Some(swap) = self.compound_swaps.get_mut(&source_id) {
+					swap.second_leg_unwound = true;
+				}
+				self.check_unwound(&source_id);
+			}
+			(HopCall::Initiate, Err(error)) => {
+				warn!(
+					"BridgeRouter: Initiating the second leg of compound swap {:?} failed: {}",
+					source_id, error
+				);
+				self.unwind(&source_id);
+			}
+			(call, Err(error)) => {
+				warn!(
+					"BridgeRouter: {:?} of compound swap {:?} failed for good: {}",
+					call, source_id, error
+				);
+				self.set_compound_status(&source_id, CompoundSwapStatus::Failed);
+			}
+		}
+	}
+
+	fn initiate_second_leg(&mut self, source_id: &BridgeTransferId<B::Hash>) {
+		let Some(swap) = self.compound_swaps.get(source_id) else {
+			return;
+		};
+		let (Some(lock), Some(hop_address), Some(via)) = (
+			swap.first_leg_lock.clone(),
+			self.hop_addresses.get(&swap.via).cloned(),
+			self.chains.get(&swap.via),
+		) else {
+			return;
+		};
+		// The second leg expires a safety margin before the first leg lock, leaving the hop time to
+		// claim the first leg once the secret is revealed
+		let time_lock = match self.config.time_lock_policy.counterparty_time_lock(
+			&lock.time_lock,
+			&via.config,
+			&via.config,
+		) {
+			Ok(time_lock) => time_lock,
+			Err(error) => {
+				warn!(
+					"BridgeRouter: Second leg of compound swap {:?} cannot be time locked: {}",
+					source_id, error
+				);
+				self.unwind(source_id);
+				return;
+			}
+		};
+		let contract = via.service.initiator_contract().clone();
+		let recipient = swap.details.recipient_address.clone();
+		let counterparty_hash_lock = swap.details.counterparty_hash_lock.clone();
+		let destination = swap.destination;
+		// Initiating is not retried, a call that went through despite an error would lock twice
+		let call = call_with_retries(self.config.active_swap.clone(), false, move || {
+			let mut contract = contract.clone();
+			let lock = lock.clone();
+			let initiator = InitiatorAddress(hop_address.clone());
+			let recipient = recipient.clone();
+			let counterparty_hash_lock = counterparty_hash_lock.clone();
+			let time_lock = time_lock.clone();
+			async move {
+				contract
+					.initiate_bridge_transfer(
+						initiator,
+						recipient,
+						lock.hash_lock,
+						counterparty_hash_lock,
+						time_lock,
+						lock.asset,
+						lock.amount,
+						Some(destination),
+					)
+					.await
+			}
+		});
+		self.spawn_hop_call(source_id, HopCall::Initiate, call);
+		self.set_compound_status(source_id, CompoundSwapStatus::InitiatingSecondLeg);
+	}
+
+	fn claim_first_leg(&mut self, source_id: &BridgeTransferId<B::Hash>, secret: HashLockPreImage) {
+		let Some(swap) = self.compound_swaps.get(source_id) else {
+			return;
+		};
+		let (Some(lock), Some(via)) = (swap.first_leg_lock.as_ref(), self.chains.get(&swap.via))
+		else {
+			return;
+		};
+		let contract = via.service.counterparty_contract().clone();
+		let lock_id = lock.bridge_transfer_id.clone();
+		let call = call_with_retries(self.config.active_swap.clone(), true, move || {
+			let mut contract = contract.clone();
+			let lock_id = lock_id.clone();
+			let secret = secret.clone();
+			async move { contract.complete_bridge_transfer(lock_id, secret).await }
+		});
+		self.spawn_hop_call(source_id, HopCall::Claim, call);
+	}
+
+	/// Aborts the first leg lock and refunds the second leg, if it was initiated, once its time lock
+	/// expires on the intermediate chain.
+	fn unwind(&mut self, source_id: &BridgeTransferId<B::Hash>) {
+		let Some(swap) = self.compound_swaps.get_mut(source_id) else {
+			return;
+		};
+		let route = Route { from: swap.source, to: swap.via };
+		let second_leg = swap.second_leg.clone();
+		let time_lock = swap.second_leg_time_lock.clone();
+		swap.second_leg_unwound = second_leg.is_none();
+		let via = swap.via;
+		self.set_compound_status(source_id, CompoundSwapStatus::Unwinding);
+
+		let aborted = match self.routes.get_mut(&route) {
+			Some(active_swaps) => active_swaps.abort_bridge_transfer(source_id),
+			None => Err(ActiveSwapMapError::NonExistingSwap),
+		};
+		if let Err(error) = aborted {
+			warn!(
+				"BridgeRouter: Cannot abort the first leg of compound swap {:?}: {}",
+				source_id, error
+			);
+			self.set_compound_status(source_id, CompoundSwapStatus::Failed);
+			return;
+		}
+
+		if let (Some(second_leg), Some(time_lock), Some(via)) =
+			(second_leg, time_lock, self.chains.get(&via))
+		{
+			let contract = via.service.initiator_contract().clone();
+			let clock = via.config.clock.clone();
+			let call = call_with_retries(self.config.active_swap.clone(), true, move || {
+				let mut contract = contract.clone();
+				let second_leg = second_leg.clone();
+				async move { contract.refund_bridge_transfer(second_leg).await }
+			});
+			trace!("BridgeRouter: Refunding the second leg of {:?} at {:?}", source_id, time_lock);
+			self.spawn_hop_call(
+				source_id,
+				HopCall::Refund,
+				expiry(time_lock, clock).then(|()| call),
+			);
+		}
+	}
+
+	fn check_unwound(&mut self, source_id: &BridgeTransferId<B::Hash>) {
+		if self
+			.compound_swaps
+			.get(source_id)
+			.is_some_and(|swap| swap.first_leg_unwound && swap.second_leg_unwound)
+		{
+			self.set_compound_status(source_id, CompoundSwapStatus::Unwound);
+		}
+	}
+
+	fn spawn_hop_call(
+		&mut self,
+		source_id: &BridgeTransferId<B::Hash>,
+		call: HopCall,
+		future: impl Future<Output = Result<(), HopCallError>> + Send + 'static,
+	) {
+		let source_id = source_id.clone();
+		self.hop_calls.push(future.map(move |result| (source_id, call, result)).boxed());
+		self.waker.wake();
+	}
+}
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::{bridge_service::ChainConfig, chain_clock::ChainClock, types::TimeLock};
+
+/// Kind of time lock a chain's HTLC contracts accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeLockKind {
+	AbsoluteTimestamp,
+	AbsoluteHeight,
+	#[default]
+	Relative,
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TimeLockError {
+	#[error("Time lock of {time_lock:?} does not cover the safety margin of {safety_margin:?}")]
+	MarginNotMet { time_lock: Duration, safety_margin: Duration },
+	#[error("A chain clock is required to translate absolute time locks")]
+	MissingClock,
+}
+
+/// Derives the counterparty time lock from the initiator time lock. The counterparty lock expires
+/// `safety_margin` before the initiator lock, leaving the bridge time to claim on the initiator
+/// chain once the secret is revealed. The margin should also absorb block time variance and
+/// clock drift between the chains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLockPolicy {
+	pub safety_margin: Duration,
+}
+
+impl Default for TimeLockPolicy {
+	fn default() -> Self {
+		Self { safety_margin: Duration::from_secs(60 * 60) }
+	}
+}
+
+impl TimeLockPolicy {
+	pub fn counterparty_time_lock(
+		&self,
+		time_lock: &TimeLock,
+		initiator: &ChainConfig,
+		counterparty: &ChainConfig,
+	) -> Result<TimeLock, TimeLockError> {
+		let remaining = match (time_lock, &initiator.clock) {
+			(TimeLock::Relative(duration), _) => *duration,
+			(time_lock, Some(clock)) => time_lock.time_remaining(&clock.now(), clock.block_time()),
+			(_, None) => return Err(TimeLockError::MissingClock),
+		};
+		let margin_not_met = || TimeLockError::MarginNotMet {
+			time_lock: remaining,
+			safety_margin: self.safety_margin,
+		};
+
+		let remaining = remaining
+			.checked_sub(self.safety_margin)
+			.filter(|remaining| !remaining.is_zero())
+			.ok_or_else(margin_not_met)?;
+
+		let clock = || counterparty.clock.as_deref().ok_or(TimeLockError::MissingClock);
+		match counterparty.time_lock_kind {
+			TimeLockKind::Relative => Ok(TimeLock::Relative(remaining)),
+			TimeLockKind::AbsoluteTimestamp => {
+				let now = clock()?.now();
+				Ok(TimeLock::AbsoluteTimestamp(now.timestamp.saturating_add(remaining.as_secs())))
+			}
+			TimeLockKind::AbsoluteHeight => {
+				let clock: &dyn ChainClock = clock()?;
+				// Partial blocks are dropped, so the lock never expires later than intended
+				let blocks = remaining.as_nanos() / clock.block_time().as_nanos().max(1);
+				if blocks == 0 {
+					return Err(margin_not_met());
+				}
+				let blocks = u64::try_from(blocks).unwrap_or(u64::MAX);
+				Ok(TimeLock::AbsoluteHeight(clock.now().height.saturating_add(blocks)))
+			}
+		}
+	}
+}
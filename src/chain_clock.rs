@@ -0,0 +1,48 @@
+use std::{
+	fmt::Debug,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+/// Height and block timestamp, in seconds since the unix epoch, of the latest block on a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ChainTime {
+	pub height: u64,
+	pub timestamp: u64,
+}
+
+/// Reports the current time of a chain, as seen by its HTLC contracts.
+pub trait ChainClock: Debug + Send + Sync {
+	fn now(&self) -> ChainTime;
+
+	/// Average time between blocks, used to estimate when a block height is reached.
+	fn block_time(&self) -> Duration;
+}
+
+/// Clock that is advanced explicitly, e.g. from the block stream of a chain. Clones share the
+/// same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+	now: Arc<Mutex<ChainTime>>,
+	block_time: Duration,
+}
+
+impl ManualClock {
+	pub fn new(now: ChainTime, block_time: Duration) -> Self {
+		Self { now: Arc::new(Mutex::new(now)), block_time }
+	}
+
+	pub fn set(&self, now: ChainTime) {
+		*self.now.lock().expect("lock poisoned") = now;
+	}
+}
+
+impl ChainClock for ManualClock {
+	fn now(&self) -> ChainTime {
+		*self.now.lock().expect("lock poisoned")
+	}
+
+	fn block_time(&self) -> Duration {
+		self.block_time
+	}
+}
@@ -0,0 +1,123 @@
+use std::fmt::Debug;
+
+use blake2::{digest::consts::U32, Blake2b};
+use sha2::Digest;
+use thiserror::Error;
+
+use crate::types::HashLockPreImage;
+
+/// Hash function a chain's HTLC contracts use to turn a pre-image into a hash lock.
+pub trait HashLockScheme: Debug + Send + Sync {
+	/// Identifies the hash function. Chains whose schemes share a name hash pre-images the same
+	/// way, so their hash locks can be converted into each other.
+	fn name(&self) -> &'static str;
+
+	fn digest(&self, pre_image: &HashLockPreImage) -> Vec<u8>;
+
+	/// Checks that `pre_image` unlocks the hash lock with the given bytes under this scheme.
+	fn verify(&self, pre_image: &HashLockPreImage, hash_lock: &[u8]) -> bool {
+		self.digest(pre_image) == hash_lock
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256;
+
+impl HashLockScheme for Sha256 {
+	fn name(&self) -> &'static str {
+		"sha256"
+	}
+
+	fn digest(&self, pre_image: &HashLockPreImage) -> Vec<u8> {
+		sha2::Sha256::digest(pre_image.expose_secret()).to_vec()
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keccak256;
+
+impl HashLockScheme for Keccak256 {
+	fn name(&self) -> &'static str {
+		"keccak256"
+	}
+
+	fn digest(&self, pre_image: &HashLockPreImage) -> Vec<u8> {
+		sha3::Keccak256::digest(pre_image.expose_secret()).to_vec()
+	}
+}
+
+/// BLAKE2b with a 256 bit digest.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Blake2b256;
+
+impl HashLockScheme for Blake2b256 {
+	fn name(&self) -> &'static str {
+		"blake2b256"
+	}
+
+	fn digest(&self, pre_image: &HashLockPreImage) -> Vec<u8> {
+		Blake2b::<U32>::digest(pre_image.expose_secret()).to_vec()
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SecretFormat {
+	/// Any bytes.
+	#[default]
+	Bytes,
+	/// Valid UTF-8, e.g. for contracts taking the pre-image as a string.
+	Utf8,
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SecretConstraintError {
+	#[error("Pre-image of {actual} bytes is shorter than the minimum of {min}")]
+	TooShort { min: usize, actual: usize },
+	#[error("Pre-image of {actual} bytes is longer than the maximum of {max}")]
+	TooLong { max: usize, actual: usize },
+	#[error("Pre-image is not in the {0:?} format")]
+	InvalidFormat(SecretFormat),
+}
+
+/// Pre-images a chain's HTLC contracts accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecretConstraints {
+	pub min_length: usize,
+	pub max_length: usize,
+	pub format: SecretFormat,
+}
+
+impl Default for SecretConstraints {
+	fn default() -> Self {
+		Self { min_length: 0, max_length: usize::MAX, format: SecretFormat::default() }
+	}
+}
+
+impl SecretConstraints {
+	pub fn check(&self, pre_image: &HashLockPreImage) -> Result<(), SecretConstraintError> {
+		let secret = pre_image.expose_secret();
+		if secret.len() < self.min_length {
+			return Err(SecretConstraintError::TooShort {
+				min: self.min_length,
+				actual: secret.len(),
+			});
+		}
+		if secret.len() > self.max_length {
+			return Err(SecretConstraintError::TooLong {
+				max: self.max_length,
+				actual: secret.len(),
+			});
+		}
+		if self.format == SecretFormat::Utf8 && std::str::from_utf8(secret).is_err() {
+			return Err(SecretConstraintError::InvalidFormat(self.format));
+		}
+		Ok(())
+	}
+
+	/// Whether every pre-image accepted under these constraints is accepted under `other` too.
+	pub fn is_within(&self, other: &SecretConstraints) -> bool {
+		self.min_length >= other.min_length
+			&& self.max_length <= other.max_length
+			&& (other.format == SecretFormat::Bytes || self.format == other.format)
+	}
+}
@@ -1,5 +1,10 @@
+pub mod address_codec;
 pub mod blockchain_service;
 pub mod bridge_contracts;
 pub mod bridge_monitoring;
 pub mod bridge_service;
+pub mod chain_clock;
+pub mod hash_lock;
+pub mod swap_client;
 pub mod types;
+pub mod watchtower;
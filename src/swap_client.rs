@@ -0,0 +1,595 @@
+use std::{
+	future::Future,
+	pin::Pin,
+	task::{ready, Context, Poll},
+	time::Duration,
+};
+
+use futures::{
+	channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+	FutureExt, StreamExt,
+};
+use futures_time::future::FutureExt as TimeoutFutureExt;
+use futures_timer::Delay;
+use rand::{CryptoRng, RngCore};
+use thiserror::Error;
+use tracing::{trace, warn};
+
+use crate::{
+	bridge_contracts::{
+		BridgeContractCounterparty, BridgeContractCounterpartyError, BridgeContractInitiator,
+		BridgeContractInitiatorError,
+	},
+	bridge_monitoring::{
+		BridgeContractCounterpartyEvent, BridgeContractCounterpartyMonitoring,
+		BridgeContractInitiatorEvent, BridgeContractInitiatorMonitoring,
+	},
+	bridge_service::{
+		active_swap::{ActiveSwapConfig, BoxedFuture},
+		anomaly::SwapAnomaly,
+		quote::Quote,
+		ChainConfig,
+	},
+	types::{
+		Amount, AssetId, BridgeTransferId, ChainId, ConversionError, HashLock, HashLockPreImage,
+		InitiatorAddress, LockDetails, RecipientAddress, TimeLock, TryConvert,
+	},
+};
+
+/// Length of the secrets `generate_secret` draws.
+pub const SECRET_LENGTH: usize = 32;
+
+/// Draws a secret to hash lock a swap with.
+pub fn generate_secret<R: RngCore + CryptoRng>(rng: &mut R) -> HashLockPreImage {
+	let mut secret = vec![0u8; SECRET_LENGTH];
+	rng.fill_bytes(&mut secret);
+	HashLockPreImage::new(secret)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SwapClientConfig {
+	/// The chain the user initiates on and is refunded on.
+	pub initiator_chain: ChainConfig,
+	/// The chain the user claims the bridge lock on.
+	pub counterparty_chain: ChainConfig,
+	/// Retries and timeout of the contract calls the client makes.
+	pub calls: ActiveSwapConfig,
+}
+
+/// A swap as the user asks for it. The secret has to be kept, with the state, to resume the swap.
+#[derive(Debug, Clone)]
+pub struct SwapRequest<A> {
+	pub initiator_address: InitiatorAddress<A>,
+	pub recipient_address: RecipientAddress<Vec<u8>>,
+	pub secret: HashLockPreImage,
+	pub time_lock: TimeLock,
+	pub asset: AssetId,
+	pub amount: Amount,
+	pub destination_chain: Option<ChainId>,
+	/// Quote the transfer is initiated under. The bridge lock has to meet its terms, or carry the
+	/// requested asset and amount without a quote.
+	pub quote: Option<Quote>,
+}
+
+/// Progress of a swap on the user side. Every state but `New` may be persisted and the swap
+/// resumed from it with `SwapClient::resume`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwapState<IH, CH> {
+	/// Nothing is initiated yet.
+	New,
+	/// The transfer is initiated, but not reported by the initiator monitoring yet. `time_lock`
+	/// is resolved against the initiator chain clock when there is one.
+	Initiating {
+		time_lock: TimeLock,
+	},
+	/// Waiting for the bridge to lock on the counterparty chain.
+	Initiated {
+		bridge_transfer_id: BridgeTransferId<IH>,
+		time_lock: TimeLock,
+	},
+	/// The bridge locked under `lock_id`, which is claimed with the secret.
+	Locked {
+		bridge_transfer_id: BridgeTransferId<IH>,
+		lock_id: BridgeTransferId<CH>,
+		time_lock: TimeLock,
+	},
+	/// The lock is claimed, the bridge completes the initiator side with the revealed secret.
+	Completed {
+		bridge_transfer_id: BridgeTransferId<IH>,
+		lock_id: BridgeTransferId<CH>,
+	},
+	/// The initiator time lock expired before the lock was claimed, the transfer is refunded.
+	/// The refund is retried until the initiator chain accepts it.
+	Refunding {
+		bridge_transfer_id: BridgeTransferId<IH>,
+		time_lock: TimeLock,
+	},
+	Refunded {
+		bridge_transfer_id: BridgeTransferId<IH>,
+	},
+}
+
+impl<IH, CH> SwapState<IH, CH> {
+	pub fn is_final(&self) -> bool {
+		matches!(self, Self::Completed { .. } | Self::Refunded { .. })
+	}
+
+	fn time_lock(&self) -> Option<&TimeLock> {
+		match self {
+			Self::Initiating { time_lock }
+			| Self::Initiated { time_lock, .. }
+			| Self::Locked { time_lock, .. }
+			| Self::Refunding { time_lock, .. } => Some(time_lock),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub enum SwapEvent<IH, CH> {
+	/// The swap moved to a new state.
+	State(SwapState<IH, CH>),
+	/// A contract call failed. It is retried while attempts remain.
+	CallError(SwapClientError),
+	/// A lock under the hash lock of the swap differs from the expected one. It is not claimed,
+	/// the swap waits for the refund.
+	LockRejected(BridgeTransferId<CH>, Vec<SwapAnomaly>),
+}
+
+pub type SwapEvents<IH, CH> = UnboundedReceiver<SwapEvent<IH, CH>>;
+
+type StartedSwap<'a, I, IM, C, CM> = (
+	Swap<'a, I, IM, C, CM>,
+	SwapEvents<<I as BridgeContractInitiator>::Hash, <C as BridgeContractCounterparty>::Hash>,
+);
+
+#[derive(Debug, Error, Clone)]
+pub enum SwapClientError {
+	#[error("Timeout while performing contract call")]
+	ContractCallTimeoutError,
+	#[error(transparent)]
+	InitiatorError(#[from] BridgeContractInitiatorError),
+	#[error(transparent)]
+	CounterpartyError(#[from] BridgeContractCounterpartyError),
+	#[error("Hash lock cannot be represented on the initiator chain: {0}")]
+	Conversion(#[from] ConversionError),
+	#[error("The initiator time lock expired before the transfer was reported")]
+	NotInitiated,
+	#[error("A chain clock is required to wait for absolute or resumed initiator time locks")]
+	MissingClock,
+	#[error("Monitoring stream ended")]
+	MonitoringEnded,
+}
+
+/// Drives swaps on the user side: initiates with a hash lock, claims the bridge lock with the
+/// secret, and refunds once the initiator time lock expires if the swap does not complete.
+pub struct SwapClient<I, IM, C, CM> {
+	pub config: SwapClientConfig,
+	initiator_contract: I,
+	initiator_monitoring: IM,
+	counterparty_contract: C,
+	counterparty_monitoring: CM,
+}
+
+impl<I, IM, C, CM> SwapClient<I, IM, C, CM>
+where
+	I: BridgeContractInitiator + 'static,
+	I::Hash: TryConvert<Vec<u8>>,
+	IM: BridgeContractInitiatorMonitoring<Address = I::Address, Hash = I::Hash>,
+	C: BridgeContractCounterparty + 'static,
+	CM: BridgeContractCounterpartyMonitoring<Address = C::Address, Hash = C::Hash>,
+{
+	pub fn new(
+		initiator_contract: I,
+		initiator_monitoring: IM,
+		counterparty_contract: C,
+		counterparty_monitoring: CM,
+		config: SwapClientConfig,
+	) -> Self {
+		Self {
+			config,
+			initiator_contract,
+			initiator_monitoring,
+			counterparty_contract,
+			counterparty_monitoring,
+		}
+	}
+
+	/// Starts a swap. The returned future resolves to the final state, while its progress is
+	/// reported on the returned events.
+	pub fn swap(&mut self, request: SwapRequest<I::Address>) -> StartedSwap<'_, I, IM, C, CM> {
+		self.resume(request, SwapState::New)
+	}
+
+	/// Resumes a swap from a persisted state. Monitoring events the client missed meanwhile are
+	/// not replayed, so a swap resumed while `Initiating` is only refunded if the transfer is
+	/// reported again. Without an initiator chain clock the start of a relative time lock is not
+	/// kept, so swaps resumed with one fail with `MissingClock`.
+	pub fn resume(
+		&mut self,
+		request: SwapRequest<I::Address>,
+		state: SwapState<I::Hash, C::Hash>,
+	) -> StartedSwap<'_, I, IM, C, CM> {
+		let hash_lock = self.config.initiator_chain.hash_lock_scheme.digest(&request.secret);
+		let counterparty_hash_lock =
+			self.config.counterparty_chain.hash_lock_scheme.digest(&request.secret);
+		let (events, receiver) = mpsc::unbounded();
+		let mut swap = Swap {
+			client: self,
+			request,
+			hash_lock,
+			counterparty_hash_lock,
+			state,
+			call: None,
+			attempts: 0,
+			retry: None,
+			awaiting_refund: false,
+			expiry: None,
+			events,
+		};
+		swap.arm_expiry();
+		if !matches!(swap.state, SwapState::New)
+			&& matches!(swap.state.time_lock(), Some(TimeLock::Relative(_)))
+		{
+			swap.expiry = None;
+		}
+		(swap, receiver)
+	}
+}
+
+/// A swap in progress, see `SwapClient::swap`.
+pub struct Swap<'a, I, IM, C, CM>
+where
+	I: BridgeContractInitiator,
+	C: BridgeContractCounterparty,
+{
+	client: &'a mut SwapClient<I, IM, C, CM>,
+	request: SwapRequest<I::Address>,
+	/// Raw hash locks of the secret on both chains.
+	hash_lock: Vec<u8>,
+	counterparty_hash_lock: Vec<u8>,
+	state: SwapState<I::Hash, C::Hash>,
+	/// Contract call of the current state.
+	call: Option<BoxedFuture<(), SwapClientError>>,
+	attempts: usize,
+	retry: Option<Delay>,
+	/// The lock was rejected or claiming it ran out of attempts, the swap waits for the refund.
+	awaiting_refund: bool,
+	/// Fires once the initiator time lock expires. Unset when it cannot be waited for, or once the
+	/// lock expired on the initiator chain.
+	expiry: Option<Delay>,
+	events: UnboundedSender<SwapEvent<I::Hash, C::Hash>>,
+}
+
+impl<I, IM, C, CM> Swap<'_, I, IM, C, CM>
+where
+	I: BridgeContractInitiator + 'static,
+	I::Hash: TryConvert<Vec<u8>>,
+	IM: BridgeContractInitiatorMonitoring<Address = I::Address, Hash = I::Hash>,
+	C: BridgeContractCounterparty + 'static,
+	CM: BridgeContractCounterpartyMonitoring<Address = C::Address, Hash = C::Hash>,
+{
+	pub fn state(&self) -> &SwapState<I::Hash, C::Hash> {
+		&self.state
+	}
+
+	fn set_state(&mut self, state: SwapState<I::Hash, C::Hash>) {
+		trace!("SwapClient: {:?}", state);
+		self.state = state.clone();
+		let _ = self.events.unbounded_send(SwapEvent::State(state));
+	}
+
+	/// Waits for the initiator time lock, measured locally unless the initiator chain has a clock.
+	/// Absolute time locks cannot be waited for without a clock.
+	fn arm_expiry(&mut self) {
+		let initiator_chain = &self.client.config.initiator_chain;
+		let remaining =
+			self.state.time_lock().and_then(|time_lock| time_remaining(time_lock, initiator_chain));
+		self.expiry = remaining.map(Delay::new);
+	}
+
+	/// Time left before the initiator time lock expires on the initiator chain, `None` once it has
+	/// or without a clock.
+	fn remaining_on_chain(&self) -> Option<Duration> {
+		let clock = self.client.config.initiator_chain.clock.as_ref()?;
+		let time_lock = self.state.time_lock()?;
+		let now = clock.now();
+		(!time_lock.is_expired(&now)).then(|| time_lock.time_remaining(&now, clock.block_time()))
+	}
+
+	/// Differences between a lock under the hash lock of the swap and the lock the bridge is
+	/// expected to place, which may not outlast the initiator lock.
+	fn check_lock(&self, details: &LockDetails<C::Address, C::Hash>) -> Vec<SwapAnomaly> {
+		let request = &self.request;
+		let (asset, amount, recipient) = match &request.quote {
+			Some(quote) => (&quote.counterparty_asset, quote.locked_amount, &quote.recipient),
+			None => (&request.asset, request.amount, &request.recipient_address.0),
+		};
+		let mut anomalies = Vec::new();
+		if details.amount != amount {
+			anomalies
+				.push(SwapAnomaly::AmountMismatch { expected: amount, actual: details.amount });
+		}
+		if details.asset != *asset {
+			anomalies.push(SwapAnomaly::AssetMismatch {
+				expected: asset.clone(),
+				actual: details.asset.clone(),
+			});
+		}
+		let recipient = <C::Address as TryConvert<Vec<u8>>>::try_convert(recipient.clone());
+		if recipient.ok().as_ref() != Some(&details.recipient_address.0) {
+			anomalies.push(SwapAnomaly::RecipientMismatch);
+		}
+		let config = &self.client.config;
+		let initiator_time_lock = self.state.time_lock().unwrap_or(&request.time_lock);
+		let initiator_remaining = time_remaining(initiator_time_lock, &config.initiator_chain);
+		let lock_remaining = time_remaining(&details.time_lock, &config.counterparty_chain);
+		match (initiator_remaining, lock_remaining) {
+			(Some(initiator_remaining), Some(lock_remaining))
+				if lock_remaining <= initiator_remaining => {}
+			_ => anomalies.push(SwapAnomaly::TimeLockMismatch {
+				expected: initiator_time_lock.clone(),
+				actual: details.time_lock.clone(),
+			}),
+		}
+		anomalies
+	}
+
+	/// The time lock the initiator lock expires at, resolved when the initiator chain has a clock.
+	fn resolved_time_lock(&self) -> TimeLock {
+		match &self.client.config.initiator_chain.clock {
+			Some(clock) => self.request.time_lock.resolve(&clock.now()),
+			None => self.request.time_lock.clone(),
+		}
+	}
+
+	fn new_call(&self) -> Result<BoxedFuture<(), SwapClientError>, SwapClientError> {
+		let request = &self.request;
+		let future = match &self.state {
+			SwapState::New => {
+				let mut contract = self.client.initiator_contract.clone();
+				let hash_lock = HashLock(TryConvert::try_convert(self.hash_lock.clone())?);
+				// Chains hashing differently need the counterparty digest committed to
+				let counterparty_hash_lock =
+					(self.client.config.initiator_chain.hash_lock_scheme.name()
+						!= self.client.config.counterparty_chain.hash_lock_scheme.name())
+					.then(|| HashLock(self.counterparty_hash_lock.clone()));
+				let request = request.clone();
+				async move {
+					contract
+						.initiate_bridge_transfer(
+							request.initiator_address,
+							request.recipient_address,
+							hash_lock,
+							counterparty_hash_lock,
+							request.time_lock,
+							request.asset,
+							request.amount,
+							request.destination_chain,
+						)
+						.await?;
+					Ok(())
+				}
+				.boxed()
+			}
+			SwapState::Locked { lock_id, .. } => {
+				let mut contract = self.client.counterparty_contract.clone();
+				let (lock_id, secret) = (lock_id.clone(), request.secret.clone());
+				async move {
+					contract.complete_bridge_transfer(lock_id, secret).await?;
+					Ok(())
+				}
+				.boxed()
+			}
+			SwapState::Refunding { bridge_transfer_id, .. } => {
+				let mut contract = self.client.initiator_contract.clone();
+				let bridge_transfer_id = bridge_transfer_id.clone();
+				async move {
+					contract.refund_bridge_transfer(bridge_transfer_id).await?;
+					Ok(())
+				}
+				.boxed()
+			}
+			state => unreachable!("no contract call in state {state:?}"),
+		};
+		Ok(future.timeout(Delay::new(self.client.config.calls.contract_call_timeout)))
+	}
+
+	/// Runs the contract call of the current state, retrying it up to `error_attempts` times.
+	fn poll_call(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SwapClientError>> {
+		loop {
+			if let Some(retry) = &mut self.retry {
+				ready!(retry.poll_unpin(cx));
+				self.retry = None;
+			}
+			if self.call.is_none() {
+				self.call = Some(self.new_call()?);
+			}
+			let call = self.call.as_mut().expect("call is set");
+			let result = match ready!(call.poll_unpin(cx)) {
+				Ok(result) => result,
+				Err(_) => Err(SwapClientError::ContractCallTimeoutError),
+			};
+			self.call = None;
+			let Err(error) = result else {
+				self.attempts = 0;
+				return Poll::Ready(Ok(()));
+			};
+			warn!("SwapClient: Contract call failed: {}, attempts: {}", error, self.attempts);
+			let _ = self.events.unbounded_send(SwapEvent::CallError(error.clone()));
+			if self.attempts >= self.client.config.calls.error_attempts {
+				self.attempts = 0;
+				return Poll::Ready(Err(error));
+			}
+			self.attempts += 1;
+			self.retry = Some(Delay::new(self.client.config.calls.error_delay));
+		}
+	}
+
+	/// Moves an expired swap to refunding. Fails if the transfer was never reported.
+	fn on_expired(&mut self) -> Result<(), SwapClientError> {
+		// The local timer may run ahead of the chain, which rejects early refunds
+		if let Some(remaining) = self.remaining_on_chain() {
+			trace!("SwapClient: Time lock not expired on chain yet, waiting {:?}", remaining);
+			self.expiry = Some(Delay::new(remaining));
+			return Ok(());
+		}
+		let (bridge_transfer_id, time_lock) = match &self.state {
+			SwapState::Initiating { .. } => return Err(SwapClientError::NotInitiated),
+			SwapState::Initiated { bridge_transfer_id, time_lock }
+			| SwapState::Locked { bridge_transfer_id, time_lock, .. } => {
+				(bridge_transfer_id.clone(), time_lock.clone())
+			}
+			_ => return Ok(()),
+		};
+		warn!("SwapClient: Time lock of {:?} expired, refunding", bridge_transfer_id);
+		self.call = None;
+		self.retry = None;
+		self.attempts = 0;
+		self.set_state(SwapState::Refunding { bridge_transfer_id, time_lock });
+		Ok(())
+	}
+}
+
+impl<I, IM, C, CM> Future for Swap<'_, I, IM, C, CM>
+where
+	I: BridgeContractInitiator + 'static,
+	I::Hash: TryConvert<Vec<u8>>,
+	IM: BridgeContractInitiatorMonitoring<Address = I::Address, Hash = I::Hash>,
+	C: BridgeContractCounterparty + 'static,
+	CM: BridgeContractCounterpartyMonitoring<Address = C::Address, Hash = C::Hash>,
+{
+	type Output = Result<SwapState<I::Hash, C::Hash>, SwapClientError>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let this = self.get_mut();
+		loop {
+			while let Some(expiry) = &mut this.expiry {
+				if expiry.poll_unpin(cx).is_pending() {
+					break;
+				}
+				this.expiry = None;
+				this.on_expired()?;
+			}
+			// The initiator time lock cannot be waited for, so the swap could not be refunded
+			if this.expiry.is_none()
+				&& matches!(
+					this.state,
+					SwapState::Initiating { .. }
+						| SwapState::Initiated { .. }
+						| SwapState::Locked { .. }
+				) {
+				return Poll::Ready(Err(SwapClientError::MissingClock));
+			}
+
+			match &this.state {
+				SwapState::New => {
+					if time_remaining(&this.request.time_lock, &this.client.config.initiator_chain)
+						.is_none()
+					{
+						return Poll::Ready(Err(SwapClientError::MissingClock));
+					}
+					ready!(this.poll_call(cx))?;
+					let time_lock = this.resolved_time_lock();
+					this.set_state(SwapState::Initiating { time_lock });
+					this.arm_expiry();
+				}
+				SwapState::Initiating { time_lock } => {
+					let time_lock = time_lock.clone();
+					let bridge_transfer_id = loop {
+						match ready!(this.client.initiator_monitoring.poll_next_unpin(cx)) {
+							Some(BridgeContractInitiatorEvent::Initiated(details))
+								if details.hash_lock.0.as_ref() == this.hash_lock.as_slice() =>
+							{
+								break details.bridge_transfer_id
+							}
+							Some(_) => continue,
+							None => return Poll::Ready(Err(SwapClientError::MonitoringEnded)),
+						}
+					};
+					this.set_state(SwapState::Initiated { bridge_transfer_id, time_lock });
+				}
+				SwapState::Initiated { bridge_transfer_id, time_lock } => {
+					if this.awaiting_refund {
+						return Poll::Pending;
+					}
+					let (bridge_transfer_id, time_lock) =
+						(bridge_transfer_id.clone(), time_lock.clone());
+					let details = loop {
+						match ready!(this.client.counterparty_monitoring.poll_next_unpin(cx)) {
+							Some(BridgeContractCounterpartyEvent::Locked(details))
+								if details.hash_lock.0.as_ref()
+									== this.counterparty_hash_lock.as_slice() =>
+							{
+								break details
+							}
+							Some(_) => continue,
+							None => return Poll::Ready(Err(SwapClientError::MonitoringEnded)),
+						}
+					};
+					// The secret is only revealed to claim the lock expected
+					let anomalies = this.check_lock(&details);
+					if !anomalies.is_empty() {
+						warn!(
+							"SwapClient: Rejecting lock {:?}: {:?}, waiting to refund",
+							details.bridge_transfer_id, anomalies
+						);
+						let event = SwapEvent::LockRejected(details.bridge_transfer_id, anomalies);
+						let _ = this.events.unbounded_send(event);
+						this.awaiting_refund = true;
+						continue;
+					}
+					let lock_id = details.bridge_transfer_id;
+					this.set_state(SwapState::Locked { bridge_transfer_id, lock_id, time_lock });
+				}
+				SwapState::Locked { bridge_transfer_id, lock_id, .. } => {
+					if this.awaiting_refund {
+						return Poll::Pending;
+					}
+					let (bridge_transfer_id, lock_id) =
+						(bridge_transfer_id.clone(), lock_id.clone());
+					if let Err(error) = ready!(this.poll_call(cx)) {
+						warn!(
+							"SwapClient: Claiming {:?} failed: {}, waiting to refund",
+							lock_id, error
+						);
+						this.awaiting_refund = true;
+						continue;
+					}
+					this.set_state(SwapState::Completed { bridge_transfer_id, lock_id });
+				}
+				SwapState::Refunding { bridge_transfer_id, .. } => {
+					// Resumed before the time lock expired on the initiator chain
+					if this.expiry.is_some() {
+						return Poll::Pending;
+					}
+					let bridge_transfer_id = bridge_transfer_id.clone();
+					if let Err(error) = ready!(this.poll_call(cx)) {
+						warn!(
+							"SwapClient: Refunding {:?} failed: {}, retrying",
+							bridge_transfer_id, error
+						);
+						this.retry = Some(Delay::new(this.client.config.calls.error_delay));
+						continue;
+					}
+					this.set_state(SwapState::Refunded { bridge_transfer_id });
+				}
+				state @ (SwapState::Completed { .. } | SwapState::Refunded { .. }) => {
+					return Poll::Ready(Ok(state.clone()));
+				}
+			}
+		}
+	}
+}
+
+/// Time left on `time_lock`, set on `chain`. Relative time locks are taken as set just now, and
+/// absolute time locks need the clock of the chain.
+fn time_remaining(time_lock: &TimeLock, chain: &ChainConfig) -> Option<Duration> {
+	match (time_lock, &chain.clock) {
+		(time_lock, Some(clock)) => {
+			Some(time_lock.time_remaining(&clock.now(), clock.block_time()))
+		}
+		(TimeLock::Relative(duration), None) => Some(*duration),
+		(_, None) => None,
+	}
+}
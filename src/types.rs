@@ -1,7 +1,12 @@
-use std::{fmt::Debug, hash::Hash};
+use std::{fmt::Debug, hash::Hash, time::Duration};
 
 use derive_more::{Deref, DerefMut};
 use rand::Rng;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use zeroize::Zeroize;
+
+use crate::chain_clock::ChainTime;
 
 #[derive(Deref, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BridgeTransferId<H>(pub H);
@@ -61,14 +66,197 @@ pub fn convert_hash_lock<H: From<O>, O>(other: HashLock<O>) -> HashLock<H> {
 	HashLock(From::from(other.0))
 }
 
-#[derive(Deref, Debug, Clone, PartialEq, Eq)]
-pub struct HashLockPreImage(pub Vec<u8>);
+/// Identifies the asset moved by a transfer on a given chain, e.g. a token contract address.
+#[derive(Deref, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AssetId(pub Vec<u8>);
+
+impl From<&str> for AssetId {
+	fn from(value: &str) -> Self {
+		AssetId(value.as_bytes().to_vec())
+	}
+}
+
+/// Secret that unlocks a hash lock. The bytes are redacted from `Debug` and `Display`, compared
+/// in constant time and zeroed on drop. `expose_secret` is the only way to read them.
+#[derive(Clone)]
+pub struct HashLockPreImage(Vec<u8>);
 
-#[derive(Deref, Debug, Clone, PartialEq, Eq)]
-pub struct TimeLock(pub u64);
+impl HashLockPreImage {
+	pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+		Self(secret.into())
+	}
 
-#[derive(Deref, DerefMut, Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Amount(pub u64);
+	pub fn expose_secret(&self) -> &[u8] {
+		&self.0
+	}
+}
+
+impl Debug for HashLockPreImage {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("HashLockPreImage(<redacted>)")
+	}
+}
+
+impl std::fmt::Display for HashLockPreImage {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("<redacted>")
+	}
+}
+
+impl PartialEq for HashLockPreImage {
+	fn eq(&self, other: &Self) -> bool {
+		self.0.ct_eq(&other.0).into()
+	}
+}
+
+impl Eq for HashLockPreImage {}
+
+impl Drop for HashLockPreImage {
+	fn drop(&mut self) {
+		self.0.zeroize();
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TimeLock {
+	/// Expires once the block timestamp reaches the given unix time, in seconds.
+	AbsoluteTimestamp(u64),
+	/// Expires once the chain reaches the given block height.
+	AbsoluteHeight(u64),
+	/// Expires the given duration after the lock is placed.
+	Relative(Duration),
+}
+
+impl TimeLock {
+	/// Turns a relative lock into an absolute timestamp, counting from when it was placed.
+	/// Absolute locks are returned unchanged.
+	pub fn resolve(&self, placed_at: &ChainTime) -> TimeLock {
+		match self {
+			TimeLock::Relative(duration) => {
+				TimeLock::AbsoluteTimestamp(placed_at.timestamp.saturating_add(duration.as_secs()))
+			}
+			absolute => absolute.clone(),
+		}
+	}
+
+	/// Relative locks are treated as if they were placed at `now`.
+	pub fn is_expired(&self, now: &ChainTime) -> bool {
+		match self {
+			TimeLock::AbsoluteTimestamp(timestamp) => now.timestamp >= *timestamp,
+			TimeLock::AbsoluteHeight(height) => now.height >= *height,
+			TimeLock::Relative(duration) => duration.is_zero(),
+		}
+	}
+
+	/// Time until the lock expires, zero once it has. Remaining blocks are estimated with
+	/// `block_time`. Relative locks are treated as if they were placed at `now`.
+	pub fn time_remaining(&self, now: &ChainTime, block_time: Duration) -> Duration {
+		match self {
+			TimeLock::AbsoluteTimestamp(timestamp) => {
+				Duration::from_secs(timestamp.saturating_sub(now.timestamp))
+			}
+			TimeLock::AbsoluteHeight(height) => {
+				let blocks = height.saturating_sub(now.height);
+				block_time
+					.checked_mul(u32::try_from(blocks).unwrap_or(u32::MAX))
+					.unwrap_or(Duration::MAX)
+			}
+			TimeLock::Relative(duration) => *duration,
+		}
+	}
+}
+
+/// Amount in the smallest unit of an asset. The width is chosen to hold balances of 18-decimal
+/// assets, which do not fit in a `u64`.
+#[derive(Deref, DerefMut, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Amount(pub u128);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AmountConversionError {
+	#[error("Amount {amount:?} loses dust {dust:?} when scaling from {from_decimals} to {to_decimals} decimals")]
+	Dust { amount: Amount, dust: Amount, from_decimals: u8, to_decimals: u8 },
+	#[error(
+		"Amount {amount:?} overflows when scaling from {from_decimals} to {to_decimals} decimals"
+	)]
+	Overflow { amount: Amount, from_decimals: u8, to_decimals: u8 },
+}
+
+/// Converts amounts between two chains that represent the same asset with a different number of
+/// decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountScaling {
+	pub from_decimals: u8,
+	pub to_decimals: u8,
+}
+
+impl Default for AmountScaling {
+	fn default() -> Self {
+		Self::identity()
+	}
+}
+
+impl AmountScaling {
+	pub fn new(from_decimals: u8, to_decimals: u8) -> Self {
+		Self { from_decimals, to_decimals }
+	}
+
+	pub fn identity() -> Self {
+		Self { from_decimals: 0, to_decimals: 0 }
+	}
+
+	pub fn inverse(&self) -> Self {
+		Self { from_decimals: self.to_decimals, to_decimals: self.from_decimals }
+	}
+
+	/// Scales `amount` rounding towards zero, returning the scaled amount together with the dust
+	/// (in source units) that could not be represented on the target chain.
+	pub fn convert_rounding_down(
+		&self,
+		amount: Amount,
+	) -> Result<(Amount, Amount), AmountConversionError> {
+		let Self { from_decimals, to_decimals } = *self;
+		if to_decimals >= from_decimals {
+			let factor = 10u128.checked_pow(u32::from(to_decimals - from_decimals));
+			let scaled = factor.and_then(|factor| amount.0.checked_mul(factor));
+			return match scaled {
+				Some(scaled) => Ok((Amount(scaled), Amount(0))),
+				// Zero scales to zero, whatever the factor
+				None if amount.0 == 0 => Ok((Amount(0), Amount(0))),
+				None => Err(AmountConversionError::Overflow { amount, from_decimals, to_decimals }),
+			};
+		}
+
+		match 10u128.checked_pow(u32::from(from_decimals - to_decimals)) {
+			Some(factor) => Ok((Amount(amount.0 / factor), Amount(amount.0 % factor))),
+			// The factor exceeds any representable amount, so everything is dust
+			None => Ok((Amount(0), amount)),
+		}
+	}
+
+	/// Scales `amount` exactly, rejecting conversions that would lose dust or overflow.
+	pub fn convert(&self, amount: Amount) -> Result<Amount, AmountConversionError> {
+		let (scaled, dust) = self.convert_rounding_down(amount)?;
+		if dust.0 != 0 {
+			return Err(AmountConversionError::Dust {
+				amount,
+				dust,
+				from_decimals: self.from_decimals,
+				to_decimals: self.to_decimals,
+			});
+		}
+		Ok(scaled)
+	}
+}
+
+/// Identifies a chain connected to a `BridgeRouter`, e.g. its EIP-155 chain id.
+#[derive(Deref, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChainId(pub u64);
+
+impl From<u64> for ChainId {
+	fn from(value: u64) -> Self {
+		ChainId(value)
+	}
+}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct BridgeTransferDetails<A, H> {
@@ -76,8 +264,15 @@ pub struct BridgeTransferDetails<A, H> {
 	pub initiator_address: InitiatorAddress<A>,
 	pub recipient_address: RecipientAddress<Vec<u8>>,
 	pub hash_lock: HashLock<H>,
+	/// Digest of the same secret under the counterparty chain's hash function, committed to by
+	/// the initiator when both chains hash pre-images differently.
+	pub counterparty_hash_lock: Option<HashLock<Vec<u8>>>,
 	pub time_lock: TimeLock,
+	pub asset: AssetId,
 	pub amount: Amount,
+	/// Chain the transfer is bound for, used by the `BridgeRouter` to pick the counterparty chain.
+	/// A two-chain bridge leaves it unset.
+	pub destination_chain: Option<ChainId>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -87,6 +282,7 @@ pub struct LockDetails<A, H> {
 	pub recipient_address: RecipientAddress<A>,
 	pub hash_lock: HashLock<H>,
 	pub time_lock: TimeLock,
+	pub asset: AssetId,
 	pub amount: Amount,
 }
 
@@ -97,6 +293,7 @@ pub struct CounterpartyCompletedDetails<A, H> {
 	pub recipient_address: RecipientAddress<A>,
 	pub hash_lock: HashLock<H>,
 	pub secret: HashLockPreImage,
+	pub asset: AssetId,
 	pub amount: Amount,
 }
 
@@ -115,6 +312,7 @@ where
 			recipient_address: From::from(bridge_transfer_details.recipient_address),
 			hash_lock: bridge_transfer_details.hash_lock,
 			secret,
+			asset: bridge_transfer_details.asset,
 			amount: bridge_transfer_details.amount,
 		}
 	}
@@ -128,15 +326,19 @@ impl<A, H> CounterpartyCompletedDetails<A, H> {
 			recipient_address: lock_details.recipient_address,
 			hash_lock: lock_details.hash_lock,
 			secret,
+			asset: lock_details.asset,
 			amount: lock_details.amount,
 		}
 	}
 }
 
 // Types
-pub trait BridgeHashType: Debug + PartialEq + Eq + Hash + Unpin + Send + Sync + Clone {}
+pub trait BridgeHashType:
+	Debug + PartialEq + Eq + Hash + Unpin + Send + Sync + Clone + AsRef<[u8]>
+{
+}
 pub trait BridgeAddressType:
-	Debug + PartialEq + Eq + Hash + Unpin + Send + Sync + Clone + From<Vec<u8>>
+	Debug + PartialEq + Eq + Hash + Unpin + Send + Sync + Clone + TryConvert<Vec<u8>>
 {
 }
 
@@ -144,10 +346,33 @@ pub trait Convert<O> {
 	fn convert(other: &Self) -> O;
 }
 
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+	#[error("Expected {expected} bytes, got {actual}")]
+	InvalidLength { expected: usize, actual: usize },
+	#[error("Invalid encoding: {0}")]
+	InvalidEncoding(String),
+}
+
+/// Fallible conversion of a value, usually originating from another chain, into this chain's
+/// representation.
+pub trait TryConvert<O>: Sized {
+	fn try_convert(other: O) -> Result<Self, ConversionError>;
+}
+
+impl TryConvert<Vec<u8>> for Vec<u8> {
+	fn try_convert(other: Vec<u8>) -> Result<Self, ConversionError> {
+		Ok(other)
+	}
+}
+
 // Blankets
-impl<T> BridgeHashType for T where T: Debug + PartialEq + Eq + Hash + Unpin + Send + Sync + Clone {}
+impl<T> BridgeHashType for T where
+	T: Debug + PartialEq + Eq + Hash + Unpin + Send + Sync + Clone + AsRef<[u8]>
+{
+}
 impl<T> BridgeAddressType for T where
-	T: Debug + PartialEq + Eq + Hash + Unpin + Send + Sync + Clone + From<Vec<u8>>
+	T: Debug + PartialEq + Eq + Hash + Unpin + Send + Sync + Clone + TryConvert<Vec<u8>>
 {
 }
 
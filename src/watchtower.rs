@@ -0,0 +1,383 @@
+use std::{
+	collections::HashMap,
+	pin::Pin,
+	sync::Arc,
+	task::{Context, Poll},
+	time::Duration,
+};
+
+use futures::{task::AtomicWaker, FutureExt, Stream, StreamExt};
+use futures_time::future::FutureExt as TimeoutFutureExt;
+use futures_timer::Delay;
+use thiserror::Error;
+use tracing::{trace, warn};
+
+use crate::{
+	bridge_contracts::{BridgeContractInitiator, BridgeContractInitiatorError},
+	bridge_monitoring::{
+		BridgeContractCounterpartyEvent, BridgeContractCounterpartyMonitoring,
+		BridgeContractInitiatorEvent, BridgeContractInitiatorMonitoring,
+	},
+	bridge_service::{
+		active_swap::{ActiveSwapConfig, BoxedFuture},
+		ChainConfig,
+	},
+	chain_clock::ChainClock,
+	types::{BridgeHashType, BridgeTransferDetails, BridgeTransferId, TimeLock},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct WatchtowerConfig {
+	/// The chain transfers are initiated and refunded on.
+	pub initiator_chain: ChainConfig,
+	/// The chain the bridge locks on.
+	pub counterparty_chain: ChainConfig,
+	/// Retries and timeout of the refunds the watchtower submits.
+	pub calls: ActiveSwapConfig,
+}
+
+#[derive(Debug, Error, Clone)]
+pub enum WatchtowerError {
+	#[error("Timeout while performing contract call")]
+	ContractCallTimeoutError,
+	#[error(transparent)]
+	ContractCallError(#[from] BridgeContractInitiatorError),
+}
+
+#[derive(Debug)]
+pub enum WatchtowerEvent<H> {
+	/// An initiated transfer is watched until a counterparty lock for it is seen.
+	Watching(BridgeTransferId<H>),
+	/// The bridge locked for the transfer, or it was completed or refunded otherwise. The
+	/// transfer is no longer watched.
+	Dismissed(BridgeTransferId<H>),
+	/// The time lock expired on the initiator chain without a counterparty lock, the refund is
+	/// submitted.
+	Refunding(BridgeTransferId<H>),
+	/// The transfer was refunded after its time lock expired, by the watchtower or the initiator.
+	Refunded(BridgeTransferId<H>),
+	RefundError(BridgeTransferId<H>, WatchtowerError),
+	/// The refund failed on `error_attempts` attempts in a row. It keeps being retried until the
+	/// transfer is refunded.
+	RefundStalled(BridgeTransferId<H>),
+	/// The time lock is absolute and the initiator chain has no clock, so the transfer cannot be
+	/// refunded automatically.
+	CannotWatch(BridgeTransferId<H>),
+}
+
+type Attempts = usize;
+
+enum WatchState {
+	/// Waiting for the initiator time lock to expire.
+	Waiting(Delay),
+	Refunding(BoxedFuture<(), WatchtowerError>, Attempts),
+	RefundingError(Delay, Attempts),
+}
+
+struct Watched {
+	/// Raw hash lock the bridge locks under on the counterparty chain, unknown when the chains
+	/// hash differently and the initiator did not commit to the counterparty digest.
+	counterparty_hash_lock: Option<Vec<u8>>,
+	/// Initiator time lock, resolved against the initiator chain clock when there is one.
+	time_lock: TimeLock,
+	state: WatchState,
+}
+
+/// Watch-only mode of the bridge: follows the initiator and counterparty monitoring without
+/// placing locks, and refunds initiated transfers the bridge never locked for once their time lock
+/// expires, e.g. because the bridge is offline or gave up locking.
+///
+/// Initiator events are taken before counterparty events, so the lock of a transfer is not missed
+/// when both are reported at once. Locks placed before the watchtower started are not known, so it
+/// should be started before the bridge.
+pub struct Watchtower<I, IM, CM>
+where
+	I: BridgeContractInitiator,
+{
+	pub config: WatchtowerConfig,
+	initiator_contract: I,
+	initiator_monitoring: IM,
+	counterparty_monitoring: CM,
+	watched: HashMap<BridgeTransferId<I::Hash>, Watched>,
+	waker: AtomicWaker,
+}
+
+impl<I, IM, CM> Watchtower<I, IM, CM>
+where
+	I: BridgeContractInitiator + 'static,
+	IM: BridgeContractInitiatorMonitoring<Address = I::Address, Hash = I::Hash>,
+	CM: BridgeContractCounterpartyMonitoring,
+	CM::Hash: BridgeHashType,
+{
+	pub fn new(
+		initiator_contract: I,
+		initiator_monitoring: IM,
+		counterparty_monitoring: CM,
+		config: WatchtowerConfig,
+	) -> Self {
+		Self {
+			config,
+			initiator_contract,
+			initiator_monitoring,
+			counterparty_monitoring,
+			watched: HashMap::new(),
+			waker: AtomicWaker::new(),
+		}
+	}
+
+	/// Transfers watched, including those being refunded.
+	pub fn watched(&self) -> impl Iterator<Item = &BridgeTransferId<I::Hash>> {
+		self.watched.keys()
+	}
+
+	/// Starts watching an initiated transfer. Absolute time locks need the initiator chain clock.
+	fn watch(
+		&mut self,
+		details: BridgeTransferDetails<I::Address, I::Hash>,
+	) -> WatchtowerEvent<I::Hash> {
+		let clock = &self.config.initiator_chain.clock;
+		let time_lock = match clock {
+			Some(clock) => details.time_lock.resolve(&clock.now()),
+			None => details.time_lock.clone(),
+		};
+		let remaining = match (&time_lock, clock) {
+			(time_lock, Some(clock)) => {
+				Some(time_lock.time_remaining(&clock.now(), clock.block_time()))
+			}
+			(TimeLock::Relative(duration), None) => Some(*duration),
+			(_, None) => None,
+		};
+		let Some(remaining) = remaining else {
+			warn!("Watchtower: Cannot watch {:?} without a clock", details.bridge_transfer_id);
+			return WatchtowerEvent::CannotWatch(details.bridge_transfer_id);
+		};
+
+		// Chains sharing a hash lock scheme lock under the initiator hash lock
+		let counterparty_hash_lock = match details.counterparty_hash_lock {
+			Some(hash_lock) => Some(hash_lock.0),
+			None if self.config.initiator_chain.hash_lock_scheme.name()
+				== self.config.counterparty_chain.hash_lock_scheme.name() =>
+			{
+				Some(details.hash_lock.0.as_ref().to_vec())
+			}
+			None => None,
+		};
+
+		trace!(
+			"Watchtower: Watching {:?}, expiring in {:?}",
+			details.bridge_transfer_id,
+			remaining
+		);
+		self.watched.insert(
+			details.bridge_transfer_id.clone(),
+			Watched {
+				counterparty_hash_lock,
+				time_lock,
+				state: WatchState::Waiting(Delay::new(remaining)),
+			},
+		);
+		self.waker.wake();
+		WatchtowerEvent::Watching(details.bridge_transfer_id)
+	}
+
+	fn on_initiator_event(
+		&mut self,
+		event: BridgeContractInitiatorEvent<I::Address, I::Hash>,
+	) -> Option<WatchtowerEvent<I::Hash>> {
+		match event {
+			BridgeContractInitiatorEvent::Initiated(details) => Some(self.watch(details)),
+			BridgeContractInitiatorEvent::Completed(bridge_transfer_id) => {
+				self.watched.remove(&bridge_transfer_id)?;
+				Some(WatchtowerEvent::Dismissed(bridge_transfer_id))
+			}
+			BridgeContractInitiatorEvent::Refunded(bridge_transfer_id) => {
+				// Dropping the refund in flight, whoever refunded first
+				let watched = self.watched.remove(&bridge_transfer_id)?;
+				match watched.state {
+					WatchState::Waiting(_) => Some(WatchtowerEvent::Dismissed(bridge_transfer_id)),
+					WatchState::Refunding(..) | WatchState::RefundingError(..) => {
+						Some(WatchtowerEvent::Refunded(bridge_transfer_id))
+					}
+				}
+			}
+		}
+	}
+
+	fn on_counterparty_event(
+		&mut self,
+		event: BridgeContractCounterpartyEvent<CM::Address, CM::Hash>,
+	) -> Option<WatchtowerEvent<I::Hash>> {
+		let BridgeContractCounterpartyEvent::Locked(details) = event else {
+			return None;
+		};
+		let hash_lock = details.hash_lock.0.as_ref();
+		let bridge_transfer_id = self
+			.watched
+			.iter()
+			.find(|(_, watched)| {
+				matches!(watched.state, WatchState::Waiting(_))
+					&& watched.counterparty_hash_lock.as_deref() == Some(hash_lock)
+			})
+			.map(|(bridge_transfer_id, _)| bridge_transfer_id.clone())?;
+		trace!("Watchtower: Counterparty lock placed for {:?}", bridge_transfer_id);
+		self.watched.remove(&bridge_transfer_id);
+		Some(WatchtowerEvent::Dismissed(bridge_transfer_id))
+	}
+}
+
+/// Time left before `time_lock` expires on the initiator chain, `None` once it has. Without a clock
+/// the lock is taken as expired, as only relative locks are watched then and their delay elapsed.
+fn remaining_on_chain(
+	clock: Option<&Arc<dyn ChainClock>>,
+	time_lock: &TimeLock,
+) -> Option<Duration> {
+	let clock = clock?;
+	let now = clock.now();
+	(!time_lock.is_expired(&now)).then(|| time_lock.time_remaining(&now, clock.block_time()))
+}
+
+fn refund<I: BridgeContractInitiator + 'static>(
+	initiator_contract: &I,
+	bridge_transfer_id: &BridgeTransferId<I::Hash>,
+	config: &ActiveSwapConfig,
+) -> BoxedFuture<(), WatchtowerError> {
+	let mut initiator_contract = initiator_contract.clone();
+	let bridge_transfer_id = bridge_transfer_id.clone();
+	async move {
+		trace!("Watchtower: Calling refund bridge transfer for {:?}", bridge_transfer_id);
+		initiator_contract.refund_bridge_transfer(bridge_transfer_id).await?;
+		Ok(())
+	}
+	.boxed()
+	.timeout(Delay::new(config.contract_call_timeout))
+}
+
+impl<I, IM, CM> Stream for Watchtower<I, IM, CM>
+where
+	I: BridgeContractInitiator + 'static,
+	IM: BridgeContractInitiatorMonitoring<Address = I::Address, Hash = I::Hash>,
+	CM: BridgeContractCounterpartyMonitoring,
+	CM::Hash: BridgeHashType,
+{
+	type Item = WatchtowerEvent<I::Hash>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+
+		loop {
+			match this.initiator_monitoring.poll_next_unpin(cx) {
+				Poll::Ready(Some(event)) => {
+					if let Some(event) = this.on_initiator_event(event) {
+						return Poll::Ready(Some(event));
+					}
+				}
+				Poll::Ready(None) => {
+					trace!("Watchtower: Initiator monitoring has no more events");
+					return Poll::Ready(None);
+				}
+				Poll::Pending => break,
+			}
+		}
+
+		loop {
+			match this.counterparty_monitoring.poll_next_unpin(cx) {
+				Poll::Ready(Some(event)) => {
+					if let Some(event) = this.on_counterparty_event(event) {
+						return Poll::Ready(Some(event));
+					}
+				}
+				Poll::Ready(None) => {
+					trace!("Watchtower: Counterparty monitoring has no more events");
+					return Poll::Ready(None);
+				}
+				Poll::Pending => break,
+			}
+		}
+
+		let config = &this.config.calls;
+		let clock = this.config.initiator_chain.clock.as_ref();
+		let mut finished = None;
+		let mut event = None;
+		for (bridge_transfer_id, watched) in this.watched.iter_mut() {
+			match &mut watched.state {
+				WatchState::Waiting(expiry) => {
+					if expiry.poll_unpin(cx).is_ready() {
+						// The local timer may run ahead of the chain, which rejects early refunds
+						if let Some(remaining) = remaining_on_chain(clock, &watched.time_lock) {
+							trace!(
+								"Watchtower: Time lock of {:?} not expired on chain yet, waiting {:?}",
+								bridge_transfer_id,
+								remaining
+							);
+							*expiry = Delay::new(remaining);
+							cx.waker().wake_by_ref();
+							continue;
+						}
+						warn!(
+							"Watchtower: Time lock of {:?} expired without a counterparty lock, refunding",
+							bridge_transfer_id
+						);
+						watched.state = WatchState::Refunding(
+							refund(&this.initiator_contract, bridge_transfer_id, config),
+							0,
+						);
+						cx.waker().wake_by_ref();
+						event = Some(WatchtowerEvent::Refunding(bridge_transfer_id.clone()));
+						break;
+					}
+				}
+				WatchState::Refunding(future, attempts) => {
+					let result = match future.poll_unpin(cx) {
+						Poll::Ready(Ok(result)) => result,
+						Poll::Ready(Err(_)) => Err(WatchtowerError::ContractCallTimeoutError),
+						Poll::Pending => continue,
+					};
+					match result {
+						Ok(()) => {
+							finished = Some(bridge_transfer_id.clone());
+							event = Some(WatchtowerEvent::Refunded(bridge_transfer_id.clone()));
+						}
+						Err(error) => {
+							warn!(
+								"Watchtower: Refunding {:?} failed: {}, attempts: {}",
+								bridge_transfer_id, error, attempts
+							);
+							// The transfer stays open until refunded, so the refund is never given up
+							let stalled = *attempts >= config.error_attempts;
+							watched.state = WatchState::RefundingError(
+								Delay::new(config.error_delay),
+								if stalled { 0 } else { *attempts + 1 },
+							);
+							cx.waker().wake_by_ref();
+							event = Some(if stalled {
+								WatchtowerEvent::RefundStalled(bridge_transfer_id.clone())
+							} else {
+								WatchtowerEvent::RefundError(bridge_transfer_id.clone(), error)
+							});
+						}
+					}
+					break;
+				}
+				WatchState::RefundingError(delay, attempts) => {
+					if delay.poll_unpin(cx).is_ready() {
+						watched.state = WatchState::Refunding(
+							refund(&this.initiator_contract, bridge_transfer_id, config),
+							*attempts,
+						);
+						cx.waker().wake_by_ref();
+					}
+				}
+			}
+		}
+		if let Some(bridge_transfer_id) = finished {
+			this.watched.remove(&bridge_transfer_id);
+		}
+		if event.is_some() {
+			return Poll::Ready(event);
+		}
+
+		this.waker.register(cx.waker());
+
+		Poll::Pending
+	}
+}
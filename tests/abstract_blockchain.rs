@@ -1,11 +1,12 @@
 use bridge_shared::types::{
-	Amount, BridgeTransferDetails, BridgeTransferId, GenUniqueHash, HashLock, InitiatorAddress,
-	RecipientAddress, TimeLock,
+	Amount, AssetId, BridgeTransferDetails, BridgeTransferId, ConversionError, GenUniqueHash,
+	HashLock, InitiatorAddress, RecipientAddress, TimeLock, TryConvert,
 };
 use bridge_shared::types::{HashLockPreImage, LockDetails};
 use futures::StreamExt;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
+use std::time::Duration;
 
 use test_log::test;
 
@@ -35,15 +36,23 @@ impl From<TestAddress> for Vec<u8> {
 	}
 }
 
-impl From<Vec<u8>> for TestAddress {
-	fn from(value: Vec<u8>) -> Self {
-		Self(static_str_ops::staticize(&String::from_utf8(value).expect("Invalid UTF-8")))
+impl TryConvert<Vec<u8>> for TestAddress {
+	fn try_convert(value: Vec<u8>) -> Result<Self, ConversionError> {
+		let address = String::from_utf8(value)
+			.map_err(|error| ConversionError::InvalidEncoding(error.to_string()))?;
+		Ok(Self(static_str_ops::staticize(&address)))
 	}
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct TestHash(pub &'static str);
 
+impl AsRef<[u8]> for TestHash {
+	fn as_ref(&self) -> &[u8] {
+		self.0.as_bytes()
+	}
+}
+
 impl From<TestAddress> for RecipientAddress<Vec<u8>> {
 	fn from(value: TestAddress) -> Self {
 		RecipientAddress(value.0.as_bytes().to_vec())
@@ -77,16 +86,20 @@ async fn test_initiate_bridge_transfer() {
 
 	let initiator_address = InitiatorAddress(TestAddress("initiator"));
 	let recipient_address = RecipientAddress::from(TestAddress("recipient"));
+	let asset = AssetId::from("asset");
 	let amount = Amount(1000);
-	let time_lock = TimeLock(100);
+	let time_lock = TimeLock::Relative(Duration::from_secs(100));
 	let hash_lock = HashLock(TestHash("hash_lock"));
 
 	let transaction = Transaction::Initiator(InitiatorCall::InitiateBridgeTransfer(
 		initiator_address.clone(),
 		recipient_address.clone(),
+		asset.clone(),
 		amount,
 		time_lock.clone(),
 		hash_lock.clone(),
+		None,
+		None,
 	));
 
 	blockchain.transaction_sender.unbounded_send(transaction).unwrap();
@@ -105,9 +118,12 @@ async fn test_initiate_bridge_transfer() {
 				bridge_transfer_id: BridgeTransferId(TestHash("unique_hash")),
 				initiator_address: initiator_address.clone(),
 				recipient_address: recipient_address.clone(),
-				amount: amount.clone(),
+				asset: asset.clone(),
+				amount,
 				time_lock: time_lock.clone(),
 				hash_lock: hash_lock.clone(),
+				counterparty_hash_lock: None,
+				destination_chain: None,
 			})
 		))
 	);
@@ -121,6 +137,7 @@ async fn test_initiate_bridge_transfer() {
 	let details = details.unwrap();
 	assert_eq!(details.initiator_address, initiator_address);
 	assert_eq!(details.recipient_address, recipient_address);
+	assert_eq!(details.asset, asset);
 	assert_eq!(details.amount, amount);
 	assert_eq!(details.time_lock, time_lock);
 	assert_eq!(details.hash_lock, hash_lock);
@@ -135,9 +152,10 @@ async fn test_lock_bridge_transfer() {
 
 	let bridge_transfer_id = BridgeTransferId(TestHash("unique_hash"));
 	let hash_lock = HashLock(TestHash("hash_lock"));
-	let time_lock = TimeLock(100);
+	let time_lock = TimeLock::Relative(Duration::from_secs(100));
 	let initiator_adress = InitiatorAddress(vec![]);
 	let recipient_address = RecipientAddress(TestAddress("recipient"));
+	let asset = AssetId::from("asset");
 	let amount = Amount(1000);
 
 	let transaction =
@@ -147,6 +165,7 @@ async fn test_lock_bridge_transfer() {
 			time_lock.clone(),
 			initiator_adress.clone(),
 			recipient_address.clone(),
+			asset.clone(),
 			amount,
 		));
 
@@ -168,6 +187,7 @@ async fn test_lock_bridge_transfer() {
 				time_lock: time_lock.clone(),
 				initiator_address: InitiatorAddress(Vec::new()),
 				recipient_address: recipient_address.clone(),
+				asset: asset.clone(),
 				amount,
 			})
 		))
@@ -181,5 +201,6 @@ async fn test_lock_bridge_transfer() {
 	assert_eq!(details.recipient_address, recipient_address);
 	assert_eq!(details.hash_lock, hash_lock);
 	assert_eq!(details.time_lock, time_lock);
+	assert_eq!(details.asset, asset);
 	assert_eq!(details.amount, amount);
 }
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	address_codec::{AddressCodec, AddressCodecError, Base58, Bech32Codec, Eip55},
+	bridge_monitoring::BridgeContractCounterpartyEvent,
+	bridge_service::{
+		active_swap::ActiveSwapMapError,
+		assets::{AssetConfig, AssetMapping},
+		events::{IEvent, IWarn},
+		BridgeServiceConfig, ChainConfig,
+	},
+	types::{Amount, AssetId},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, setup_bridge_service, test_chain_config,
+	SetupBridgeServiceResult, TestTransfer, TEST_ASSET,
+};
+
+#[test]
+fn test_eip55_codec() {
+	let address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+	let bytes = Eip55.parse(address).expect("valid address");
+	assert_eq!(Eip55.render(&bytes), Ok(address.to_string()));
+	assert_eq!(Eip55.parse(&address.to_lowercase()), Ok(bytes));
+
+	assert_eq!(
+		Eip55.parse("0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+		Err(AddressCodecError::InvalidChecksum)
+	);
+	assert_eq!(
+		Eip55.parse("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA"),
+		Err(AddressCodecError::InvalidLength { expected: 20, actual: 19 })
+	);
+	assert!(matches!(
+		Eip55.parse("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+		Err(AddressCodecError::InvalidEncoding(_))
+	));
+}
+
+#[test]
+fn test_bech32_codec() {
+	let codec = Bech32Codec::new("cosmos", 20).expect("valid prefix");
+	let address = codec.render(&[7; 20]).expect("valid address");
+	assert!(address.starts_with("cosmos1"));
+	assert_eq!(codec.parse(&address), Ok(vec![7; 20]));
+
+	let mut corrupted = address.clone();
+	let last = if corrupted.pop() == Some('q') { 'p' } else { 'q' };
+	corrupted.push(last);
+	assert_eq!(codec.parse(&corrupted), Err(AddressCodecError::InvalidChecksum));
+
+	let other = Bech32Codec::new("osmo", 20).expect("valid prefix");
+	assert_eq!(
+		other.parse(&address),
+		Err(AddressCodecError::UnexpectedPrefix {
+			expected: "osmo".to_string(),
+			actual: "cosmos".to_string()
+		})
+	);
+}
+
+#[test]
+fn test_base58_codec() {
+	let codec = Base58 { length: 32 };
+	// The Solana system program
+	assert_eq!(codec.parse("11111111111111111111111111111111"), Ok(vec![0; 32]));
+	assert_eq!(codec.render(&[0; 32]), Ok("11111111111111111111111111111111".to_string()));
+	assert!(matches!(codec.parse("0OIl"), Err(AddressCodecError::InvalidEncoding(_))));
+	assert_eq!(
+		codec.validate(&[0; 20]),
+		Err(AddressCodecError::InvalidLength { expected: 32, actual: 20 })
+	);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_validates_recipient() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		assets_b1_to_b2: AssetMapping::new()
+			.with_asset(TEST_ASSET, AssetConfig::new(TEST_ASSET).with_liquidity(Amount(5_000))),
+		blockchain_2: ChainConfig { address_codec: Some(Arc::new(Eip55)), ..test_chain_config() },
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { recipient: b"recipient", ..Default::default() },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn).expect("not a b1i warn event"),
+		IWarn::TransferRejected(
+			_,
+			ActiveSwapMapError::InvalidRecipient(AddressCodecError::InvalidLength {
+				expected: 20,
+				actual: 9
+			})
+		)
+	));
+	// No liquidity was committed to the rejected transfer
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.available_liquidity(&AssetId::from(TEST_ASSET)),
+		Some(Amount(5_000))
+	);
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { recipient: b"20_byte_evm_address_", ..Default::default() },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+}
@@ -0,0 +1,131 @@
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_monitoring::BridgeContractCounterpartyEvent,
+	bridge_service::{
+		active_swap::ActiveSwapMapError,
+		assets::{AssetConfig, AssetMapping},
+		events::{IEvent, IWarn},
+		BridgeServiceConfig,
+	},
+	types::{Amount, AmountConversionError, AmountScaling},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, setup_bridge_service,
+	SetupBridgeServiceResult, TestTransfer, TEST_ASSET,
+};
+
+#[test]
+fn test_amount_scaling_up() {
+	let scaling = AmountScaling::new(8, 18);
+	assert_eq!(scaling.convert(Amount(1)), Ok(Amount(10_000_000_000)));
+	assert_eq!(scaling.convert(Amount(0)), Ok(Amount(0)));
+
+	// 21M BTC with 8 decimals still fits once scaled to 18 decimals
+	let supply = Amount(21_000_000 * 100_000_000);
+	assert_eq!(scaling.convert(supply), Ok(Amount(21_000_000 * 10u128.pow(18))));
+
+	assert_eq!(
+		scaling.convert(Amount(u128::MAX)),
+		Err(AmountConversionError::Overflow {
+			amount: Amount(u128::MAX),
+			from_decimals: 8,
+			to_decimals: 18
+		})
+	);
+}
+
+#[test]
+fn test_amount_scaling_down() {
+	let scaling = AmountScaling::new(18, 8);
+	assert_eq!(scaling.convert(Amount(10_000_000_000)), Ok(Amount(1)));
+	assert_eq!(scaling.convert_rounding_down(Amount(10_000_000_123)), Ok((Amount(1), Amount(123))));
+	assert_eq!(
+		scaling.convert(Amount(10_000_000_123)),
+		Err(AmountConversionError::Dust {
+			amount: Amount(10_000_000_123),
+			dust: Amount(123),
+			from_decimals: 18,
+			to_decimals: 8
+		})
+	);
+
+	// A scaling factor beyond u128 turns every amount into dust
+	let scaling = AmountScaling::new(255, 0);
+	assert_eq!(scaling.convert_rounding_down(Amount(42)), Ok((Amount(0), Amount(42))));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_scales_locked_amount() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		assets_b1_to_b2: AssetMapping::new().with_asset(
+			TEST_ASSET,
+			AssetConfig::new(TEST_ASSET).with_amount_scaling(AmountScaling::new(8, 18)),
+		),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+
+	// The counterparty lock carries the amount in the decimals of blockchain 2
+	let event = bridge_service.next().await.expect("No event");
+	let event = event.B2C_ContractEvent().expect("Not a B2C event");
+	let BridgeContractCounterpartyEvent::Locked(details) = event else {
+		panic!("Not a locked event: {event:?}");
+	};
+	assert_eq!(details.amount, Amount(1000 * 10_000_000_000));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_rejects_transfer_losing_dust() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		assets_b1_to_b2: AssetMapping::new().with_asset(
+			TEST_ASSET,
+			AssetConfig::new(TEST_ASSET).with_amount_scaling(AmountScaling::new(18, 8)),
+		),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+
+	let event = bridge_service.next().await.expect("No event");
+	let warn = event.B1I().and_then(IEvent::warn).expect("not a b1i warn event");
+	let IWarn::TransferRejected(details, error) = warn else {
+		panic!("Not a rejected transfer: {warn:?}");
+	};
+	assert_eq!(
+		error,
+		&ActiveSwapMapError::AmountConversion(AmountConversionError::Dust {
+			amount: Amount(1000),
+			dust: Amount(1000),
+			from_decimals: 18,
+			to_decimals: 8
+		})
+	);
+	assert!(!bridge_service.active_swaps_b1_to_b2.already_executing(&details.bridge_transfer_id));
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_contracts::BridgeContractCounterparty,
+	bridge_monitoring::BridgeContractCounterpartyEvent,
+	bridge_service::{
+		active_swap::ActiveSwapMapError,
+		anomaly::{AnomalyPolicy, SwapAnomaly},
+		circuit_breaker::{BreakerScope, CircuitBreakerEvent, TripReason},
+		direction::Direction,
+		events::{CEvent, CWarn, IEvent, IWarn},
+		BridgeServiceConfig,
+	},
+	types::{
+		Amount, AssetId, BridgeTransferId, Convert, HashLock, InitiatorAddress, RecipientAddress,
+		TimeLock,
+	},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, setup_bridge_service, B2Client, BC2Address,
+	BC2Hash, SetupBridgeServiceResult, TestTransfer, TEST_ASSET,
+};
+
+async fn lock(
+	client: &mut B2Client,
+	bridge_transfer_id: BridgeTransferId<BC2Hash>,
+	amount: Amount,
+) {
+	client
+		.lock_bridge_transfer_assets(
+			bridge_transfer_id,
+			HashLock(BC2Hash::from("hash_lock")),
+			TimeLock::Relative(Duration::from_secs(1_000)),
+			InitiatorAddress(b"initiator".to_vec()),
+			RecipientAddress(BC2Address("recipient")),
+			AssetId::from(TEST_ASSET),
+			amount,
+		)
+		.await
+		.expect("lock_bridge_transfer_assets failed");
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_flags_mismatched_lock() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+	let event = bridge_service.next().await.expect("No event");
+	let bridge_transfer_id =
+		event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+
+	// A lock reported under the same id but differing from what the bridge requested
+	lock(&mut blockchain_2_client, Convert::convert(&bridge_transfer_id), Amount(1)).await;
+	let event = bridge_service.next().await.expect("No event");
+	let Some(CWarn::Anomaly(BridgeContractCounterpartyEvent::Locked(_), anomalies)) =
+		event.B2C().and_then(CEvent::warn)
+	else {
+		panic!("Not an anomaly: {event:?}");
+	};
+	assert_eq!(
+		anomalies,
+		&vec![
+			SwapAnomaly::AmountMismatch { expected: Amount(1000), actual: Amount(1) },
+			SwapAnomaly::TimeLockMismatch {
+				expected: TimeLock::Relative(Duration::from_secs(100)),
+				actual: TimeLock::Relative(Duration::from_secs(1_000)),
+			},
+		]
+	);
+	assert!(!bridge_service.active_swaps_b1_to_b2.is_paused());
+	assert!(bridge_service.circuit_breaker.trip_reason(BreakerScope::Global).is_none());
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_pauses_on_unknown_lock() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		anomaly_policy: AnomalyPolicy { pause_on_anomaly: true },
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	lock(&mut blockchain_2_client, BridgeTransferId(BC2Hash::from("unknown")), Amount(1000)).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B2C().and_then(CEvent::warn),
+		Some(CWarn::Anomaly(_, anomalies)) if anomalies == &vec![SwapAnomaly::UnknownTransfer]
+	));
+	let event = bridge_service.next().await.expect("No event");
+	assert_eq!(
+		event.circuit_breaker(),
+		Some(&CircuitBreakerEvent::Tripped(
+			BreakerScope::Direction(Direction::B1ToB2),
+			TripReason::Anomaly
+		))
+	);
+	assert!(bridge_service.active_swaps_b1_to_b2.is_paused());
+	assert!(!bridge_service.active_swaps_b2_to_b1.is_paused());
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn),
+		Some(IWarn::TransferRejected(_, ActiveSwapMapError::Paused))
+	));
+
+	bridge_service.reset_circuit_breaker(BreakerScope::Direction(Direction::B1ToB2));
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.circuit_breaker(), Some(CircuitBreakerEvent::Reset(_))));
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+}
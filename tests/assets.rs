@@ -0,0 +1,123 @@
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_monitoring::BridgeContractCounterpartyEvent,
+	bridge_service::{
+		active_swap::ActiveSwapMapError,
+		assets::{AssetConfig, AssetFee, AssetMapping, TransferLimits},
+		events::{IEvent, IWarn},
+		BridgeServiceConfig,
+	},
+	types::{Amount, AssetId},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, setup_bridge_service,
+	SetupBridgeServiceResult, TestTransfer, TEST_ASSET,
+};
+
+#[test]
+fn test_asset_fee() {
+	let fee = AssetFee { flat: Amount(5), basis_points: 30 };
+	assert_eq!(fee.fee_for(Amount(10_000)), Some(Amount(35)));
+	assert_eq!(fee.fee_for(Amount(0)), Some(Amount(5)));
+	assert_eq!(
+		AssetFee { flat: Amount(0), basis_points: 10_000 }.fee_for(Amount(u128::MAX)),
+		Some(Amount(u128::MAX))
+	);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_rejects_unmapped_asset() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { asset: "unknown", ..Default::default() },
+	)
+	.await;
+
+	let event = bridge_service.next().await.expect("No event");
+	let warn = event.B1I().and_then(IEvent::warn).expect("not a b1i warn event");
+	assert!(matches!(
+		warn,
+		IWarn::TransferRejected(_, ActiveSwapMapError::UnmappedAsset(asset))
+			if asset == &AssetId::from("unknown")
+	));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_maps_asset_and_deducts_fee() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		assets_b1_to_b2: AssetMapping::new().with_asset(
+			TEST_ASSET,
+			AssetConfig::new("wrapped_asset")
+				.with_fee(AssetFee { flat: Amount(10), basis_points: 100 })
+				.with_limits(TransferLimits { min: Amount(100), max: Amount(10_000) })
+				.with_liquidity(Amount(5_000)),
+		),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	// Below the minimum
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(50), ..Default::default() },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn).expect("not a b1i warn event"),
+		IWarn::TransferRejected(_, ActiveSwapMapError::AmountBelowMinimum { .. })
+	));
+
+	// More than the available liquidity once the fee is deducted
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(6_000), ..Default::default() },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn).expect("not a b1i warn event"),
+		IWarn::TransferRejected(_, ActiveSwapMapError::InsufficientLiquidity { .. })
+	));
+
+	// Accepted: 1000 minus a fee of 10 + 1%
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+
+	let event = bridge_service.next().await.expect("No event");
+	let event = event.B2C_ContractEvent().expect("Not a B2C event");
+	let BridgeContractCounterpartyEvent::Locked(details) = event else {
+		panic!("Not a locked event: {event:?}");
+	};
+	assert_eq!(details.asset, AssetId::from("wrapped_asset"));
+	assert_eq!(details.amount, Amount(980));
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.available_liquidity(&AssetId::from(TEST_ASSET)),
+		Some(Amount(4_020))
+	);
+}
@@ -1,11 +1,12 @@
 use bridge_shared::bridge_monitoring::BridgeContractInitiatorEvent;
 use bridge_shared::types::{
-	Amount, BridgeTransferDetails, BridgeTransferId, HashLock, InitiatorAddress, RecipientAddress,
-	TimeLock,
+	Amount, AssetId, BridgeTransferDetails, BridgeTransferId, HashLock, InitiatorAddress,
+	RecipientAddress, TimeLock,
 };
 use bridge_shared::{blockchain_service::ContractEvent, bridge_contracts::BridgeContractInitiator};
 use futures::StreamExt;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 mod shared;
 
@@ -22,8 +23,11 @@ async fn test_bridge_transfer_initiated() {
 			InitiatorAddress::from("initiator"),
 			RecipientAddress::from("recipient"),
 			HashLock("hash_lock"),
-			TimeLock(100),
+			None,
+			TimeLock::Relative(Duration::from_secs(100)),
+			AssetId::from("asset"),
 			Amount(1000),
+			None,
 		)
 		.await
 		.expect("initiate_bridge_transfer failed");
@@ -39,8 +43,11 @@ async fn test_bridge_transfer_initiated() {
 				initiator_address: InitiatorAddress::from("initiator"),
 				recipient_address: RecipientAddress::from("recipient"),
 				hash_lock: HashLock("hash_lock"),
-				time_lock: TimeLock(100),
+				counterparty_hash_lock: None,
+				time_lock: TimeLock::Relative(Duration::from_secs(100)),
+				asset: AssetId::from("asset"),
 				amount: Amount(1000),
+				destination_chain: None,
 			}
 		))))
 	);
@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use futures::StreamExt;
+use rand::SeedableRng;
 use test_log::test;
 
 use bridge_shared::{
@@ -8,14 +9,15 @@ use bridge_shared::{
 	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
 	bridge_service::{active_swap::ActiveSwapConfig, BridgeServiceConfig},
 	types::{
-		Amount, BridgeTransferDetails, Convert, CounterpartyCompletedDetails, HashLock,
+		Amount, AssetId, BridgeTransferDetails, Convert, CounterpartyCompletedDetails, HashLock,
 		HashLockPreImage, InitiatorAddress, LockDetails, RecipientAddress, TimeLock,
 	},
 };
 
 use crate::shared::{
-	setup_bridge_service, B1Client, B2Client, BC1Address, BC1Hash, BC2Address, BC2Hash,
-	SetupBridgeServiceResult,
+	default_bridge_service_config, initiate_transfer, setup_bridge_service, testing::rng::TestRng,
+	B1Client, B2Client, BC1Address, BC1Hash, BC2Address, BC2Hash, SetupBridgeServiceResult,
+	TestTransfer, TEST_ASSET,
 };
 
 mod shared;
@@ -34,6 +36,7 @@ async fn test_bridge_service_integration_a_to_b() {
 			error_delay: Duration::from_secs(1),
 			contract_call_timeout: Duration::from_secs(5),
 		},
+		..default_bridge_service_config()
 	});
 
 	tokio::spawn(blockchain_1);
@@ -49,8 +52,11 @@ async fn test_bridge_service_integration_a_to_b() {
 			InitiatorAddress(BC1Address("initiator")),
 			RecipientAddress::from(BC1Address("recipient")),
 			HashLock(BC1Hash::from("hash_lock")),
-			TimeLock(100),
+			None,
+			TimeLock::Relative(Duration::from_secs(100)),
+			AssetId::from(TEST_ASSET),
 			Amount(1000),
+			None,
 		)
 		.await
 		.expect("initiate_bridge_transfer failed");
@@ -67,8 +73,11 @@ async fn test_bridge_service_integration_a_to_b() {
 			initiator_address: InitiatorAddress(BC1Address("initiator")),
 			recipient_address: RecipientAddress::from(BC1Address("recipient")),
 			hash_lock: HashLock(BC1Hash::from("hash_lock")),
-			time_lock: TimeLock(100),
-			amount: Amount(1000)
+			counterparty_hash_lock: None,
+			time_lock: TimeLock::Relative(Duration::from_secs(100)),
+			asset: AssetId::from(TEST_ASSET),
+			amount: Amount(1000),
+			destination_chain: None
 		})
 	);
 
@@ -87,9 +96,10 @@ async fn test_bridge_service_integration_a_to_b() {
 		&BridgeContractCounterpartyEvent::Locked(LockDetails {
 			bridge_transfer_id: Convert::convert(transfer_initiated_event.bridge_transfer_id()),
 			hash_lock: HashLock(BC2Hash::from("hash_lock")),
-			time_lock: TimeLock(100),
+			time_lock: TimeLock::Relative(Duration::from_secs(100)),
 			initiator_address: InitiatorAddress::from(BC1Address("initiator")),
 			recipient_address: RecipientAddress(BC2Address("recipient")),
+			asset: AssetId::from(TEST_ASSET),
 			amount: Amount(1000),
 		})
 	);
@@ -103,7 +113,7 @@ async fn test_bridge_service_integration_a_to_b() {
 	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
 		&mut blockchain_2_client,
 		Convert::convert(transfer_initiated_event.bridge_transfer_id()),
-		HashLockPreImage(b"hash_lock".to_vec()),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
 	)
 	.await
 	.expect("complete_bridge_transfer failed");
@@ -121,7 +131,8 @@ async fn test_bridge_service_integration_a_to_b() {
 			initiator_address: InitiatorAddress::from(BC1Address("initiator")),
 			recipient_address: RecipientAddress(BC2Address("recipient")),
 			hash_lock: HashLock(BC2Hash::from("hash_lock")),
-			secret: HashLockPreImage(b"hash_lock".to_vec()),
+			secret: HashLockPreImage::new(b"hash_lock".to_vec()),
+			asset: AssetId::from(TEST_ASSET),
 			amount: Amount(1000),
 		})
 	);
@@ -159,6 +170,7 @@ async fn test_bridge_service_integration_b_to_a() {
 			error_delay: Duration::from_secs(1),
 			contract_call_timeout: Duration::from_secs(5),
 		},
+		..default_bridge_service_config()
 	});
 
 	tokio::spawn(blockchain_1);
@@ -174,8 +186,11 @@ async fn test_bridge_service_integration_b_to_a() {
 			InitiatorAddress(BC2Address("initiator")),
 			RecipientAddress::from(BC2Address("recipient")),
 			HashLock(BC2Hash::from("hash_lock")),
-			TimeLock(100),
+			None,
+			TimeLock::Relative(Duration::from_secs(100)),
+			AssetId::from(TEST_ASSET),
 			Amount(1000),
+			None,
 		)
 		.await
 		.expect("initiate_bridge_transfer failed");
@@ -192,8 +207,11 @@ async fn test_bridge_service_integration_b_to_a() {
 			initiator_address: InitiatorAddress(BC2Address("initiator")),
 			recipient_address: RecipientAddress::from(BC2Address("recipient")),
 			hash_lock: HashLock(BC2Hash::from("hash_lock")),
-			time_lock: TimeLock(100),
-			amount: Amount(1000)
+			counterparty_hash_lock: None,
+			time_lock: TimeLock::Relative(Duration::from_secs(100)),
+			asset: AssetId::from(TEST_ASSET),
+			amount: Amount(1000),
+			destination_chain: None
 		})
 	);
 
@@ -212,9 +230,10 @@ async fn test_bridge_service_integration_b_to_a() {
 		&BridgeContractCounterpartyEvent::Locked(LockDetails {
 			bridge_transfer_id: Convert::convert(transfer_initiated_event.bridge_transfer_id()),
 			hash_lock: HashLock(BC1Hash::from("hash_lock")),
-			time_lock: TimeLock(100),
+			time_lock: TimeLock::Relative(Duration::from_secs(100)),
 			initiator_address: InitiatorAddress::from(BC1Address("initiator")),
 			recipient_address: RecipientAddress(BC1Address("recipient")),
+			asset: AssetId::from(TEST_ASSET),
 			amount: Amount(1000),
 		})
 	);
@@ -228,7 +247,7 @@ async fn test_bridge_service_integration_b_to_a() {
 	<B1Client as BridgeContractCounterparty>::complete_bridge_transfer(
 		&mut blockchain_1_client,
 		Convert::convert(transfer_initiated_event.bridge_transfer_id()),
-		HashLockPreImage(b"hash_lock".to_vec()),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
 	)
 	.await
 	.expect("complete_bridge_transfer failed");
@@ -246,7 +265,8 @@ async fn test_bridge_service_integration_b_to_a() {
 			initiator_address: InitiatorAddress::from(BC1Address("initiator")),
 			recipient_address: RecipientAddress(BC1Address("recipient")),
 			hash_lock: HashLock(BC1Hash::from("hash_lock")),
-			secret: HashLockPreImage(b"hash_lock".to_vec()),
+			secret: HashLockPreImage::new(b"hash_lock".to_vec()),
+			asset: AssetId::from(TEST_ASSET),
 			amount: Amount(1000),
 		})
 	);
@@ -269,3 +289,109 @@ async fn test_bridge_service_integration_b_to_a() {
 		)
 	);
 }
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_counterparty_assigned_ids() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+	let counterparty_contract = &mut bridge_service.active_swaps_b1_to_b2.counterparty_contract;
+	counterparty_contract.assign_counterparty_ids = true;
+	// The clients share a seed, so the contract would otherwise draw the initiator id
+	counterparty_contract.rng = TestRng::from_seed([1; 32]);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+
+	let event = bridge_service.next().await.expect("No event");
+	let initiator_id =
+		event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+
+	// The lock is placed under an id of the counterparty's choosing
+	let event = bridge_service.next().await.expect("No event");
+	let Some(BridgeContractCounterpartyEvent::Locked(lock_details)) = event.B2C_ContractEvent()
+	else {
+		panic!("Not a locked event: {event:?}");
+	};
+	let counterparty_id = lock_details.bridge_transfer_id.clone();
+	assert_ne!(counterparty_id, Convert::convert(&initiator_id));
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.initiator_bridge_transfer_id(&counterparty_id),
+		Some(&initiator_id)
+	);
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.counterparty_bridge_transfer_id(&initiator_id),
+		Some(&counterparty_id)
+	);
+
+	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut blockchain_2_client,
+		counterparty_id.clone(),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B2C_ContractEvent(),
+		Some(BridgeContractCounterpartyEvent::Completed(details))
+			if details.bridge_transfer_id == counterparty_id
+	));
+
+	// The completion is resolved back to the initiator id through the mapping
+	let event = bridge_service.next().await.expect("No event");
+	assert_eq!(
+		event.B1I_ContractEvent(),
+		Some(&BridgeContractInitiatorEvent::Completed(initiator_id))
+	);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_lock_reported_before_call_returns() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+	let counterparty_contract = &mut bridge_service.active_swaps_b1_to_b2.counterparty_contract;
+	counterparty_contract.assign_counterparty_ids = true;
+	counterparty_contract.rng = TestRng::from_seed([1; 32]);
+	counterparty_contract.lock_return_delay = Some(Duration::from_millis(500));
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+
+	let event = bridge_service.next().await.expect("No event");
+	let initiator_id =
+		event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+
+	// The lock under an id the bridge does not know yet is matched on its hash lock
+	let event = bridge_service.next().await.expect("No event");
+	let Some(BridgeContractCounterpartyEvent::Locked(lock_details)) = event.B2C_ContractEvent()
+	else {
+		panic!("Not a locked event: {event:?}");
+	};
+	let counterparty_id = lock_details.bridge_transfer_id.clone();
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.counterparty_bridge_transfer_id(&initiator_id),
+		None
+	);
+
+	// The id is mapped once the call returns
+	let _ = tokio::time::timeout(Duration::from_secs(1), bridge_service.next()).await;
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.initiator_bridge_transfer_id(&counterparty_id),
+		Some(&initiator_id)
+	);
+}
@@ -10,21 +10,23 @@ use bridge_shared::{
 	},
 	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
 	bridge_service::{
-		active_swap::{ActiveSwapConfig, LockBridgeTransferAssetsError},
+		active_swap::{ActiveSwapConfig, LockBridgeTransferAssetsError, TransferField},
 		events::{CEvent, CWarn, Event, IEvent, IWarn},
 		BridgeServiceConfig,
 	},
 	types::{
-		Amount, BridgeTransferDetails, Convert, CounterpartyCompletedDetails, HashLock,
-		HashLockPreImage, InitiatorAddress, RecipientAddress, TimeLock,
+		Amount, AssetId, BridgeTransferDetails, ConversionError, Convert,
+		CounterpartyCompletedDetails, HashLock, HashLockPreImage, InitiatorAddress,
+		RecipientAddress, TimeLock,
 	},
 };
 
 mod shared;
 
 use crate::shared::{
-	setup_bridge_service, testing::blockchain::client::MethodName, B2Client, BC1Address, BC1Hash,
-	BC2Address, BC2Hash, SetupBridgeServiceResult,
+	default_bridge_service_config, initiate_transfer, setup_bridge_service,
+	testing::blockchain::client::MethodName, B2Client, BC1Address, BC1Hash, BC2Address, BC2Hash,
+	SetupBridgeServiceResult, TestTransfer, TEST_ASSET,
 };
 
 use self::shared::testing::blockchain::client::{CallConfig, ErrorConfig};
@@ -43,6 +45,7 @@ async fn test_bridge_service_error_handling() {
 			error_delay: Duration::from_secs(1),
 			contract_call_timeout: Duration::from_secs(5),
 		},
+		..default_bridge_service_config()
 	});
 
 	tokio::spawn(blockchain_1);
@@ -68,8 +71,11 @@ async fn test_bridge_service_error_handling() {
 			InitiatorAddress(BC1Address("initiator")),
 			RecipientAddress::from(BC1Address("recipient")),
 			HashLock(BC1Hash::from("hash_lock")),
-			TimeLock(100),
+			None,
+			TimeLock::Relative(Duration::from_secs(100)),
+			AssetId::from(TEST_ASSET),
 			Amount(1000),
+			None,
 		)
 		.await
 		.expect("initiate_bridge_transfer failed");
@@ -86,8 +92,11 @@ async fn test_bridge_service_error_handling() {
 			initiator_address: InitiatorAddress(BC1Address("initiator")),
 			recipient_address: RecipientAddress::from(BC1Address("recipient")),
 			hash_lock: HashLock(BC1Hash::from("hash_lock")),
-			time_lock: TimeLock(100),
-			amount: Amount(1000)
+			counterparty_hash_lock: None,
+			time_lock: TimeLock::Relative(Duration::from_secs(100)),
+			asset: AssetId::from(TEST_ASSET),
+			amount: Amount(1000),
+			destination_chain: None
 		})
 	);
 
@@ -121,7 +130,7 @@ async fn test_bridge_service_error_handling() {
 	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
 		&mut blockchain_2_client,
 		Convert::convert(transfer_initiated_event.bridge_transfer_id()),
-		HashLockPreImage(b"hash_lock".to_vec()),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
 	)
 	.await
 	.expect("complete_bridge_transfer failed");
@@ -138,7 +147,8 @@ async fn test_bridge_service_error_handling() {
 			initiator_address: InitiatorAddress::from(BC1Address("initiator")),
 			recipient_address: RecipientAddress(BC2Address("recipient")),
 			hash_lock: HashLock(BC2Hash::from("hash_lock")),
-			secret: HashLockPreImage(b"hash_lock".to_vec()),
+			secret: HashLockPreImage::new(b"hash_lock".to_vec()),
+			asset: AssetId::from(TEST_ASSET),
 			amount: Amount(1000),
 		})
 	);
@@ -202,6 +212,7 @@ async fn test_bridge_service_locking_termination_after_errors() {
 			error_delay: Duration::from_secs(1),
 			contract_call_timeout: Duration::from_secs(5),
 		},
+		..default_bridge_service_config()
 	});
 
 	tokio::spawn(blockchain_1);
@@ -228,8 +239,11 @@ async fn test_bridge_service_locking_termination_after_errors() {
 			InitiatorAddress(BC1Address("initiator")),
 			RecipientAddress::from(BC1Address("recipient")),
 			HashLock(BC1Hash::from("hash_lock")),
-			TimeLock(100),
+			None,
+			TimeLock::Relative(Duration::from_secs(100)),
+			AssetId::from(TEST_ASSET),
 			Amount(1000),
+			None,
 		)
 		.await
 		.expect("initiate_bridge_transfer failed");
@@ -246,8 +260,11 @@ async fn test_bridge_service_locking_termination_after_errors() {
 			initiator_address: InitiatorAddress(BC1Address("initiator")),
 			recipient_address: RecipientAddress::from(BC1Address("recipient")),
 			hash_lock: HashLock(BC1Hash::from("hash_lock")),
-			time_lock: TimeLock(100),
-			amount: Amount(1000)
+			counterparty_hash_lock: None,
+			time_lock: TimeLock::Relative(Duration::from_secs(100)),
+			asset: AssetId::from(TEST_ASSET),
+			amount: Amount(1000),
+			destination_chain: None
 		})
 	);
 
@@ -297,6 +314,7 @@ async fn test_bridge_service_completion_abort_after_errors() {
 			error_delay: Duration::from_secs(1),
 			contract_call_timeout: Duration::from_secs(5),
 		},
+		..default_bridge_service_config()
 	});
 
 	tokio::spawn(blockchain_1);
@@ -309,8 +327,11 @@ async fn test_bridge_service_completion_abort_after_errors() {
 			InitiatorAddress(BC1Address("initiator")),
 			RecipientAddress::from(BC1Address("recipient")),
 			HashLock(BC1Hash::from("hash_lock")),
-			TimeLock(100),
+			None,
+			TimeLock::Relative(Duration::from_secs(100)),
+			AssetId::from(TEST_ASSET),
 			Amount(1000),
+			None,
 		)
 		.await
 		.expect("initiate_bridge_transfer failed");
@@ -327,8 +348,11 @@ async fn test_bridge_service_completion_abort_after_errors() {
 			initiator_address: InitiatorAddress(BC1Address("initiator")),
 			recipient_address: RecipientAddress::from(BC1Address("recipient")),
 			hash_lock: HashLock(BC1Hash::from("hash_lock")),
-			time_lock: TimeLock(100),
-			amount: Amount(1000)
+			counterparty_hash_lock: None,
+			time_lock: TimeLock::Relative(Duration::from_secs(100)),
+			asset: AssetId::from(TEST_ASSET),
+			amount: Amount(1000),
+			destination_chain: None
 		})
 	);
 
@@ -358,7 +382,7 @@ async fn test_bridge_service_completion_abort_after_errors() {
 	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
 		&mut blockchain_2_client,
 		Convert::convert(transfer_initiated_event.bridge_transfer_id()),
-		HashLockPreImage(b"hash_lock".to_vec()),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
 	)
 	.await
 	.expect("complete_bridge_transfer failed");
@@ -375,7 +399,8 @@ async fn test_bridge_service_completion_abort_after_errors() {
 			initiator_address: InitiatorAddress::from(BC1Address("initiator")),
 			recipient_address: RecipientAddress(BC2Address("recipient")),
 			hash_lock: HashLock(BC2Hash::from("hash_lock")),
-			secret: HashLockPreImage(b"hash_lock".to_vec()),
+			secret: HashLockPreImage::new(b"hash_lock".to_vec()),
+			asset: AssetId::from(TEST_ASSET),
 			amount: Amount(1000),
 		})
 	);
@@ -425,6 +450,7 @@ async fn test_bridge_service_timeout_error_handling() {
 			error_delay: Duration::from_secs(1),
 			contract_call_timeout: Duration::from_millis(100), // Set a short timeout for testing
 		},
+		..default_bridge_service_config()
 	});
 
 	tokio::spawn(blockchain_1);
@@ -445,8 +471,11 @@ async fn test_bridge_service_timeout_error_handling() {
 			InitiatorAddress(BC1Address("initiator")),
 			RecipientAddress::from(BC1Address("recipient")),
 			HashLock(BC1Hash::from("hash_lock")),
-			TimeLock(100),
+			None,
+			TimeLock::Relative(Duration::from_secs(100)),
+			AssetId::from(TEST_ASSET),
 			Amount(1000),
+			None,
 		)
 		.await
 		.expect("initiate_bridge_transfer failed");
@@ -463,8 +492,11 @@ async fn test_bridge_service_timeout_error_handling() {
 			initiator_address: InitiatorAddress(BC1Address("initiator")),
 			recipient_address: RecipientAddress::from(BC1Address("recipient")),
 			hash_lock: HashLock(BC1Hash::from("hash_lock")),
-			time_lock: TimeLock(100),
-			amount: Amount(1000)
+			counterparty_hash_lock: None,
+			time_lock: TimeLock::Relative(Duration::from_secs(100)),
+			asset: AssetId::from(TEST_ASSET),
+			amount: Amount(1000),
+			destination_chain: None
 		})
 	);
 
@@ -488,3 +520,37 @@ async fn test_bridge_service_timeout_error_handling() {
 	tracing::debug!(?event);
 	assert!(matches!(event, BridgeContractCounterpartyEvent::Locked(_)));
 }
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_rejects_malformed_recipient() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	// Not valid UTF-8, so it cannot be turned into an address of blockchain 2
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { recipient: &[0xff, 0xfe], ..Default::default() },
+	)
+	.await;
+
+	let event = bridge_service.next().await.expect("No event");
+	tracing::debug!(?event);
+	let warn = event.B1I().and_then(IEvent::warn).expect("not a b1i warn event");
+	let IWarn::CannotConvertTransfer {
+		bridge_transfer_id,
+		field: TransferField::RecipientAddress,
+		error: ConversionError::InvalidEncoding(_),
+	} = warn
+	else {
+		panic!("Not a recipient conversion warning: {warn:?}");
+	};
+	assert!(!bridge_service.active_swaps_b1_to_b2.already_executing(bridge_transfer_id));
+}
@@ -0,0 +1,272 @@
+use std::{
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	task::{Context, Poll, Wake, Waker},
+	time::{Duration, Instant},
+};
+
+use futures::{task::noop_waker_ref, StreamExt};
+use test_log::test;
+
+use bridge_shared::{
+	bridge_monitoring::BridgeContractCounterpartyEvent,
+	bridge_service::{
+		call_limit::{CallKind, CallLimitConfig, CallLimiter, CallQueueOrder, RateLimit},
+		circuit_breaker::BreakerScope,
+		direction::Direction,
+		BridgeServiceConfig, ChainConfig,
+	},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, setup_bridge_service, test_chain_config,
+	SetupBridgeServiceResult, TestTransfer,
+};
+
+#[test]
+fn test_call_limiter_in_flight() {
+	let mut cx = Context::from_waker(noop_waker_ref());
+	let mut calls =
+		CallLimiter::new(CallLimitConfig { max_in_flight: Some(1), ..Default::default() });
+	let first = calls.enqueue(CallKind::Lock, None);
+	let second = calls.enqueue(CallKind::Complete, None);
+	assert_eq!(calls.queue_depth(), 2);
+
+	// Calls go in order
+	assert_eq!(calls.poll_acquire(second, &mut cx), Poll::Pending);
+	assert_eq!(calls.poll_acquire(first, &mut cx), Poll::Ready(()));
+	assert_eq!(calls.poll_acquire(second, &mut cx), Poll::Pending);
+	assert_eq!(calls.in_flight(), 1);
+
+	calls.release();
+	assert_eq!(calls.poll_acquire(second, &mut cx), Poll::Ready(()));
+	assert_eq!(calls.queue_depth(), 0);
+}
+
+#[test]
+fn test_call_limiter_priority() {
+	let mut cx = Context::from_waker(noop_waker_ref());
+	let mut calls = CallLimiter::new(CallLimitConfig {
+		max_in_flight: Some(1),
+		queue_order: CallQueueOrder::Priority,
+		..Default::default()
+	});
+	let lock = calls.enqueue(CallKind::Lock, None);
+	let complete = calls.enqueue(CallKind::Complete, None);
+	assert_eq!(calls.poll_acquire(lock, &mut cx), Poll::Pending);
+	assert_eq!(calls.poll_acquire(complete, &mut cx), Poll::Ready(()));
+	calls.release();
+	assert_eq!(calls.poll_acquire(lock, &mut cx), Poll::Ready(()));
+}
+
+#[test]
+fn test_call_limiter_urgency() {
+	let mut cx = Context::from_waker(noop_waker_ref());
+	let mut calls = CallLimiter::new(CallLimitConfig {
+		max_in_flight: Some(1),
+		queue_order: CallQueueOrder::Priority,
+		urgent_within: Duration::from_secs(60),
+		..Default::default()
+	});
+	let now = Instant::now();
+	let lock = calls.enqueue(CallKind::Lock, Some(now + Duration::from_secs(1)));
+	let unknown_expiry = calls.enqueue(CallKind::Complete, None);
+	let later = calls.enqueue(CallKind::Complete, Some(now + Duration::from_secs(3_600)));
+	let urgent = calls.enqueue(CallKind::Complete, Some(now + Duration::from_secs(30)));
+	let most_urgent = calls.enqueue(CallKind::Complete, Some(now + Duration::from_secs(10)));
+
+	for ticket in [most_urgent, urgent, later, unknown_expiry, lock] {
+		assert_eq!(calls.next_in_line(), Some(ticket));
+		assert_eq!(calls.poll_acquire(ticket, &mut cx), Poll::Ready(()));
+		calls.release();
+	}
+}
+
+#[test]
+fn test_call_limiter_starvation() {
+	let mut cx = Context::from_waker(noop_waker_ref());
+	let mut calls = CallLimiter::new(CallLimitConfig {
+		max_in_flight: Some(1),
+		queue_order: CallQueueOrder::Priority,
+		max_queue_wait: Some(Duration::from_millis(50)),
+		..Default::default()
+	});
+	let lock = calls.enqueue(CallKind::Lock, None);
+	let complete = calls.enqueue(CallKind::Complete, None);
+	assert_eq!(calls.next_in_line(), Some(complete));
+
+	// The lock waited long enough to go ahead of completions
+	std::thread::sleep(Duration::from_millis(60));
+	let later_complete = calls.enqueue(CallKind::Complete, None);
+	assert_eq!(calls.poll_acquire(later_complete, &mut cx), Poll::Pending);
+	assert_eq!(calls.poll_acquire(lock, &mut cx), Poll::Ready(()));
+}
+
+#[test]
+fn test_call_limiter_rate_limit() {
+	let mut cx = Context::from_waker(noop_waker_ref());
+	let mut calls = CallLimiter::new(CallLimitConfig {
+		rate_limit: Some(RateLimit { capacity: 2, refill_interval: Duration::from_millis(50) }),
+		..Default::default()
+	});
+	let tickets: Vec<_> = (0..3).map(|_| calls.enqueue(CallKind::Lock, None)).collect();
+	assert_eq!(calls.poll_acquire(tickets[0], &mut cx), Poll::Ready(()));
+	assert_eq!(calls.poll_acquire(tickets[1], &mut cx), Poll::Ready(()));
+	// The burst is used up, whether the calls returned or not
+	calls.release();
+	calls.release();
+	assert_eq!(calls.poll_acquire(tickets[2], &mut cx), Poll::Pending);
+
+	std::thread::sleep(Duration::from_millis(60));
+	assert_eq!(calls.poll_acquire(tickets[2], &mut cx), Poll::Ready(()));
+}
+
+/// Counts how often a task was woken.
+#[derive(Default)]
+struct WakeCount(AtomicUsize);
+
+impl WakeCount {
+	fn get(&self) -> usize {
+		self.0.load(Ordering::SeqCst)
+	}
+}
+
+impl Wake for WakeCount {
+	fn wake(self: Arc<Self>) {
+		self.0.fetch_add(1, Ordering::SeqCst);
+	}
+}
+
+#[test]
+fn test_call_limiter_wakes_next_call_only() {
+	let mut calls =
+		CallLimiter::new(CallLimitConfig { max_in_flight: Some(1), ..Default::default() });
+	let tickets: Vec<_> = (0..4).map(|_| calls.enqueue(CallKind::Lock, None)).collect();
+	let wakes: Vec<_> = (0..4).map(|_| Arc::new(WakeCount::default())).collect();
+	let wakers: Vec<_> = wakes.iter().map(|wake| Waker::from(wake.clone())).collect();
+	let wake_counts = || wakes.iter().map(|wake| wake.get()).collect::<Vec<_>>();
+
+	let mut cx = Context::from_waker(&wakers[0]);
+	assert_eq!(calls.poll_acquire(tickets[0], &mut cx), Poll::Ready(()));
+	// Waiting calls do not wake each other
+	for _ in 0..3 {
+		for n in 1..4 {
+			let mut cx = Context::from_waker(&wakers[n]);
+			assert_eq!(calls.poll_acquire(tickets[n], &mut cx), Poll::Pending);
+		}
+	}
+	assert_eq!(wake_counts(), [0, 0, 0, 0]);
+
+	calls.release();
+	assert_eq!(wake_counts(), [0, 1, 0, 0]);
+
+	// The call behind a cancelled one is woken in its place
+	calls.cancel(tickets[1]);
+	assert_eq!(wake_counts(), [0, 1, 1, 0]);
+	let mut cx = Context::from_waker(&wakers[2]);
+	assert_eq!(calls.poll_acquire(tickets[2], &mut cx), Poll::Ready(()));
+	assert_eq!(wake_counts(), [0, 1, 1, 0]);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_queues_rate_limited_locks() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_2: ChainConfig {
+			call_limit: CallLimitConfig {
+				max_in_flight: Some(1),
+				rate_limit: Some(RateLimit {
+					capacity: 1,
+					refill_interval: Duration::from_millis(100),
+				}),
+				..Default::default()
+			},
+			..test_chain_config()
+		},
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	for hash_lock in ["first", "second", "third"] {
+		initiate_transfer(&mut blockchain_1_client, TestTransfer::new(hash_lock)).await;
+	}
+
+	let mut locked = 0;
+	let mut max_queue_depth = 0;
+	while locked < 3 {
+		let event = bridge_service.next().await.expect("No event");
+		if let Some(BridgeContractCounterpartyEvent::Locked(_)) = event.B2C_ContractEvent() {
+			locked += 1;
+		}
+		let queue_depth = bridge_service.calls_b2.lock().unwrap().queue_depth();
+		max_queue_depth = max_queue_depth.max(queue_depth);
+	}
+	assert!(max_queue_depth > 0);
+	let calls = bridge_service.calls_b2.lock().unwrap();
+	assert_eq!(calls.queue_depth(), 0);
+	assert_eq!(calls.in_flight(), 0);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_holds_queued_locks_while_paused() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_2: ChainConfig {
+			call_limit: CallLimitConfig {
+				rate_limit: Some(RateLimit {
+					capacity: 1,
+					refill_interval: Duration::from_millis(300),
+				}),
+				..Default::default()
+			},
+			..test_chain_config()
+		},
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	for hash_lock in ["first", "second"] {
+		initiate_transfer(&mut blockchain_1_client, TestTransfer::new(hash_lock)).await;
+	}
+
+	// The second lock waits for a rate token when the first is placed
+	loop {
+		let event = bridge_service.next().await.expect("No event");
+		if let Some(BridgeContractCounterpartyEvent::Locked(_)) = event.B2C_ContractEvent() {
+			break;
+		}
+	}
+	bridge_service.trip_circuit_breaker(BreakerScope::Direction(Direction::B1ToB2), "maintenance");
+
+	let deadline = tokio::time::Instant::now() + Duration::from_millis(600);
+	while let Ok(Some(event)) = tokio::time::timeout_at(deadline, bridge_service.next()).await {
+		assert!(event.B2C_ContractEvent().is_none(), "Lock placed while paused: {:?}", event);
+	}
+	assert_eq!(bridge_service.calls_b2.lock().unwrap().queue_depth(), 0);
+
+	bridge_service.reset_circuit_breaker(BreakerScope::Direction(Direction::B1ToB2));
+	loop {
+		let event = bridge_service.next().await.expect("No event");
+		if let Some(BridgeContractCounterpartyEvent::Locked(_)) = event.B2C_ContractEvent() {
+			break;
+		}
+	}
+}
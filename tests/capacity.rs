@@ -0,0 +1,249 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_contracts::{BridgeContractCounterparty, BridgeContractInitiator},
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::SwapCapacity,
+		direction::Direction,
+		events::{CEvent, CapacityEvent, Event},
+		time_lock::{TimeLockKind, TimeLockPolicy},
+		BridgeServiceConfig, ChainConfig,
+	},
+	chain_clock::{ChainTime, ManualClock},
+	types::{Convert, HashLockPreImage, TimeLock},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, next_matching, setup_bridge_service,
+	test_chain_config, B1Service, B2Client, B2Service, SetupBridgeServiceResult, TestTransfer,
+};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+const BLOCK_TIME: Duration = Duration::from_secs(12);
+const NOW: ChainTime = ChainTime { height: 500, timestamp: 1_700_000_000 };
+
+/// The lock on blockchain 2 is placed.
+fn is_locked(event: &Event<B1Service, B2Service>) -> bool {
+	matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_)))
+}
+
+/// A transfer out of blockchain 1 is taken on.
+fn is_initiated(event: &Event<B1Service, B2Service>) -> bool {
+	matches!(event.B1I_ContractEvent(), Some(BridgeContractInitiatorEvent::Initiated(_)))
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_stops_taking_transfers_at_capacity() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		max_active_swaps_b1_to_b2: Some(1),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	let full = SwapCapacity { active: 1, max: Some(1) };
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("first")).await;
+	let event = bridge_service.next().await.expect("No event");
+	let first_id = event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+	let event = bridge_service.next().await.expect("No event");
+	assert_eq!(event.capacity(), Some(&CapacityEvent::Reached(Direction::B1ToB2, full)));
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+
+	// The second transfer stays in the initiator monitoring
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("second")).await;
+	let next = tokio::time::timeout(Duration::from_millis(200), bridge_service.next()).await;
+	assert!(next.is_err(), "Unexpected event: {next:?}");
+	assert_eq!(bridge_service.capacity(Direction::B1ToB2), full);
+	assert_eq!(bridge_service.capacity(Direction::B2ToB1), SwapCapacity { active: 0, max: None });
+
+	// Completing the first swap frees capacity for the second
+	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut blockchain_2_client,
+		Convert::convert(&first_id),
+		HashLockPreImage::new(b"first".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+
+	let mut freed = false;
+	let mut second_initiated = false;
+	while !(freed && second_initiated) {
+		match bridge_service.next().await.expect("No event") {
+			Event::Capacity(CapacityEvent::Freed(Direction::B1ToB2, _)) => freed = true,
+			Event::B1I(event) => {
+				if let Some(BridgeContractInitiatorEvent::Initiated(details)) =
+					event.contract_event()
+				{
+					assert_ne!(details.bridge_transfer_id, first_id);
+					second_initiated = true;
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_follows_refunds_at_capacity() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		max_active_swaps_b1_to_b2: Some(1),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("first")).await;
+	let event =
+		next_matching(&mut bridge_service, |event| event.B1I_ContractEvent().is_some()).await;
+	let first_id = event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+	next_matching(&mut bridge_service, is_locked).await;
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("second")).await;
+
+	// The refund of the first transfer is seen past the second one, which is held back
+	blockchain_1_client
+		.refund_bridge_transfer(first_id.clone())
+		.await
+		.expect("refund_bridge_transfer failed");
+	next_matching(&mut bridge_service, |event| matches!(event.B2C(), Some(CEvent::LockAborted(_))))
+		.await;
+	let event = next_matching(&mut bridge_service, is_initiated).await;
+	assert_ne!(event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id(), &first_id);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_takes_back_expired_locks_at_capacity() {
+	let clock = ManualClock::new(NOW, BLOCK_TIME);
+	let chain_config = ChainConfig {
+		time_lock_kind: TimeLockKind::AbsoluteTimestamp,
+		clock: Some(Arc::new(clock.clone())),
+		..test_chain_config()
+	};
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_1: chain_config.clone(),
+		blockchain_2: chain_config,
+		time_lock_policy: TimeLockPolicy { safety_margin: HOUR },
+		max_active_swaps_b1_to_b2: Some(1),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	// The counterparty lock expires two hours from now
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			time_lock: TimeLock::AbsoluteTimestamp(NOW.timestamp + 3 * 3600),
+			..TestTransfer::new("first")
+		},
+	)
+	.await;
+	next_matching(&mut bridge_service, is_locked).await;
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			time_lock: TimeLock::AbsoluteTimestamp(NOW.timestamp + 6 * 3600),
+			..TestTransfer::new("second")
+		},
+	)
+	.await;
+	let next = tokio::time::timeout(Duration::from_millis(200), bridge_service.next()).await;
+	assert!(next.is_err(), "Unexpected event: {next:?}");
+
+	// The recipient never claims, so the bridge takes its lock back once it expires
+	clock.set(ChainTime { height: NOW.height + 600, timestamp: NOW.timestamp + 2 * 3600 });
+	next_matching(&mut bridge_service, |event| matches!(event.B2C(), Some(CEvent::LockAborted(_))))
+		.await;
+	next_matching(&mut bridge_service, is_initiated).await;
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_resumes_polling_once_capacity_frees() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		max_active_swaps_b1_to_b2: Some(1),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("first")).await;
+	let event = next_matching(&mut bridge_service, is_initiated).await;
+	let first_id = event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+	next_matching(&mut bridge_service, is_locked).await;
+
+	// Both transfers are held back while the first swap runs
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("second")).await;
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("third")).await;
+	let next = tokio::time::timeout(Duration::from_millis(200), bridge_service.next()).await;
+	assert!(next.is_err(), "Unexpected event: {next:?}");
+
+	// Each finished swap lets the next transfer through, in the order they were initiated
+	let mut finished = (first_id, "first");
+	for hash_lock in ["second", "third"] {
+		<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
+			&mut blockchain_2_client,
+			Convert::convert(&finished.0),
+			HashLockPreImage::new(finished.1.as_bytes().to_vec()),
+		)
+		.await
+		.expect("complete_bridge_transfer failed");
+		let event = next_matching(&mut bridge_service, is_initiated).await;
+		let Some(BridgeContractInitiatorEvent::Initiated(details)) = event.B1I_ContractEvent()
+		else {
+			unreachable!()
+		};
+		assert_eq!(details.hash_lock.0, hash_lock.into());
+		next_matching(&mut bridge_service, is_locked).await;
+		finished = (details.bridge_transfer_id.clone(), hash_lock);
+	}
+
+	// Transfers initiated once capacity is free again are taken on straight from the monitoring
+	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut blockchain_2_client,
+		Convert::convert(&finished.0),
+		HashLockPreImage::new(finished.1.as_bytes().to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+	next_matching(&mut bridge_service, |event| {
+		matches!(event.capacity(), Some(CapacityEvent::Freed(Direction::B1ToB2, _)))
+	})
+	.await;
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("fourth")).await;
+	next_matching(&mut bridge_service, is_locked).await;
+}
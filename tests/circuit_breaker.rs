@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_contracts::BridgeContractCounterparty,
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::ActiveSwapMapError,
+		assets::{AssetConfig, AssetMapping},
+		circuit_breaker::{
+			BreakerScope, CircuitBreaker, CircuitBreakerConfig, CircuitBreakerEvent, TripReason,
+		},
+		direction::Direction,
+		events::{IEvent, IWarn},
+		BridgeServiceConfig,
+	},
+	types::{Amount, AssetId, Convert, HashLockPreImage},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, setup_bridge_service, B2Client,
+	SetupBridgeServiceResult, TestTransfer, TEST_ASSET,
+};
+
+#[test]
+fn test_circuit_breaker_scopes() {
+	let mut breaker = CircuitBreaker::new(CircuitBreakerConfig::default());
+	let scope = BreakerScope::Direction(Direction::B1ToB2);
+	assert_eq!(
+		breaker.trip(scope, TripReason::Anomaly),
+		Some(CircuitBreakerEvent::Tripped(scope, TripReason::Anomaly))
+	);
+	// Already tripped, nothing to report
+	assert_eq!(breaker.trip(scope, TripReason::Manual("again".into())), None);
+	assert!(breaker.is_tripped(Direction::B1ToB2));
+	assert!(!breaker.is_tripped(Direction::B2ToB1));
+
+	breaker.trip(BreakerScope::Global, TripReason::Manual("maintenance".into()));
+	assert_eq!(breaker.reset(scope), Some(CircuitBreakerEvent::Reset(scope)));
+	assert_eq!(breaker.reset(scope), None);
+	// The global breaker covers both directions
+	assert!(breaker.is_tripped(Direction::B1ToB2));
+	assert!(breaker.is_tripped(Direction::B2ToB1));
+	breaker.reset(BreakerScope::Global);
+	assert!(!breaker.is_tripped(Direction::B1ToB2));
+}
+
+#[test]
+fn test_circuit_breaker_error_rate() {
+	let mut breaker = CircuitBreaker::new(CircuitBreakerConfig {
+		max_errors: Some(3),
+		error_window: Duration::from_secs(60),
+		..Default::default()
+	});
+	assert_eq!(breaker.record_error(Direction::B2ToB1), None);
+	assert_eq!(breaker.record_error(Direction::B2ToB1), None);
+	assert_eq!(breaker.record_error(Direction::B1ToB2), None);
+	assert_eq!(
+		breaker.record_error(Direction::B2ToB1),
+		Some(CircuitBreakerEvent::Tripped(
+			BreakerScope::Direction(Direction::B2ToB1),
+			TripReason::ErrorRate { errors: 3, window: Duration::from_secs(60) }
+		))
+	);
+	assert!(!breaker.is_tripped(Direction::B1ToB2));
+
+	// Without a threshold errors never trip
+	let mut breaker = CircuitBreaker::new(CircuitBreakerConfig::default());
+	for _ in 0..10 {
+		assert_eq!(breaker.record_error(Direction::B1ToB2), None);
+	}
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_tripped_breaker_completes_locked_swaps() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(1000), ..TestTransfer::new("hash_lock") },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	let bridge_transfer_id =
+		event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+
+	bridge_service.trip_circuit_breaker(BreakerScope::Global, "maintenance");
+	let event = bridge_service.next().await.expect("No event");
+	assert_eq!(
+		event.circuit_breaker(),
+		Some(&CircuitBreakerEvent::Tripped(
+			BreakerScope::Global,
+			TripReason::Manual("maintenance".into())
+		))
+	);
+
+	// No new locks
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(1000), ..TestTransfer::new("other_hash_lock") },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn),
+		Some(IWarn::TransferRejected(_, ActiveSwapMapError::Paused))
+	));
+
+	// The swap already locked still completes
+	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut blockchain_2_client,
+		Convert::convert(&bridge_transfer_id),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B2C_ContractEvent(),
+		Some(BridgeContractCounterpartyEvent::Completed(_))
+	));
+	let event = bridge_service.next().await.expect("No event");
+	assert_eq!(
+		event.B1I_ContractEvent(),
+		Some(&BridgeContractInitiatorEvent::Completed(bridge_transfer_id))
+	);
+
+	bridge_service.reset_circuit_breaker(BreakerScope::Global);
+	let event = bridge_service.next().await.expect("No event");
+	assert_eq!(event.circuit_breaker(), Some(&CircuitBreakerEvent::Reset(BreakerScope::Global)));
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(1000), ..TestTransfer::new("third_hash_lock") },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_trips_on_insufficient_liquidity() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		assets_b1_to_b2: AssetMapping::new()
+			.with_asset(TEST_ASSET, AssetConfig::new(TEST_ASSET).with_liquidity(Amount(5_000))),
+		circuit_breaker: CircuitBreakerConfig {
+			trip_on_insufficient_liquidity: true,
+			..Default::default()
+		},
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(6_000), ..TestTransfer::new("hash_lock") },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn),
+		Some(IWarn::TransferRejected(_, ActiveSwapMapError::InsufficientLiquidity { .. }))
+	));
+	let event = bridge_service.next().await.expect("No event");
+	assert_eq!(
+		event.circuit_breaker(),
+		Some(&CircuitBreakerEvent::Tripped(
+			BreakerScope::Direction(Direction::B1ToB2),
+			TripReason::InsufficientLiquidity(AssetId::from(TEST_ASSET))
+		))
+	);
+	assert!(bridge_service.active_swaps_b1_to_b2.is_paused());
+	assert!(!bridge_service.active_swaps_b2_to_b1.is_paused());
+}
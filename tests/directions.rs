@@ -0,0 +1,168 @@
+use futures::StreamExt;
+use rand::SeedableRng;
+use test_log::test;
+
+use bridge_shared::{
+	blockchain_service::{AbstractBlockchainService, NoCounterparty},
+	bridge_contracts::{BridgeContractCounterparty, BridgeContractInitiator},
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::ActiveSwapMapError,
+		direction::{Direction, EnabledDirections},
+		events::{CEvent, IEvent, IWarn},
+		BridgeService, BridgeServiceConfig,
+	},
+	types::{BridgeTransferId, HashLockPreImage},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, setup_bridge_service,
+	testing::{
+		blockchain::{AbstractBlockchain, AbstractBlockchainClient},
+		rng::{RngSeededClone, TestRng},
+	},
+	BC1Address, BC1Hash, BC2Address, BC2Hash, CounterpartyContractMonitoring,
+	InitiatorContractMonitoring, SetupBridgeServiceResult, TestTransfer,
+};
+
+#[test]
+fn test_enabled_directions() {
+	let both = EnabledDirections::default();
+	assert!(both.is_enabled(Direction::B1ToB2));
+	assert!(both.is_enabled(Direction::B2ToB1));
+
+	let only = EnabledDirections::only(Direction::B2ToB1);
+	assert!(!only.is_enabled(Direction::B1ToB2));
+	assert!(only.is_enabled(Direction::B2ToB1));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_disabled_direction_is_ignored() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		directions: EnabledDirections::only(Direction::B1ToB2),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_2_client, TestTransfer::new("b2_hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B2I().and_then(IEvent::warn),
+		Some(IWarn::DirectionDisabled(BridgeContractInitiatorEvent::Initiated(_)))
+	));
+	assert_eq!(bridge_service.active_swaps_b2_to_b1.capacity().active, 0);
+
+	// The enabled direction is unaffected
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("b1_hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B1I_ContractEvent(), Some(BridgeContractInitiatorEvent::Initiated(_))));
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_sees_swaps_of_disabled_direction_through() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	let bridge_transfer_id =
+		event.B1I_ContractEvent().expect("not a b1i event").bridge_transfer_id().clone();
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+
+	// Nothing new is taken on in the direction once it is disabled
+	bridge_service.directions = EnabledDirections::only(Direction::B2ToB1);
+	assert_eq!(
+		bridge_service
+			.relay_secret(Direction::B1ToB2, HashLockPreImage::new(b"hash_lock".to_vec())),
+		Err(ActiveSwapMapError::DirectionDisabled)
+	);
+
+	// The swap already locked is still refunded
+	blockchain_1_client
+		.refund_bridge_transfer(bridge_transfer_id)
+		.await
+		.expect("refund_bridge_transfer failed");
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B1I_ContractEvent(), Some(BridgeContractInitiatorEvent::Refunded(_))));
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C(), Some(CEvent::LockAborted(_))), "{event:?}");
+	assert_eq!(bridge_service.active_swaps_b1_to_b2.capacity().active, 0);
+}
+
+#[test(tokio::test)]
+async fn test_no_counterparty_calls_fail() {
+	let mut counterparty = NoCounterparty::<BC2Address, BC2Hash>::new();
+	let result =
+		counterparty.abort_bridge_transfer(BridgeTransferId(BC2Hash::from("transfer"))).await;
+	assert!(result.is_err());
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_one_sided_chain() {
+	let mut rng = TestRng::from_seed([0u8; 32]);
+	let mut blockchain_1 =
+		AbstractBlockchain::<BC1Address, BC1Hash, _>::new(rng.seeded_clone(), "Blockchain1");
+	let mut blockchain_2 =
+		AbstractBlockchain::<BC2Address, BC2Hash, _>::new(rng.seeded_clone(), "Blockchain2");
+
+	let client_1 =
+		AbstractBlockchainClient::new(blockchain_1.connection(), rng.seeded_clone(), 0.0, 0.00);
+	let mut client_2 =
+		AbstractBlockchainClient::new(blockchain_2.connection(), rng.seeded_clone(), 0.0, 0.00);
+
+	let blockchain_1_service = AbstractBlockchainService {
+		initiator_contract: client_1.clone(),
+		initiator_monitoring: InitiatorContractMonitoring::build(blockchain_1.add_event_listener()),
+		counterparty_contract: client_1.clone(),
+		counterparty_monitoring: CounterpartyContractMonitoring::build(
+			blockchain_1.add_event_listener(),
+		),
+		_phantom: Default::default(),
+	};
+	// Blockchain 2 only has an initiator contract, transfers can only leave it
+	let blockchain_2_service = AbstractBlockchainService {
+		initiator_contract: client_2.clone(),
+		initiator_monitoring: InitiatorContractMonitoring::build(blockchain_2.add_event_listener()),
+		counterparty_contract: NoCounterparty::new(),
+		counterparty_monitoring: NoCounterparty::new(),
+		_phantom: Default::default(),
+	};
+	let mut bridge_service = BridgeService::new(
+		blockchain_1_service,
+		blockchain_2_service,
+		BridgeServiceConfig {
+			directions: EnabledDirections::only(Direction::B2ToB1),
+			..default_bridge_service_config()
+		},
+	);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut client_2, TestTransfer::new("hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2I_ContractEvent(), Some(BridgeContractInitiatorEvent::Initiated(_))));
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B1C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+}
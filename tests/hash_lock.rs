@@ -0,0 +1,374 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_contracts::BridgeContractCounterparty,
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::{ActiveSwapMapError, ActiveSwapState},
+		events::{CEvent, CWarn, IEvent, IWarn},
+		BridgeServiceConfig, ChainConfig,
+	},
+	hash_lock::{
+		Blake2b256, HashLockScheme, Keccak256, SecretConstraintError, SecretConstraints,
+		SecretFormat, Sha256,
+	},
+	types::{Convert, HashLock, HashLockPreImage},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, hash_static_string, hash_vec_u8, initiate_transfer,
+	setup_bridge_service, test_chain_config, B2Client, BC2Hash, SetupBridgeServiceResult,
+	TestTransfer,
+};
+
+/// Hashes like the test chains, but is reported as a different hash function.
+#[derive(Debug)]
+struct OtherHashLockScheme;
+
+impl HashLockScheme for OtherHashLockScheme {
+	fn name(&self) -> &'static str {
+		"other"
+	}
+
+	fn digest(&self, pre_image: &HashLockPreImage) -> Vec<u8> {
+		hash_vec_u8(pre_image.expose_secret()).to_vec()
+	}
+}
+
+#[test]
+fn test_pre_image_is_redacted() {
+	let secret = HashLockPreImage::new(b"hash_lock".to_vec());
+	assert_eq!(format!("{secret:?}"), "HashLockPreImage(<redacted>)");
+	assert_eq!(secret.to_string(), "<redacted>");
+	assert!(!format!("{:?}", Some(secret.clone())).contains("hash_lock"));
+	assert_eq!(secret.expose_secret(), b"hash_lock");
+
+	assert_eq!(secret, HashLockPreImage::new(b"hash_lock".to_vec()));
+	assert_ne!(secret, HashLockPreImage::new(b"hash_locks".to_vec()));
+	assert_ne!(secret, HashLockPreImage::new(b"hash_loch".to_vec()));
+}
+
+#[test]
+fn test_hash_lock_schemes() {
+	let pre_image = HashLockPreImage::new(b"abc".to_vec());
+
+	let cases: [(&dyn HashLockScheme, &str); 3] = [
+		(&Sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+		(&Keccak256, "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"),
+		(&Blake2b256, "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"),
+	];
+
+	for (scheme, digest) in cases {
+		let digest = hex::decode(digest).expect("invalid hex");
+		assert_eq!(scheme.digest(&pre_image), digest, "{scheme:?}");
+		assert!(scheme.verify(&pre_image, &digest));
+		assert!(!scheme.verify(&HashLockPreImage::new(b"abd".to_vec()), &digest));
+	}
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_rejects_missing_counterparty_hash_lock() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_2: ChainConfig {
+			hash_lock_scheme: Arc::new(OtherHashLockScheme),
+			..test_chain_config()
+		},
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+
+	let event = bridge_service.next().await.expect("No event");
+	let warn = event.B1I().and_then(IEvent::warn).expect("not a b1i warn event");
+	assert!(matches!(
+		warn,
+		IWarn::TransferRejected(
+			_,
+			ActiveSwapMapError::MissingCounterpartyHashLock {
+				initiator: "test",
+				counterparty: "other"
+			}
+		)
+	));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_rejects_inconsistent_hash_locks() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	// Chains sharing a hash function have to agree on the committed hash lock
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			counterparty_hash_lock: Some(HashLock(b"mismatch".to_vec())),
+			..Default::default()
+		},
+	)
+	.await;
+
+	let event = bridge_service.next().await.expect("No event");
+	let warn = event.B1I().and_then(IEvent::warn).expect("not a b1i warn event");
+	assert!(matches!(warn, IWarn::TransferRejected(_, ActiveSwapMapError::InconsistentHashLocks)));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_completes_with_dual_hash_locks() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_2: ChainConfig {
+			hash_lock_scheme: Arc::new(OtherHashLockScheme),
+			..test_chain_config()
+		},
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	let counterparty_hash_lock = HashLock(hash_static_string("hash_lock").to_vec());
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { counterparty_hash_lock: Some(counterparty_hash_lock), ..Default::default() },
+	)
+	.await;
+
+	let event = bridge_service.next().await.expect("No event");
+	let transfer_initiated_event = event.B1I_ContractEvent().expect("Not a B1I event");
+	let bridge_transfer_id = transfer_initiated_event.bridge_transfer_id().clone();
+
+	// The counterparty lock uses the digest the initiator committed to
+	let event = bridge_service.next().await.expect("No event");
+	let event = event.B2C_ContractEvent().expect("Not a B2C event");
+	let BridgeContractCounterpartyEvent::Locked(details) = event else {
+		panic!("Not a locked event: {event:?}");
+	};
+	assert_eq!(details.hash_lock, HashLock(BC2Hash::from("hash_lock")));
+
+	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut blockchain_2_client,
+		Convert::convert(&bridge_transfer_id),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B2C_ContractEvent(),
+		Some(BridgeContractCounterpartyEvent::Completed(_))
+	));
+
+	let event = bridge_service.next().await.expect("No event");
+	assert_eq!(
+		event.B1I_ContractEvent(),
+		Some(&BridgeContractInitiatorEvent::Completed(bridge_transfer_id))
+	);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_rejects_secret_not_unlocking_initiator() {
+	// Blockchain 1 is configured with a different hash function than its contracts use, so the
+	// secret accepted on blockchain 2 does not verify against the initiator hash lock.
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_1: ChainConfig::default(),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	let counterparty_hash_lock = HashLock(hash_static_string("hash_lock").to_vec());
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { counterparty_hash_lock: Some(counterparty_hash_lock), ..Default::default() },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	let transfer_initiated_event = event.B1I_ContractEvent().expect("Not a B1I event");
+	let bridge_transfer_id = transfer_initiated_event.bridge_transfer_id().clone();
+
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B2C_ContractEvent().is_some());
+
+	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut blockchain_2_client,
+		Convert::convert(&bridge_transfer_id),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B2C().and_then(CEvent::warn).expect("not a b2c warn event"),
+		CWarn::InvalidHashLockPreImage(_)
+	));
+
+	// The swap is left untouched, the completion is neither attempted nor retried
+	let active_swap =
+		bridge_service.active_swaps_b1_to_b2.get(&bridge_transfer_id).expect("swap removed");
+	assert!(matches!(active_swap.state, ActiveSwapState::WaitingForUnlockedEvent));
+}
+
+#[test]
+fn test_secret_constraints() {
+	let constraints =
+		SecretConstraints { min_length: 4, max_length: 8, format: SecretFormat::Utf8 };
+	assert_eq!(constraints.check(&HashLockPreImage::new(b"secret".to_vec())), Ok(()));
+	assert_eq!(
+		constraints.check(&HashLockPreImage::new(b"abc".to_vec())),
+		Err(SecretConstraintError::TooShort { min: 4, actual: 3 })
+	);
+	assert_eq!(
+		constraints.check(&HashLockPreImage::new(b"hash_lock".to_vec())),
+		Err(SecretConstraintError::TooLong { max: 8, actual: 9 })
+	);
+	assert_eq!(
+		constraints.check(&HashLockPreImage::new(vec![0xff; 4])),
+		Err(SecretConstraintError::InvalidFormat(SecretFormat::Utf8))
+	);
+
+	assert!(constraints.is_within(&SecretConstraints::default()));
+	assert!(!SecretConstraints::default().is_within(&constraints));
+	assert!(
+		!SecretConstraints { format: SecretFormat::Bytes, ..constraints }.is_within(&constraints)
+	);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_rejects_incompatible_secret_constraints() {
+	// Blockchain 2 accepts pre-images blockchain 1 cannot take
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_1: ChainConfig {
+			secret_constraints: SecretConstraints { max_length: 32, ..Default::default() },
+			..test_chain_config()
+		},
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn).expect("not a b1i warn event"),
+		IWarn::TransferRejected(_, ActiveSwapMapError::IncompatibleSecretConstraints)
+	));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_checks_contract_secret_constraints() {
+	// Both chains are configured alike, but the initiator contract of blockchain 1 reports a
+	// tighter limit than the counterparty contract of blockchain 2 accepts
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+	bridge_service.active_swaps_b1_to_b2.initiator_contract.secret_constraints =
+		Some(SecretConstraints { max_length: 32, ..Default::default() });
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+
+	// Nothing is locked
+	let event = bridge_service.next().await.expect("No event");
+	let Some(IWarn::TransferRejected(details, ActiveSwapMapError::IncompatibleSecretConstraints)) =
+		event.B1I().and_then(IEvent::warn)
+	else {
+		panic!("Not a rejected transfer: {event:?}");
+	};
+	assert!(!bridge_service.active_swaps_b1_to_b2.already_executing(&details.bridge_transfer_id));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_flags_secret_not_replayable() {
+	// The test contracts do not enforce the configured limits, standing in for a counterparty
+	// contract that is more permissive than documented
+	let chain_config = ChainConfig {
+		secret_constraints: SecretConstraints { max_length: 8, ..Default::default() },
+		..test_chain_config()
+	};
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_1: chain_config.clone(),
+		blockchain_2: chain_config,
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+	let event = bridge_service.next().await.expect("No event");
+	let bridge_transfer_id =
+		event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B2C_ContractEvent().is_some());
+
+	<B2Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut blockchain_2_client,
+		Convert::convert(&bridge_transfer_id),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B2C().and_then(CEvent::warn).expect("not a b2c warn event"),
+		CWarn::SecretNotReplayable(_, SecretConstraintError::TooLong { max: 8, actual: 9 })
+	));
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_monitoring::BridgeContractCounterpartyEvent,
+	bridge_service::{
+		events::{IEvent, IWarn},
+		hash_lock_index::{HashLockIndex, HashLockReuseConfig, HashLockReusePolicy},
+		BridgeServiceConfig,
+	},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, setup_bridge_service,
+	SetupBridgeServiceResult, TestTransfer,
+};
+
+fn config(policy: HashLockReusePolicy) -> BridgeServiceConfig {
+	BridgeServiceConfig {
+		hash_lock_reuse: HashLockReuseConfig { policy, ..Default::default() },
+		..default_bridge_service_config()
+	}
+}
+
+#[test]
+fn test_hash_lock_index_retention() {
+	let mut index = HashLockIndex::new(HashLockReuseConfig {
+		policy: HashLockReusePolicy::Reject,
+		retention: Duration::from_secs(60),
+	});
+	index.insert(&[b"lock"], b"first");
+	assert_eq!(index.conflicting_transfer(&[b"other", b"lock"]), Some(b"first".to_vec()));
+
+	// Only the owner releases a hash lock, recent ones still conflict
+	index.release(&[b"lock"], b"second");
+	index.release(&[b"lock"], b"first");
+	assert_eq!(index.conflicting_transfer(&[b"lock"]), Some(b"first".to_vec()));
+
+	let mut index =
+		HashLockIndex::new(HashLockReuseConfig { retention: Duration::ZERO, ..index.config });
+	index.insert(&[b"lock"], b"first");
+	index.release(&[b"lock"], b"first");
+	assert_eq!(index.conflicting_transfer(&[b"lock"]), None);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_rejects_reused_hash_lock() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(config(HashLockReusePolicy::Reject));
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	let first_id = event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn),
+		Some(IWarn::HashLockReused { conflicting_transfer, held: false, .. })
+			if conflicting_transfer.as_slice() == first_id.0.as_ref()
+	));
+
+	// Reuse is detected across directions too
+	initiate_transfer(&mut blockchain_2_client, TestTransfer::new("hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B2I().and_then(IEvent::warn),
+		Some(IWarn::HashLockReused { held: false, .. })
+	));
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("other_hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_holds_reused_hash_lock() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(config(HashLockReusePolicy::Hold));
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("hash_lock")).await;
+	let event = bridge_service.next().await.expect("No event");
+	let Some(IWarn::HashLockReused { details, held: true, .. }) =
+		event.B1I().and_then(IEvent::warn)
+	else {
+		panic!("Not a held hash lock reuse: {event:?}");
+	};
+	let held_id = details.bridge_transfer_id.clone();
+	assert!(bridge_service.active_swaps_b1_to_b2.already_executing(&held_id));
+	assert_eq!(bridge_service.active_swaps_b1_to_b2.held_transfers().count(), 1);
+
+	// The operator releases the held transfer, which is then locked
+	bridge_service
+		.active_swaps_b1_to_b2
+		.release_held_transfer(&held_id)
+		.expect("release_held_transfer failed");
+	assert_eq!(bridge_service.active_swaps_b1_to_b2.held_transfers().count(), 0);
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+}
@@ -0,0 +1,310 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use rand::SeedableRng;
+use test_log::test;
+
+use bridge_shared::{
+	blockchain_service::AbstractBlockchainService,
+	bridge_contracts::{BridgeContractCounterparty, BridgeContractCounterpartyError},
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::ActiveSwapConfig,
+		events::{CEvent, IEvent},
+		router::{
+			multi_hop::CompoundSwapStatus, BridgeRouter, BridgeRouterConfig, Route, RouteConfig,
+			RouterEvent,
+		},
+		time_lock::TimeLockPolicy,
+		ChainConfig,
+	},
+	chain_clock::{ChainTime, ManualClock},
+	types::{ChainId, HashLockPreImage, LockDetails, RecipientAddress, TimeLock},
+};
+
+mod shared;
+
+use crate::shared::{
+	initiate_transfer, test_assets, test_chain_config,
+	testing::{
+		blockchain::{
+			client::{CallConfig, ErrorConfig, MethodName},
+			AbstractBlockchain, AbstractBlockchainClient,
+		},
+		rng::{RngSeededClone, TestRng},
+	},
+	B1Client, B1Service, BC1Address, BC1Hash, CounterpartyContractMonitoring,
+	InitiatorContractMonitoring, TestTransfer,
+};
+
+type Event = RouterEvent<BC1Address, BC1Hash>;
+
+const BLOCK_TIME: Duration = Duration::from_secs(12);
+const NOW: ChainTime = ChainTime { height: 500, timestamp: 1_700_000_000 };
+
+/// Connects chain 1 to chain 3 through chain 2, whose hop address is "hop". With `via_clock`,
+/// chain 2 and its initiator contract follow the clock, and refunds before expiry are rejected.
+fn setup_multi_hop_router(
+	safety_margin: Duration,
+	via_clock: Option<ManualClock>,
+) -> (BridgeRouter<B1Service>, Vec<B1Client>) {
+	let mut rng = TestRng::from_seed([0u8; 32]);
+	let mut router = BridgeRouter::new(BridgeRouterConfig {
+		active_swap: ActiveSwapConfig {
+			error_delay: Duration::from_millis(10),
+			..Default::default()
+		},
+		time_lock_policy: TimeLockPolicy { safety_margin },
+		..Default::default()
+	});
+	let mut clients = Vec::new();
+	for chain in 1..=3 {
+		let mut blockchain = AbstractBlockchain::<BC1Address, BC1Hash, _>::new(
+			rng.seeded_clone(),
+			format!("Blockchain{chain}"),
+		);
+		let client =
+			AbstractBlockchainClient::new(blockchain.connection(), rng.seeded_clone(), 0.0, 0.00);
+		let service = AbstractBlockchainService {
+			initiator_contract: client.clone(),
+			initiator_monitoring: InitiatorContractMonitoring::build(
+				blockchain.add_event_listener(),
+			),
+			counterparty_contract: client.clone(),
+			counterparty_monitoring: CounterpartyContractMonitoring::build(
+				blockchain.add_event_listener(),
+			),
+			_phantom: Default::default(),
+		};
+		let mut config = test_chain_config();
+		if let (2, Some(clock)) = (chain, &via_clock) {
+			blockchain.initiator_contract.clock = Some(Arc::new(clock.clone()));
+			config = ChainConfig { clock: Some(Arc::new(clock.clone())), ..config };
+		}
+		router.add_chain(chain, service, config).expect("add_chain failed");
+		tokio::spawn(blockchain);
+		clients.push(client);
+	}
+	for route in [Route::new(1, 2), Route::new(2, 3)] {
+		router
+			.enable_route(route, RouteConfig { assets: test_assets(), ..Default::default() })
+			.expect("enable_route failed");
+	}
+	router.set_hop_address(2, BC1Address("hop"));
+	(router, clients)
+}
+
+/// Collects router events until one matches `until`.
+async fn collect_until(
+	router: &mut BridgeRouter<B1Service>,
+	events: &mut Vec<Event>,
+	until: impl Fn(&Event) -> bool,
+) {
+	tokio::time::timeout(Duration::from_secs(10), async {
+		loop {
+			let event = router.next().await.expect("No event");
+			let done = until(&event);
+			events.push(event);
+			if done {
+				break;
+			}
+		}
+	})
+	.await
+	.expect("Timed out waiting for router events");
+}
+
+fn is_status(status: CompoundSwapStatus) -> impl Fn(&Event) -> bool {
+	move |event| event.compound_swap().map(|(_, event_status)| event_status) == Some(status)
+}
+
+fn statuses(events: &[Event]) -> Vec<CompoundSwapStatus> {
+	events.iter().filter_map(|event| event.compound_swap().map(|(_, status)| status)).collect()
+}
+
+fn locks(events: &[Event], chain: u64) -> Vec<&LockDetails<BC1Address, BC1Hash>> {
+	events
+		.iter()
+		.filter(|event| event.chain() == ChainId(chain))
+		.filter_map(|event| match event.counterparty().and_then(CEvent::contract_event) {
+			Some(BridgeContractCounterpartyEvent::Locked(details)) => Some(details),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_multi_hop_swap_completes() {
+	let (mut router, mut clients) = setup_multi_hop_router(Duration::from_secs(10), None);
+	assert_eq!(router.via_for(ChainId(1), ChainId(3)), Some(ChainId(2)));
+
+	initiate_transfer(
+		&mut clients[0],
+		TestTransfer { destination_chain: Some(ChainId(3)), ..Default::default() },
+	)
+	.await;
+	let mut events = Vec::new();
+	collect_until(&mut router, &mut events, is_status(CompoundSwapStatus::Locked)).await;
+
+	// The first leg is locked for the hop address, the second for the recipient
+	let first_leg = locks(&events, 2);
+	assert_eq!(first_leg.len(), 1);
+	assert_eq!(first_leg[0].recipient_address, RecipientAddress(BC1Address("hop")));
+	assert_eq!(first_leg[0].time_lock, TimeLock::Relative(Duration::from_secs(90)));
+	// The second leg is initiated a safety margin short of the first leg lock
+	let second_leg_initiation = events.iter().find_map(|event| {
+		match (event.chain(), event.initiator().and_then(IEvent::contract_event)) {
+			(ChainId(2), Some(BridgeContractInitiatorEvent::Initiated(details))) => Some(details),
+			_ => None,
+		}
+	});
+	assert_eq!(
+		second_leg_initiation.expect("Second leg not initiated").time_lock,
+		TimeLock::Relative(Duration::from_secs(80))
+	);
+	let second_leg = locks(&events, 3);
+	assert_eq!(second_leg.len(), 1);
+	assert_eq!(second_leg[0].recipient_address, RecipientAddress(BC1Address("recipient")));
+	assert_eq!(second_leg[0].time_lock, TimeLock::Relative(Duration::from_secs(70)));
+	assert_eq!(second_leg[0].hash_lock, first_leg[0].hash_lock);
+	let second_leg_id = second_leg[0].bridge_transfer_id.clone();
+
+	// The recipient claims on chain 3, the bridge completes back to chain 1
+	<B1Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut clients[2],
+		second_leg_id,
+		HashLockPreImage::new(b"hash_lock".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+	collect_until(&mut router, &mut events, is_status(CompoundSwapStatus::Completed)).await;
+
+	assert_eq!(
+		statuses(&events),
+		vec![
+			CompoundSwapStatus::LockingFirstLeg,
+			CompoundSwapStatus::InitiatingSecondLeg,
+			CompoundSwapStatus::LockingSecondLeg,
+			CompoundSwapStatus::Locked,
+			CompoundSwapStatus::Completing,
+			CompoundSwapStatus::Completed,
+		]
+	);
+	assert!(events.iter().any(|event| event.chain() == ChainId(1)
+		&& matches!(
+			event.initiator().and_then(IEvent::contract_event),
+			Some(BridgeContractInitiatorEvent::Completed(_))
+		)));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_multi_hop_swap_unwinds_failed_second_leg() {
+	let (mut router, mut clients) = setup_multi_hop_router(Duration::from_secs(1), None);
+	for n in 1..=4 {
+		clients[2].set_call_config(
+			MethodName::LockBridgeTransferAssets,
+			n,
+			CallConfig {
+				error: ErrorConfig::CounterpartyError(
+					BridgeContractCounterpartyError::LockTransferAssetsError,
+				),
+				delay: None,
+			},
+		);
+	}
+
+	// The second leg is refunded once it expires, two seconds after it is initiated
+	initiate_transfer(
+		&mut clients[0],
+		TestTransfer {
+			time_lock: TimeLock::Relative(Duration::from_secs(4)),
+			destination_chain: Some(ChainId(3)),
+			..Default::default()
+		},
+	)
+	.await;
+	let mut events = Vec::new();
+	collect_until(&mut router, &mut events, is_status(CompoundSwapStatus::Unwound)).await;
+
+	assert_eq!(
+		statuses(&events),
+		vec![
+			CompoundSwapStatus::LockingFirstLeg,
+			CompoundSwapStatus::InitiatingSecondLeg,
+			CompoundSwapStatus::LockingSecondLeg,
+			CompoundSwapStatus::Unwinding,
+			CompoundSwapStatus::Unwound,
+		]
+	);
+	// The first leg lock is given back and the second leg refunded, both on chain 2
+	assert!(events.iter().any(|event| event.chain() == ChainId(2)
+		&& matches!(event.counterparty(), Some(CEvent::LockAborted(_)))));
+	let is_refunded = |event: &Event| {
+		event.chain() == ChainId(2)
+			&& matches!(
+				event.initiator().and_then(IEvent::contract_event),
+				Some(BridgeContractInitiatorEvent::Refunded(_))
+			)
+	};
+	if !events.iter().any(is_refunded) {
+		collect_until(&mut router, &mut events, is_refunded).await;
+	}
+	assert_eq!(router.active_swaps(Route::new(1, 2)).expect("No route").capacity().active, 0);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_multi_hop_swap_refunds_second_leg_at_expiry() {
+	let clock = ManualClock::new(NOW, BLOCK_TIME);
+	let (mut router, mut clients) =
+		setup_multi_hop_router(Duration::from_secs(1), Some(clock.clone()));
+	for n in 1..=4 {
+		clients[2].set_call_config(
+			MethodName::LockBridgeTransferAssets,
+			n,
+			CallConfig {
+				error: ErrorConfig::CounterpartyError(
+					BridgeContractCounterpartyError::LockTransferAssetsError,
+				),
+				delay: None,
+			},
+		);
+	}
+
+	// The second leg expires two seconds after it is initiated, by the clock of chain 2
+	initiate_transfer(
+		&mut clients[0],
+		TestTransfer {
+			time_lock: TimeLock::Relative(Duration::from_secs(4)),
+			destination_chain: Some(ChainId(3)),
+			..Default::default()
+		},
+	)
+	.await;
+	let mut events = Vec::new();
+	collect_until(&mut router, &mut events, is_status(CompoundSwapStatus::Unwinding)).await;
+
+	// Chain 2 is behind, so the refund is held back past the local expiry
+	let next = tokio::time::timeout(Duration::from_secs(3), async {
+		loop {
+			let event = router.next().await.expect("No event");
+			if event.compound_swap().is_some() {
+				return event;
+			}
+		}
+	})
+	.await;
+	assert!(next.is_err(), "Unexpected event: {next:?}");
+
+	clock.set(ChainTime { height: NOW.height + 1, timestamp: NOW.timestamp + 2 });
+	let is_refunded = |event: &Event| {
+		event.chain() == ChainId(2)
+			&& matches!(
+				event.initiator().and_then(IEvent::contract_event),
+				Some(BridgeContractInitiatorEvent::Refunded(_))
+			)
+	};
+	collect_until(&mut router, &mut events, is_refunded).await;
+	if !events.iter().any(is_status(CompoundSwapStatus::Unwound)) {
+		collect_until(&mut router, &mut events, is_status(CompoundSwapStatus::Unwound)).await;
+	}
+}
@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_service::{
+		active_swap::ActiveSwapMapError,
+		assets::{AssetConfig, AssetMapping},
+		events::{IEvent, IWarn},
+		netting::{Exposure, LiquidityPlanner, NettingConfig},
+		BridgeService, BridgeServiceConfig, BLOCKCHAIN_1, BLOCKCHAIN_2,
+	},
+	types::{Amount, AssetId},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, next_matching, setup_bridge_service,
+	B1Service, B2Service, SetupBridgeServiceResult, TestTransfer, TEST_ASSET,
+};
+
+fn exposure(service: &BridgeService<B1Service, B2Service>, chain: u64) -> Exposure {
+	service
+		.liquidity_planner
+		.lock()
+		.expect("liquidity planner poisoned")
+		.exposure(chain.into(), &AssetId::from(TEST_ASSET))
+}
+
+/// Drives the bridge service until the initiator funds of swaps out of blockchain 2 are due.
+async fn wait_for_incoming(service: &mut BridgeService<B1Service, B2Service>, amount: Amount) {
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while exposure(service, 2).incoming != amount {
+			let _ = tokio::time::timeout(Duration::from_millis(50), service.next()).await;
+		}
+	})
+	.await
+	.expect("Timed out waiting for the counterparty lock");
+}
+
+/// Limits the liquidity of blockchain 2 and nets it against swaps out of it.
+fn netting_config(netting: NettingConfig) -> BridgeServiceConfig {
+	BridgeServiceConfig {
+		assets_b1_to_b2: AssetMapping::new()
+			.with_asset(TEST_ASSET, AssetConfig::new(TEST_ASSET).with_liquidity(Amount(1_000))),
+		netting,
+		..default_bridge_service_config()
+	}
+}
+
+#[test]
+fn test_liquidity_planner_credit() {
+	let asset = AssetId::from(TEST_ASSET);
+	let mut planner = LiquidityPlanner::new(NettingConfig {
+		incoming_share_bps: 5_000,
+		max_credit: Some(Amount(300)),
+	});
+	assert_eq!(planner.credit(BLOCKCHAIN_2, &asset), Amount(0));
+
+	// Half of the incoming funds, capped
+	planner.expect_incoming(BLOCKCHAIN_2, &asset, Amount(400));
+	assert_eq!(planner.credit(BLOCKCHAIN_2, &asset), Amount(200));
+	planner.expect_incoming(BLOCKCHAIN_2, &asset, Amount(400));
+	assert_eq!(planner.credit(BLOCKCHAIN_2, &asset), Amount(300));
+	assert_eq!(planner.credit(BLOCKCHAIN_1, &asset), Amount(0));
+
+	// A lock takes the liquidity first, then the credit
+	let mut available = Amount(100);
+	planner.lock(BLOCKCHAIN_2, &asset, Amount(250), Some(&mut available));
+	assert_eq!(available, Amount(0));
+	assert_eq!(
+		planner.exposure(BLOCKCHAIN_2, &asset),
+		Exposure { outgoing: Amount(250), incoming: Amount(800), drawn: Amount(150) }
+	);
+	assert_eq!(planner.credit(BLOCKCHAIN_2, &asset), Amount(150));
+
+	// Claimed initiator funds repay the credit
+	planner.receive_incoming(BLOCKCHAIN_2, &asset, Amount(100));
+	assert_eq!(
+		planner.exposure(BLOCKCHAIN_2, &asset),
+		Exposure { outgoing: Amount(250), incoming: Amount(700), drawn: Amount(50) }
+	);
+	assert_eq!(planner.exposure(BLOCKCHAIN_2, &asset).net_incoming(), Amount(450));
+
+	// A top-up repays the remaining credit before adding to the liquidity
+	planner.top_up(BLOCKCHAIN_2, &asset, Amount(80), &mut available);
+	assert_eq!(available, Amount(30));
+	assert_eq!(planner.exposure(BLOCKCHAIN_2, &asset).drawn, Amount(0));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_nets_opposite_flows() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(netting_config(NettingConfig {
+		incoming_share_bps: 5_000,
+		max_credit: Some(Amount(2_000)),
+	}));
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	// A swap out of blockchain 2 makes 2000 due to the bridge there
+	initiate_transfer(
+		&mut blockchain_2_client,
+		TestTransfer { amount: Amount(2_000), ..TestTransfer::new("incoming") },
+	)
+	.await;
+	wait_for_incoming(&mut bridge_service, Amount(2_000)).await;
+	assert_eq!(exposure(&bridge_service, 1).outgoing, Amount(2_000));
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.usable_liquidity(&AssetId::from(TEST_ASSET)),
+		Some(Amount(2_000))
+	);
+
+	// More than the liquidity of blockchain 2, within its credit
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(1_800), ..TestTransfer::new("outgoing_1") },
+	)
+	.await;
+	let event = next_matching(&mut bridge_service, |event| event.B1I().is_some()).await;
+	assert!(event.B1I_ContractEvent().is_some());
+	assert_eq!(
+		exposure(&bridge_service, 2),
+		Exposure { outgoing: Amount(1_800), incoming: Amount(2_000), drawn: Amount(800) }
+	);
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.available_liquidity(&AssetId::from(TEST_ASSET)),
+		Some(Amount(0))
+	);
+
+	// The credit left is not enough
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(300), ..TestTransfer::new("outgoing_2") },
+	)
+	.await;
+	let event = next_matching(&mut bridge_service, |event| event.B1I().is_some()).await;
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn).expect("not a b1i warn event"),
+		IWarn::TransferRejected(_, ActiveSwapMapError::InsufficientLiquidity { .. })
+	));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_admits_on_gross_liquidity_by_default() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(netting_config(NettingConfig::default()));
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(
+		&mut blockchain_2_client,
+		TestTransfer { amount: Amount(2_000), ..TestTransfer::new("incoming") },
+	)
+	.await;
+	wait_for_incoming(&mut bridge_service, Amount(2_000)).await;
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(1_800), ..TestTransfer::new("outgoing") },
+	)
+	.await;
+	let event = next_matching(&mut bridge_service, |event| event.B1I().is_some()).await;
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn).expect("not a b1i warn event"),
+		IWarn::TransferRejected(_, ActiveSwapMapError::InsufficientLiquidity { .. })
+	));
+}
@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_service::{
+		active_swap::ActiveSwapMapError,
+		assets::{AssetConfig, AssetFee, AssetMapping},
+		direction::{Direction, EnabledDirections},
+		quote::{QuoteConfig, QuoteId, TimeLockRange},
+		BridgeServiceConfig,
+	},
+	types::{Amount, AssetId},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, setup_bridge_service, BC1Hash,
+	SetupBridgeServiceResult, TestTransfer, TEST_ASSET,
+};
+
+/// Blockchain 2 holds 1000 of liquidity, transfers pay a flat fee of 10.
+fn quote_config(quotes: QuoteConfig) -> BridgeServiceConfig {
+	BridgeServiceConfig {
+		assets_b1_to_b2: AssetMapping::new().with_asset(
+			TEST_ASSET,
+			AssetConfig::new(TEST_ASSET)
+				.with_fee(AssetFee { flat: Amount(10), basis_points: 0 })
+				.with_liquidity(Amount(1_000)),
+		),
+		quotes: QuoteConfig {
+			min_counterparty_time_lock: Duration::from_secs(10),
+			max_time_lock: Some(Duration::from_secs(1_000)),
+			..quotes
+		},
+		..default_bridge_service_config()
+	}
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_locks_quoted_transfer() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(quote_config(QuoteConfig::default()));
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	let asset = AssetId::from(TEST_ASSET);
+	let quote = bridge_service
+		.quote(Direction::B1ToB2, &asset, Amount(500), b"recipient")
+		.expect("quote failed");
+	assert_eq!(quote.fee, Amount(10));
+	assert_eq!(quote.locked_amount, Amount(490));
+	assert_eq!(quote.counterparty_asset, asset);
+	assert_eq!(
+		quote.time_lock,
+		TimeLockRange { min: Duration::from_secs(10), max: Some(Duration::from_secs(1_000)) }
+	);
+
+	// Accepting the quote holds its liquidity
+	let hash_lock = BC1Hash::from("reserved");
+	bridge_service
+		.accept_quote(Direction::B1ToB2, quote.id, hash_lock.as_ref())
+		.expect("accept_quote failed");
+	assert_eq!(bridge_service.active_swaps_b1_to_b2.available_liquidity(&asset), Some(Amount(510)));
+	assert_eq!(
+		bridge_service.accept_quote(Direction::B1ToB2, quote.id, hash_lock.as_ref()),
+		Err(ActiveSwapMapError::UnknownQuote)
+	);
+	assert!(matches!(
+		bridge_service.quote(Direction::B1ToB2, &asset, Amount(600), b"recipient"),
+		Err(ActiveSwapMapError::InsufficientLiquidity { .. })
+	));
+
+	// The transfer initiated under the hash lock takes the reservation
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { amount: Amount(500), ..TestTransfer::new("reserved") },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+	assert!(bridge_service.active_swaps_b1_to_b2.reservation(hash_lock.as_ref()).is_none());
+	assert_eq!(bridge_service.active_swaps_b1_to_b2.available_liquidity(&asset), Some(Amount(510)));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_releases_expired_reservation() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		_blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(quote_config(QuoteConfig {
+		reservation: Duration::from_millis(100),
+		..Default::default()
+	}));
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	let asset = AssetId::from(TEST_ASSET);
+	let quote = bridge_service
+		.quote(Direction::B1ToB2, &asset, Amount(1_000), b"recipient")
+		.expect("quote failed");
+	bridge_service
+		.accept_quote(Direction::B1ToB2, quote.id, BC1Hash::from("reserved").as_ref())
+		.expect("accept_quote failed");
+	assert_eq!(bridge_service.active_swaps_b1_to_b2.available_liquidity(&asset), Some(Amount(10)));
+
+	// The bridge service is woken once the reservation expires
+	let _ = tokio::time::timeout(Duration::from_millis(500), bridge_service.next()).await;
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.available_liquidity(&asset),
+		Some(Amount(1_000))
+	);
+}
+
+#[test]
+fn test_bridge_service_quote_errors() {
+	let SetupBridgeServiceResult(mut bridge_service, ..) =
+		setup_bridge_service(BridgeServiceConfig {
+			directions: EnabledDirections::only(Direction::B1ToB2),
+			..quote_config(QuoteConfig { validity: Duration::ZERO, ..Default::default() })
+		});
+
+	let asset = AssetId::from(TEST_ASSET);
+	assert_eq!(
+		bridge_service.quote(Direction::B2ToB1, &asset, Amount(500), b"recipient"),
+		Err(ActiveSwapMapError::DirectionDisabled)
+	);
+	assert_eq!(
+		bridge_service.accept_quote(Direction::B2ToB1, QuoteId(0), b"hash_lock"),
+		Err(ActiveSwapMapError::DirectionDisabled)
+	);
+	assert_eq!(
+		bridge_service.quote(Direction::B1ToB2, &AssetId::from("unknown"), Amount(500), b"r"),
+		Err(ActiveSwapMapError::UnmappedAsset(AssetId::from("unknown")))
+	);
+
+	// Quotes valid for no time at all cannot be accepted
+	let quote = bridge_service
+		.quote(Direction::B1ToB2, &asset, Amount(500), b"recipient")
+		.expect("quote failed");
+	assert_eq!(
+		bridge_service.accept_quote(Direction::B1ToB2, quote.id, b"hash_lock"),
+		Err(ActiveSwapMapError::UnknownQuote)
+	);
+}
+
+#[test]
+fn test_bridge_service_keeps_quote_on_failed_acceptance() {
+	let SetupBridgeServiceResult(mut bridge_service, ..) =
+		setup_bridge_service(quote_config(QuoteConfig::default()));
+
+	let asset = AssetId::from(TEST_ASSET);
+	let first = bridge_service
+		.quote(Direction::B1ToB2, &asset, Amount(100), b"recipient")
+		.expect("quote failed");
+	let second = bridge_service
+		.quote(Direction::B1ToB2, &asset, Amount(100), b"recipient")
+		.expect("quote failed");
+	bridge_service
+		.accept_quote(Direction::B1ToB2, first.id, b"hash_lock")
+		.expect("accept_quote failed");
+
+	// The hash lock is taken, but the quote can still be accepted under another one
+	assert_eq!(
+		bridge_service.accept_quote(Direction::B1ToB2, second.id, b"hash_lock"),
+		Err(ActiveSwapMapError::HashLockReserved)
+	);
+	bridge_service
+		.accept_quote(Direction::B1ToB2, second.id, b"other_hash_lock")
+		.expect("accept_quote failed");
+	assert_eq!(bridge_service.active_swaps_b1_to_b2.available_liquidity(&asset), Some(Amount(820)));
+}
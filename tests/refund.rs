@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use test_log::test;
+
+use bridge_shared::{
+	bridge_contracts::{
+		BridgeContractCounterparty, BridgeContractCounterpartyError, BridgeContractInitiator,
+		BridgeContractInitiatorError,
+	},
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::ActiveSwapConfig,
+		assets::{AssetConfig, AssetMapping},
+		direction::Direction,
+		events::{CEvent, IEvent, IWarn},
+		BridgeService, BridgeServiceConfig,
+	},
+	types::{Amount, AssetId, BridgeTransferId, Convert, HashLockPreImage},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, next_matching, setup_bridge_service,
+	testing::blockchain::client::{CallConfig, ErrorConfig, MethodName},
+	B1Client, B1Service, B2Service, BC1Hash, SetupBridgeServiceResult, TestTransfer, TEST_ASSET,
+};
+
+/// Initiates a transfer from blockchain 1 and drives the bridge service until it is locked.
+async fn initiate_locked_transfer(
+	service: &mut BridgeService<B1Service, B2Service>,
+	client: &mut B1Client,
+) -> BridgeTransferId<BC1Hash> {
+	initiate_transfer(client, TestTransfer::default()).await;
+	let event = next_matching(service, |event| event.B1I_ContractEvent().is_some()).await;
+	let bridge_transfer_id =
+		event.B1I_ContractEvent().expect("not a b1i event").bridge_transfer_id().clone();
+	next_matching(service, |event| {
+		matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_)))
+	})
+	.await;
+	bridge_transfer_id
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_aborts_lock_of_refunded_transfer() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		assets_b1_to_b2: AssetMapping::new()
+			.with_asset(TEST_ASSET, AssetConfig::new(TEST_ASSET).with_liquidity(Amount(5_000))),
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	let bridge_transfer_id =
+		initiate_locked_transfer(&mut bridge_service, &mut blockchain_1_client).await;
+	let asset = AssetId::from(TEST_ASSET);
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.available_liquidity(&asset),
+		Some(Amount(4_000))
+	);
+
+	// The initiator takes its funds back, so the bridge gives its lock back as well
+	blockchain_1_client
+		.refund_bridge_transfer(bridge_transfer_id)
+		.await
+		.expect("refund_bridge_transfer failed");
+	next_matching(&mut bridge_service, |event| {
+		matches!(event.B1I_ContractEvent(), Some(BridgeContractInitiatorEvent::Refunded(_)))
+	})
+	.await;
+	next_matching(&mut bridge_service, |event| matches!(event.B2C(), Some(CEvent::LockAborted(_))))
+		.await;
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.available_liquidity(&asset),
+		Some(Amount(5_000))
+	);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_warns_of_refund_after_claim() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		active_swap: ActiveSwapConfig {
+			error_delay: Duration::from_secs(60),
+			..Default::default()
+		},
+		..default_bridge_service_config()
+	});
+	blockchain_1_client.set_call_config(
+		MethodName::CompleteBridgeTransferInitiator,
+		1,
+		CallConfig {
+			error: ErrorConfig::InitiatorError(BridgeContractInitiatorError::CompleteTransferError),
+			delay: None,
+		},
+	);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	let bridge_transfer_id =
+		initiate_locked_transfer(&mut bridge_service, &mut blockchain_1_client).await;
+
+	// The recipient claims, but the bridge fails to claim the initiator funds in time
+	BridgeContractCounterparty::complete_bridge_transfer(
+		&mut blockchain_2_client,
+		Convert::convert(&bridge_transfer_id),
+		HashLockPreImage::new(b"hash_lock".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+	next_matching(&mut bridge_service, |event| {
+		matches!(event.B1I().and_then(IEvent::warn), Some(IWarn::CompleteTransferError(_)))
+	})
+	.await;
+
+	blockchain_1_client
+		.refund_bridge_transfer(bridge_transfer_id.clone())
+		.await
+		.expect("refund_bridge_transfer failed");
+	let event = next_matching(&mut bridge_service, |event| event.B1I().is_some()).await;
+	assert_eq!(
+		event.B1I().and_then(IEvent::warn),
+		Some(&IWarn::RefundedAfterClaim(bridge_transfer_id))
+	);
+	assert_eq!(bridge_service.capacity(Direction::B1ToB2).active, 0);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_cancels_locking_of_refunded_transfer() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+	blockchain_2_client.set_call_config(
+		MethodName::LockBridgeTransferAssets,
+		1,
+		CallConfig {
+			error: ErrorConfig::CounterpartyError(
+				BridgeContractCounterpartyError::LockTransferAssetsError,
+			),
+			delay: Some(Duration::from_millis(500)),
+		},
+	);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::default()).await;
+	let event =
+		next_matching(&mut bridge_service, |event| event.B1I_ContractEvent().is_some()).await;
+	let bridge_transfer_id =
+		event.B1I_ContractEvent().expect("not a b1i event").bridge_transfer_id().clone();
+
+	// The lock call is in flight when the initiator takes its funds back, and then fails
+	blockchain_1_client
+		.refund_bridge_transfer(bridge_transfer_id)
+		.await
+		.expect("refund_bridge_transfer failed");
+	let event = next_matching(&mut bridge_service, |event| event.B2C().is_some()).await;
+	assert!(matches!(event.B2C(), Some(CEvent::LockingCancelled(_))), "{event:?}");
+	assert_eq!(bridge_service.capacity(Direction::B1ToB2).active, 0);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_cancels_relaying_of_refunded_transfer() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(default_bridge_service_config());
+	blockchain_2_client.set_call_config(
+		MethodName::CompleteBridgeTransferCounterparty,
+		1,
+		CallConfig { error: ErrorConfig::None, delay: Some(Duration::from_secs(5)) },
+	);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	let bridge_transfer_id =
+		initiate_locked_transfer(&mut bridge_service, &mut blockchain_1_client).await;
+	bridge_service
+		.relay_secret(Direction::B1ToB2, HashLockPreImage::new(b"hash_lock".to_vec()))
+		.expect("relay_secret failed");
+
+	// The initiator takes its funds back before the relayed claim went through
+	blockchain_1_client
+		.refund_bridge_transfer(bridge_transfer_id)
+		.await
+		.expect("refund_bridge_transfer failed");
+	let event = next_matching(&mut bridge_service, |event| event.B2C().is_some()).await;
+	assert!(matches!(event.B2C(), Some(CEvent::RelayingCancelled(_))), "{event:?}");
+	next_matching(&mut bridge_service, |event| matches!(event.B2C(), Some(CEvent::LockAborted(_))))
+		.await;
+}
@@ -0,0 +1,305 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use rand::SeedableRng;
+use test_log::test;
+
+use bridge_shared::{
+	blockchain_service::AbstractBlockchainService,
+	bridge_contracts::{BridgeContractCounterparty, BridgeContractInitiator},
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::SwapCapacity,
+		assets::{AssetConfig, AssetMapping},
+		events::{CEvent, IEvent, IWarn},
+		router::{
+			BridgeRouter, BridgeRouterConfig, BridgeRouterError, Route, RouteConfig, RouterEvent,
+		},
+		time_lock::TimeLockPolicy,
+	},
+	types::{Amount, AssetId, ChainId, HashLockPreImage},
+};
+
+mod shared;
+
+use crate::shared::{
+	initiate_transfer, test_assets, test_chain_config,
+	testing::{
+		blockchain::{AbstractBlockchain, AbstractBlockchainClient},
+		rng::{RngSeededClone, TestRng},
+	},
+	B1Client, B1Service, BC1Address, BC1Hash, CounterpartyContractMonitoring,
+	InitiatorContractMonitoring, TestTransfer, TEST_ASSET,
+};
+
+/// Registers `chains` chains, numbered from 1, and returns their clients.
+fn setup_router(chains: u64) -> (BridgeRouter<B1Service>, Vec<B1Client>) {
+	let mut rng = TestRng::from_seed([0u8; 32]);
+	let mut router = BridgeRouter::new(BridgeRouterConfig {
+		// The test contracts do not enforce time locks
+		time_lock_policy: TimeLockPolicy { safety_margin: Duration::ZERO },
+		..Default::default()
+	});
+	let mut clients = Vec::new();
+	for chain in 1..=chains {
+		let mut blockchain = AbstractBlockchain::<BC1Address, BC1Hash, _>::new(
+			rng.seeded_clone(),
+			format!("Blockchain{chain}"),
+		);
+		let client =
+			AbstractBlockchainClient::new(blockchain.connection(), rng.seeded_clone(), 0.0, 0.00);
+		let service = AbstractBlockchainService {
+			initiator_contract: client.clone(),
+			initiator_monitoring: InitiatorContractMonitoring::build(
+				blockchain.add_event_listener(),
+			),
+			counterparty_contract: client.clone(),
+			counterparty_monitoring: CounterpartyContractMonitoring::build(
+				blockchain.add_event_listener(),
+			),
+			_phantom: Default::default(),
+		};
+		router.add_chain(chain, service, test_chain_config()).expect("add_chain failed");
+		tokio::spawn(blockchain);
+		clients.push(client);
+	}
+	(router, clients)
+}
+
+fn route_config() -> RouteConfig {
+	RouteConfig { assets: test_assets(), ..Default::default() }
+}
+
+#[test(tokio::test)]
+async fn test_router_registration() {
+	let (mut router, clients) = setup_router(2);
+	let (chain_1, chain_2) = (ChainId(1), ChainId(2));
+	let service = AbstractBlockchainService {
+		initiator_contract: clients[0].clone(),
+		initiator_monitoring: InitiatorContractMonitoring::build(
+			futures::channel::mpsc::unbounded().1,
+		),
+		counterparty_contract: clients[0].clone(),
+		counterparty_monitoring: CounterpartyContractMonitoring::build(
+			futures::channel::mpsc::unbounded().1,
+		),
+		_phantom: Default::default(),
+	};
+	assert_eq!(
+		router.add_chain(chain_1, service, test_chain_config()),
+		Err(BridgeRouterError::DuplicateChain(chain_1))
+	);
+	assert_eq!(
+		router.enable_route(Route::new(1, 3), route_config()),
+		Err(BridgeRouterError::UnknownChain(ChainId(3)))
+	);
+	assert_eq!(
+		router.enable_route(Route::new(2, 2), route_config()),
+		Err(BridgeRouterError::SameChain(Route::new(2, 2)))
+	);
+	router.enable_route(Route::new(1, 2), route_config()).expect("enable_route failed");
+	assert_eq!(
+		router.enable_route(Route::new(1, 2), route_config()),
+		Err(BridgeRouterError::DuplicateRoute(Route::new(1, 2)))
+	);
+	router.enable_route(Route::new(2, 1), route_config()).expect("enable_route failed");
+	assert_eq!(router.chains().collect::<Vec<_>>(), vec![chain_1, chain_2]);
+	assert_eq!(router.routes().collect::<Vec<_>>(), vec![Route::new(1, 2), Route::new(2, 1)]);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_router_swaps_to_destination_chain() {
+	let (mut router, mut clients) = setup_router(3);
+	for route in [Route::new(1, 2), Route::new(1, 3), Route::new(3, 1)] {
+		router.enable_route(route, route_config()).expect("enable_route failed");
+	}
+
+	initiate_transfer(
+		&mut clients[0],
+		TestTransfer { destination_chain: Some(ChainId(3)), ..TestTransfer::new("hash_lock") },
+	)
+	.await;
+	let event = router.next().await.expect("No event");
+	assert_eq!(event.chain(), ChainId(1));
+	let bridge_transfer_id = match event.initiator().and_then(IEvent::contract_event) {
+		Some(BridgeContractInitiatorEvent::Initiated(details)) => {
+			details.bridge_transfer_id.clone()
+		}
+		_ => panic!("Not an initiated event: {event:?}"),
+	};
+	let event = router.next().await.expect("No event");
+	assert_eq!(event.chain(), ChainId(3));
+	let counterparty_id = match event.counterparty().and_then(CEvent::contract_event) {
+		Some(BridgeContractCounterpartyEvent::Locked(details)) => {
+			details.bridge_transfer_id.clone()
+		}
+		_ => panic!("Not a locked event: {event:?}"),
+	};
+	let route = Route::new(1, 3);
+	assert_eq!(router.active_swaps(route).expect("No route").capacity().active, 1);
+	assert_eq!(router.active_swaps(Route::new(1, 2)).expect("No route").capacity().active, 0);
+
+	// The recipient claims on chain 3, the bridge completes on chain 1
+	<B1Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut clients[2],
+		counterparty_id,
+		HashLockPreImage::new(b"hash_lock".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+	let event = router.next().await.expect("No event");
+	assert_eq!(event.chain(), ChainId(3));
+	assert!(matches!(
+		event.counterparty().and_then(CEvent::contract_event),
+		Some(BridgeContractCounterpartyEvent::Completed(_))
+	));
+	let event = router.next().await.expect("No event");
+	assert_eq!(
+		event.initiator().and_then(IEvent::contract_event),
+		Some(&BridgeContractInitiatorEvent::Completed(bridge_transfer_id))
+	);
+	assert_eq!(event.chain(), ChainId(1));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_router_rejects_unroutable_transfers() {
+	let (mut router, mut clients) = setup_router(3);
+	for route in [Route::new(1, 2), Route::new(1, 3), Route::new(3, 1)] {
+		router.enable_route(route, route_config()).expect("enable_route failed");
+	}
+
+	// No route from chain 2
+	initiate_transfer(
+		&mut clients[1],
+		TestTransfer { destination_chain: Some(ChainId(1)), ..TestTransfer::new("hash_lock_1") },
+	)
+	.await;
+	let event = router.next().await.expect("No event");
+	assert_eq!(event.chain(), ChainId(2));
+	assert!(matches!(event.initiator().and_then(IEvent::warn), Some(IWarn::NoRoute(_))));
+
+	// Several routes from chain 1, the destination must be given
+	initiate_transfer(&mut clients[0], TestTransfer::new("hash_lock_2")).await;
+	let event = router.next().await.expect("No event");
+	assert!(matches!(event.initiator().and_then(IEvent::warn), Some(IWarn::NoRoute(_))));
+
+	// The only route from chain 3 is taken by default
+	initiate_transfer(&mut clients[2], TestTransfer::new("hash_lock_3")).await;
+	let event = router.next().await.expect("No event");
+	assert!(matches!(
+		event.initiator().and_then(IEvent::contract_event),
+		Some(BridgeContractInitiatorEvent::Initiated(_))
+	));
+	let event = router.next().await.expect("No event");
+	assert_eq!(event.chain(), ChainId(1));
+	assert!(matches!(
+		event.counterparty().and_then(CEvent::contract_event),
+		Some(BridgeContractCounterpartyEvent::Locked(_))
+	));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_router_aborts_lock_of_refunded_transfer() {
+	let (mut router, mut clients) = setup_router(2);
+	let route = Route::new(1, 2);
+	let assets = AssetMapping::new()
+		.with_asset(TEST_ASSET, AssetConfig::new(TEST_ASSET).with_liquidity(Amount(5_000)));
+	router
+		.enable_route(route, RouteConfig { assets, ..route_config() })
+		.expect("enable_route failed");
+
+	initiate_transfer(&mut clients[0], TestTransfer::new("hash_lock")).await;
+	let event = router.next().await.expect("No event");
+	let bridge_transfer_id = match event.initiator().and_then(IEvent::contract_event) {
+		Some(BridgeContractInitiatorEvent::Initiated(details)) => {
+			details.bridge_transfer_id.clone()
+		}
+		_ => panic!("Not an initiated event: {event:?}"),
+	};
+	let event = router.next().await.expect("No event");
+	assert!(matches!(
+		event.counterparty().and_then(CEvent::contract_event),
+		Some(BridgeContractCounterpartyEvent::Locked(_))
+	));
+	let asset = AssetId::from(TEST_ASSET);
+	let active_swaps = router.active_swaps(route).expect("No route");
+	assert_eq!(active_swaps.available_liquidity(&asset), Some(Amount(4_000)));
+
+	// The initiator takes its funds back, so the router gives the lock of the route back as well
+	clients[0]
+		.refund_bridge_transfer(bridge_transfer_id.clone())
+		.await
+		.expect("refund_bridge_transfer failed");
+	let event = router.next().await.expect("No event");
+	assert_eq!(
+		event.initiator().and_then(IEvent::contract_event),
+		Some(&BridgeContractInitiatorEvent::Refunded(bridge_transfer_id))
+	);
+	let event = router.next().await.expect("No event");
+	assert_eq!(event.chain(), ChainId(2));
+	assert!(matches!(event.counterparty(), Some(CEvent::LockAborted(_))));
+	let active_swaps = router.active_swaps(route).expect("No route");
+	assert_eq!(active_swaps.available_liquidity(&asset), Some(Amount(5_000)));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_router_stops_taking_transfers_at_capacity() {
+	let (mut router, mut clients) = setup_router(2);
+	let route = Route::new(1, 2);
+	router
+		.enable_route(route, RouteConfig { max_active_swaps: Some(1), ..route_config() })
+		.expect("enable_route failed");
+	let full = SwapCapacity { active: 1, max: Some(1) };
+
+	initiate_transfer(&mut clients[0], TestTransfer::new("first")).await;
+	let event = router.next().await.expect("No event");
+	let first_id = match event.initiator().and_then(IEvent::contract_event) {
+		Some(BridgeContractInitiatorEvent::Initiated(details)) => {
+			details.bridge_transfer_id.clone()
+		}
+		_ => panic!("Not an initiated event: {event:?}"),
+	};
+	assert_eq!(router.next().await, Some(RouterEvent::CapacityReached(route, full)));
+	let event = router.next().await.expect("No event");
+	let counterparty_id = match event.counterparty().and_then(CEvent::contract_event) {
+		Some(BridgeContractCounterpartyEvent::Locked(details)) => {
+			details.bridge_transfer_id.clone()
+		}
+		_ => panic!("Not a locked event: {event:?}"),
+	};
+
+	// The second transfer stays in the initiator monitoring of chain 1
+	initiate_transfer(&mut clients[0], TestTransfer::new("second")).await;
+	let next = tokio::time::timeout(Duration::from_millis(200), router.next()).await;
+	assert!(next.is_err(), "Unexpected event: {next:?}");
+
+	// Completing the first swap frees capacity for the second
+	<B1Client as BridgeContractCounterparty>::complete_bridge_transfer(
+		&mut clients[1],
+		counterparty_id,
+		HashLockPreImage::new(b"first".to_vec()),
+	)
+	.await
+	.expect("complete_bridge_transfer failed");
+	let mut freed = false;
+	let mut second_initiated = false;
+	while !(freed && second_initiated) {
+		let event = router.next().await.expect("No event");
+		match &event {
+			RouterEvent::CapacityFreed(freed_route, _) => {
+				assert_eq!(*freed_route, route);
+				freed = true;
+			}
+			RouterEvent::Initiator(_, event) => {
+				if let Some(BridgeContractInitiatorEvent::Initiated(details)) =
+					event.contract_event()
+				{
+					assert!(freed, "Second transfer taken before capacity was freed");
+					assert_ne!(details.bridge_transfer_id, first_id);
+					second_initiated = true;
+				}
+			}
+			_ => {}
+		}
+	}
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use test_log::test;
+
+use bridge_shared::{
+	bridge_contracts::BridgeContractCounterpartyError,
+	bridge_monitoring::{BridgeContractCounterpartyEvent, BridgeContractInitiatorEvent},
+	bridge_service::{
+		active_swap::{ActiveSwapMapError, RelayConfig},
+		direction::Direction,
+		events::{CEvent, CWarn, Event},
+		BridgeServiceConfig,
+	},
+	types::HashLockPreImage,
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, events_until, initiate_transfer, next_matching,
+	setup_bridge_service,
+	testing::blockchain::client::{CallConfig, ErrorConfig, MethodName},
+	B1Service, B2Client, B2Service, SetupBridgeServiceResult, TestTransfer,
+};
+
+/// The lock on blockchain 2 is placed.
+fn is_locked(event: &Event<B1Service, B2Service>) -> bool {
+	matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_)))
+}
+
+/// The swap is completed on blockchain 1.
+fn is_completed(event: &Event<B1Service, B2Service>) -> bool {
+	matches!(event.B1I_ContractEvent(), Some(BridgeContractInitiatorEvent::Completed(_)))
+}
+
+fn relay_config() -> BridgeServiceConfig {
+	BridgeServiceConfig {
+		relay: RelayConfig { error_delay: Duration::from_millis(10), ..Default::default() },
+		..default_bridge_service_config()
+	}
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_completes_swap_with_relayed_secret() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(relay_config());
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("hash_lock")).await;
+	next_matching(&mut bridge_service, is_locked).await;
+
+	// The recipient hands the secret to the bridge instead of claiming the lock itself
+	bridge_service
+		.relay_secret(Direction::B1ToB2, HashLockPreImage::new(b"hash_lock".to_vec()))
+		.expect("relay_secret failed");
+	assert_eq!(
+		bridge_service
+			.relay_secret(Direction::B1ToB2, HashLockPreImage::new(b"hash_lock".to_vec())),
+		Err(ActiveSwapMapError::NotRelayable)
+	);
+
+	let events = events_until(&mut bridge_service, is_completed).await;
+	assert!(events.iter().any(|event| matches!(event.B2C(), Some(CEvent::SecretRelayed(_)))));
+	// The counterparty event of the claim does not complete the swap twice
+	assert!(events.iter().all(|event| event.B2C().and_then(CEvent::warn).is_none()));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_rejects_unknown_secret() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(relay_config());
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("hash_lock")).await;
+	next_matching(&mut bridge_service, is_locked).await;
+
+	assert_eq!(
+		bridge_service.relay_secret(Direction::B1ToB2, HashLockPreImage::new(b"wrong".to_vec())),
+		Err(ActiveSwapMapError::NonExistingSwap)
+	);
+	assert_eq!(
+		bridge_service
+			.relay_secret(Direction::B2ToB1, HashLockPreImage::new(b"hash_lock".to_vec())),
+		Err(ActiveSwapMapError::NonExistingSwap)
+	);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_retries_relayed_claim() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(relay_config());
+	claim_fails(&mut blockchain_2_client, 2);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("hash_lock")).await;
+	next_matching(&mut bridge_service, is_locked).await;
+
+	bridge_service
+		.relay_secret(Direction::B1ToB2, HashLockPreImage::new(b"hash_lock".to_vec()))
+		.expect("relay_secret failed");
+
+	let events = events_until(&mut bridge_service, is_completed).await;
+	let errors = events
+		.iter()
+		.filter(|event| {
+			matches!(event.B2C().and_then(CEvent::warn), Some(CWarn::RelayingSecretError(..)))
+		})
+		.count();
+	assert_eq!(errors, 2);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_gives_up_relayed_claim() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		relay: RelayConfig {
+			error_attempts: 1,
+			error_delay: Duration::from_millis(10),
+			..Default::default()
+		},
+		..default_bridge_service_config()
+	});
+	claim_fails(&mut blockchain_2_client, 2);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	initiate_transfer(&mut blockchain_1_client, TestTransfer::new("hash_lock")).await;
+	next_matching(&mut bridge_service, is_locked).await;
+
+	bridge_service
+		.relay_secret(Direction::B1ToB2, HashLockPreImage::new(b"hash_lock".to_vec()))
+		.expect("relay_secret failed");
+	next_matching(&mut bridge_service, |event| {
+		matches!(event.B2C().and_then(CEvent::warn), Some(CWarn::RelayingAborted(_)))
+	})
+	.await;
+
+	// The lock is left in place and the secret can be relayed again
+	bridge_service
+		.relay_secret(Direction::B1ToB2, HashLockPreImage::new(b"hash_lock".to_vec()))
+		.expect("relay_secret failed");
+	events_until(&mut bridge_service, is_completed).await;
+}
+
+/// Makes the first `errors` claims on blockchain 2 fail.
+fn claim_fails(client: &mut B2Client, errors: usize) {
+	for n in 1..=errors {
+		client.set_call_config(
+			MethodName::CompleteBridgeTransferCounterparty,
+			n,
+			CallConfig {
+				error: ErrorConfig::CounterpartyError(
+					BridgeContractCounterpartyError::CompleteTransferError,
+				),
+				delay: None,
+			},
+		);
+	}
+}
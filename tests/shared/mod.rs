@@ -2,12 +2,21 @@
 
 use bridge_shared::{
 	blockchain_service::AbstractBlockchainService,
+	bridge_contracts::BridgeContractInitiator,
 	bridge_monitoring::{
 		BridgeContractCounterpartyEvent, BridgeContractCounterpartyMonitoring,
 		BridgeContractInitiatorEvent, BridgeContractInitiatorMonitoring,
 	},
-	bridge_service::{BridgeService, BridgeServiceConfig},
-	types::{Convert, GenUniqueHash, HashLockPreImage, InitiatorAddress, RecipientAddress},
+	bridge_service::{
+		assets::{AssetConfig, AssetMapping},
+		time_lock::TimeLockPolicy,
+		BridgeService, BridgeServiceConfig, ChainConfig,
+	},
+	hash_lock::HashLockScheme,
+	types::{
+		Amount, AssetId, ChainId, ConversionError, Convert, GenUniqueHash, HashLock,
+		HashLockPreImage, InitiatorAddress, RecipientAddress, TimeLock, TryConvert,
+	},
 };
 
 use futures::{channel::mpsc::UnboundedReceiver, Stream, StreamExt};
@@ -17,7 +26,9 @@ use std::{
 	fmt::{Debug, Formatter},
 	hash::{DefaultHasher, Hash, Hasher},
 	pin::Pin,
+	sync::Arc,
 	task::{Context, Poll},
+	time::Duration,
 };
 
 pub mod testing;
@@ -48,7 +59,7 @@ pub struct BC1Hash([u8; 8]);
 
 impl From<HashLockPreImage> for BC1Hash {
 	fn from(value: HashLockPreImage) -> Self {
-		Self(hash_vec_u8(&value.0))
+		Self(hash_vec_u8(value.expose_secret()))
 	}
 }
 
@@ -64,6 +75,35 @@ impl GenUniqueHash for BC1Hash {
 	}
 }
 
+impl TryConvert<Vec<u8>> for BC1Hash {
+	fn try_convert(value: Vec<u8>) -> Result<Self, ConversionError> {
+		let actual = value.len();
+		value
+			.try_into()
+			.map(Self)
+			.map_err(|_| ConversionError::InvalidLength { expected: 8, actual })
+	}
+}
+
+impl TryConvert<BC2Hash> for BC1Hash {
+	fn try_convert(hash: BC2Hash) -> Result<Self, ConversionError> {
+		Ok(Self(hash.0))
+	}
+}
+
+// Swaps between chains of the same type, see the bridge router
+impl TryConvert<BC1Hash> for BC1Hash {
+	fn try_convert(hash: BC1Hash) -> Result<Self, ConversionError> {
+		Ok(hash)
+	}
+}
+
+impl AsRef<[u8]> for BC1Hash {
+	fn as_ref(&self) -> &[u8] {
+		&self.0
+	}
+}
+
 impl Debug for BC1Hash {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
 		write!(f, "Bc1Hash({:02x})", u64::from_be_bytes(self.0))
@@ -75,7 +115,7 @@ pub struct BC2Hash([u8; 8]);
 
 impl From<HashLockPreImage> for BC2Hash {
 	fn from(value: HashLockPreImage) -> Self {
-		Self(hash_vec_u8(&value.0))
+		Self(hash_vec_u8(value.expose_secret()))
 	}
 }
 
@@ -91,6 +131,28 @@ impl From<&'static str> for BC2Hash {
 	}
 }
 
+impl TryConvert<Vec<u8>> for BC2Hash {
+	fn try_convert(value: Vec<u8>) -> Result<Self, ConversionError> {
+		let actual = value.len();
+		value
+			.try_into()
+			.map(Self)
+			.map_err(|_| ConversionError::InvalidLength { expected: 8, actual })
+	}
+}
+
+impl TryConvert<BC1Hash> for BC2Hash {
+	fn try_convert(hash: BC1Hash) -> Result<Self, ConversionError> {
+		Ok(Self(hash.0))
+	}
+}
+
+impl AsRef<[u8]> for BC2Hash {
+	fn as_ref(&self) -> &[u8] {
+		&self.0
+	}
+}
+
 impl Debug for BC2Hash {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
 		write!(f, "BC2Hash({:02x})", u64::from_be_bytes(self.0))
@@ -100,9 +162,17 @@ impl Debug for BC2Hash {
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct BC1Address(pub &'static str);
 
-impl From<Vec<u8>> for BC1Address {
-	fn from(value: Vec<u8>) -> Self {
-		Self(static_str_ops::staticize(&String::from_utf8(value).expect("Invalid UTF-8")))
+impl From<&'static str> for BC1Address {
+	fn from(address: &'static str) -> Self {
+		Self(address)
+	}
+}
+
+impl TryConvert<Vec<u8>> for BC1Address {
+	fn try_convert(value: Vec<u8>) -> Result<Self, ConversionError> {
+		let address = String::from_utf8(value)
+			.map_err(|error| ConversionError::InvalidEncoding(error.to_string()))?;
+		Ok(Self(static_str_ops::staticize(&address)))
 	}
 }
 
@@ -139,9 +209,17 @@ impl From<BC1Address> for InitiatorAddress<Vec<u8>> {
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct BC2Address(pub &'static str);
 
-impl From<Vec<u8>> for BC2Address {
-	fn from(value: Vec<u8>) -> Self {
-		Self(static_str_ops::staticize(&String::from_utf8(value).expect("Invalid UTF-8")))
+impl From<&'static str> for BC2Address {
+	fn from(address: &'static str) -> Self {
+		Self(address)
+	}
+}
+
+impl TryConvert<Vec<u8>> for BC2Address {
+	fn try_convert(value: Vec<u8>) -> Result<Self, ConversionError> {
+		let address = String::from_utf8(value)
+			.map_err(|error| ConversionError::InvalidEncoding(error.to_string()))?;
+		Ok(Self(static_str_ops::staticize(&address)))
 	}
 }
 
@@ -247,6 +325,11 @@ impl<A: Debug, H: Debug> Stream for InitiatorContractMonitoring<A, H> {
 							bridge_transfer_id,
 						)))
 					}
+					RefundedBridgeTransfer(bridge_transfer_id) => {
+						return Poll::Ready(Some(BridgeContractInitiatorEvent::Refunded(
+							bridge_transfer_id,
+						)))
+					}
 				},
 				Err(_) => {
 					// Handle error
@@ -298,6 +381,8 @@ impl<A: Debug, H: Debug> Stream for CounterpartyContractMonitoring<A, H> {
 							details,
 						)))
 					}
+					// The bridge service learns about aborts from its own calls
+					AbortedBridgeTransfer(_) => cx.waker().wake_by_ref(),
 				},
 				Err(_) => {
 					// Handle error
@@ -330,6 +415,136 @@ pub type B2Service = AbstractBlockchainService<
 	BC2Hash,
 >;
 
+/// The hash lock scheme of the abstract test blockchains.
+#[derive(Debug)]
+pub struct TestHashLockScheme;
+
+impl HashLockScheme for TestHashLockScheme {
+	fn name(&self) -> &'static str {
+		"test"
+	}
+
+	fn digest(&self, pre_image: &HashLockPreImage) -> Vec<u8> {
+		hash_vec_u8(pre_image.expose_secret()).to_vec()
+	}
+}
+
+pub fn test_chain_config() -> ChainConfig {
+	ChainConfig { hash_lock_scheme: Arc::new(TestHashLockScheme), ..Default::default() }
+}
+
+pub const TEST_ASSET: &str = "asset";
+
+/// Maps the test asset onto itself, the same on both chains.
+pub fn test_assets() -> AssetMapping {
+	AssetMapping::new().with_asset(TEST_ASSET, AssetConfig::new(TEST_ASSET))
+}
+
+pub fn default_bridge_service_config() -> BridgeServiceConfig {
+	BridgeServiceConfig {
+		assets_b1_to_b2: test_assets(),
+		assets_b2_to_b1: test_assets(),
+		blockchain_1: test_chain_config(),
+		blockchain_2: test_chain_config(),
+		// The test contracts do not enforce time locks
+		time_lock_policy: TimeLockPolicy { safety_margin: Duration::ZERO },
+		..Default::default()
+	}
+}
+
+/// Transfer from "initiator" started by `initiate_transfer`.
+#[derive(Debug, Clone)]
+pub struct TestTransfer {
+	pub recipient: &'static [u8],
+	pub hash_lock: &'static str,
+	pub counterparty_hash_lock: Option<HashLock<Vec<u8>>>,
+	pub time_lock: TimeLock,
+	pub asset: &'static str,
+	pub amount: Amount,
+	pub destination_chain: Option<ChainId>,
+}
+
+impl TestTransfer {
+	pub fn new(hash_lock: &'static str) -> Self {
+		Self { hash_lock, ..Default::default() }
+	}
+}
+
+impl Default for TestTransfer {
+	fn default() -> Self {
+		Self {
+			recipient: b"recipient",
+			hash_lock: "hash_lock",
+			counterparty_hash_lock: None,
+			time_lock: TimeLock::Relative(Duration::from_secs(100)),
+			asset: TEST_ASSET,
+			amount: Amount(1000),
+			destination_chain: None,
+		}
+	}
+}
+
+/// Initiates a transfer on the chain of `client`.
+pub async fn initiate_transfer<C>(client: &mut C, transfer: TestTransfer)
+where
+	C: BridgeContractInitiator,
+	C::Address: From<&'static str>,
+	C::Hash: From<&'static str>,
+{
+	client
+		.initiate_bridge_transfer(
+			InitiatorAddress(C::Address::from("initiator")),
+			RecipientAddress(transfer.recipient.to_vec()),
+			HashLock(C::Hash::from(transfer.hash_lock)),
+			transfer.counterparty_hash_lock,
+			transfer.time_lock,
+			AssetId::from(transfer.asset),
+			transfer.amount,
+			transfer.destination_chain,
+		)
+		.await
+		.expect("initiate_bridge_transfer failed");
+}
+
+/// Skips the events of `stream` until one matches, failing after 10 seconds.
+pub async fn next_matching<S>(stream: &mut S, matches: impl Fn(&S::Item) -> bool) -> S::Item
+where
+	S: Stream + Unpin,
+{
+	tokio::time::timeout(Duration::from_secs(10), async {
+		loop {
+			let event = stream.next().await.expect("No event");
+			if matches(&event) {
+				return event;
+			}
+		}
+	})
+	.await
+	.expect("Timed out waiting for the event")
+}
+
+/// Collects the events of `stream` up to and including the first one that matches, failing after
+/// 10 seconds.
+pub async fn events_until<S>(stream: &mut S, matches: impl Fn(&S::Item) -> bool) -> Vec<S::Item>
+where
+	S: Stream + Unpin,
+{
+	let mut events = Vec::new();
+	tokio::time::timeout(Duration::from_secs(10), async {
+		loop {
+			let event = stream.next().await.expect("No event");
+			let matched = matches(&event);
+			events.push(event);
+			if matched {
+				return;
+			}
+		}
+	})
+	.await
+	.expect("Timed out waiting for the event");
+	events
+}
+
 pub struct SetupBridgeServiceResult(
 	pub BridgeService<B1Service, B2Service>,
 	pub AbstractBlockchainClient<BC1Address, BC1Hash, TestRng>,
@@ -176,17 +176,23 @@ where
 						InitiatorCall::InitiateBridgeTransfer(
 							initiator_address,
 							recipient_address,
+							asset,
 							amount,
 							time_lock,
 							hash_lock,
+							counterparty_hash_lock,
+							destination_chain,
 						) => {
 							this.events.push(AbstractBlockchainEvent::InitiatorContractEvent(
 								this.initiator_contract.initiate_bridge_transfer(
 									initiator_address.clone(),
 									recipient_address.clone(),
+									asset.clone(),
 									amount,
 									time_lock.clone(),
 									hash_lock.clone(),
+									counterparty_hash_lock.clone(),
+									destination_chain,
 								),
 							));
 						}
@@ -199,6 +205,11 @@ where
 								),
 							));
 						}
+						InitiatorCall::RefundBridgeTransfer(bridge_transfer_id) => {
+							this.events.push(AbstractBlockchainEvent::InitiatorContractEvent(
+								this.initiator_contract.refund_bridge_transfer(bridge_transfer_id),
+							));
+						}
 					},
 					Transaction::Counterparty(call) => match call {
 						CounterpartyCall::LockBridgeTransfer(
@@ -207,6 +218,7 @@ where
 							time_lock,
 							initiator_address,
 							recipient_address,
+							asset,
 							amount,
 						) => {
 							this.events.push(AbstractBlockchainEvent::CounterpartyContractEvent(
@@ -216,6 +228,7 @@ where
 									time_lock.clone(),
 									initiator_address.clone(),
 									recipient_address.clone(),
+									asset.clone(),
 									amount,
 								),
 							));
@@ -229,6 +242,12 @@ where
 								),
 							));
 						}
+						CounterpartyCall::AbortBridgeTransfer(bridge_transfer_id) => {
+							this.events.push(AbstractBlockchainEvent::CounterpartyContractEvent(
+								this.counterparty_contract
+									.abort_bridge_transfer(&bridge_transfer_id),
+							));
+						}
 					},
 				}
 			}
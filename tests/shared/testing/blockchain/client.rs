@@ -5,9 +5,11 @@ use bridge_shared::{
 		BridgeContractCounterpartyResult, BridgeContractInitiator, BridgeContractInitiatorError,
 		BridgeContractInitiatorResult,
 	},
+	hash_lock::SecretConstraints,
 	types::{
-		Amount, BridgeAddressType, BridgeHashType, BridgeTransferDetails, BridgeTransferId,
-		HashLock, HashLockPreImage, InitiatorAddress, RecipientAddress, TimeLock,
+		Amount, AssetId, BridgeAddressType, BridgeHashType, BridgeTransferDetails,
+		BridgeTransferId, ChainId, GenUniqueHash, HashLock, HashLockPreImage, InitiatorAddress,
+		RecipientAddress, TimeLock,
 	},
 };
 use dashmap::DashMap;
@@ -128,11 +130,7 @@ where
 
 	fn have_call_config(&self, method: MethodName) -> Option<CallConfig> {
 		self.call_configs.get(&method).and_then(|configs| {
-			configs
-				.iter()
-				.find(|config| config.0 == 0)
-				.map(|found_config| &found_config.1)
-				.cloned()
+			configs.iter().find(|config| config.0 == 0).map(|found_config| &found_config.1).cloned()
 		})
 	}
 }
@@ -144,6 +142,13 @@ pub struct AbstractBlockchainClient<A, H, R> {
 	pub failure_rate: f64,
 	pub false_positive_rate: f64,
 	pub call_configs: Arc<DashMap<MethodName, Vec<(usize, CallConfig)>>>,
+	/// Locks assets under a fresh id instead of the proposed one, like counterparty contracts
+	/// that assign their own ids.
+	pub assign_counterparty_ids: bool,
+	/// Returns from locking only after the delay, by which time the lock is already reported.
+	pub lock_return_delay: Option<std::time::Duration>,
+	/// Limits the contract reports on pre-images. They are not enforced.
+	pub secret_constraints: Option<SecretConstraints>,
 }
 
 impl<A, H, R> AbstractBlockchainClient<A, H, R>
@@ -164,6 +169,9 @@ where
 			failure_rate,
 			false_positive_rate,
 			call_configs: Default::default(),
+			assign_counterparty_ids: false,
+			lock_return_delay: None,
+			secret_constraints: None,
 		}
 	}
 
@@ -205,15 +213,21 @@ where
 		initiator_address: InitiatorAddress<Self::Address>,
 		recipient_address: RecipientAddress<Vec<u8>>,
 		hash_lock: HashLock<Self::Hash>,
+		counterparty_hash_lock: Option<HashLock<Vec<u8>>>,
 		time_lock: TimeLock,
+		asset: AssetId,
 		amount: Amount,
+		destination_chain: Option<ChainId>,
 	) -> BridgeContractInitiatorResult<()> {
 		let transaction = Transaction::Initiator(InitiatorCall::InitiateBridgeTransfer(
 			initiator_address,
 			recipient_address,
+			asset,
 			amount,
 			time_lock,
 			hash_lock,
+			counterparty_hash_lock,
+			destination_chain,
 		));
 		self.register_call(MethodName::InitiateBridgeTransfer);
 		if let Some(config) = self.have_call_config(MethodName::InitiateBridgeTransfer) {
@@ -223,8 +237,7 @@ where
 			config.get_initiator_error()?;
 		}
 
-		self.send_transaction(transaction)
-			.map_err(BridgeContractInitiatorError::generic)
+		self.send_transaction(transaction).map_err(BridgeContractInitiatorError::generic)
 	}
 
 	async fn complete_bridge_transfer(
@@ -232,7 +245,7 @@ where
 		bridge_transfer_id: BridgeTransferId<Self::Hash>,
 		secret: HashLockPreImage,
 	) -> BridgeContractInitiatorResult<()> {
-		tracing::error!(
+		tracing::trace!(
 			"Intitiator complete_bridge_transfer {:?} {:?}",
 			bridge_transfer_id,
 			secret
@@ -249,15 +262,24 @@ where
 			bridge_transfer_id,
 			secret,
 		));
-		self.send_transaction(transaction)
-			.map_err(BridgeContractInitiatorError::generic)
+		self.send_transaction(transaction).map_err(BridgeContractInitiatorError::generic)
 	}
 
 	async fn refund_bridge_transfer(
 		&mut self,
-		_bridge_transfer_id: BridgeTransferId<Self::Hash>,
+		bridge_transfer_id: BridgeTransferId<Self::Hash>,
 	) -> BridgeContractInitiatorResult<()> {
-		unimplemented!()
+		self.register_call(MethodName::RefundBridgeTransfer);
+		if let Some(config) = self.have_call_config(MethodName::RefundBridgeTransfer) {
+			if let Some(delay) = config.delay {
+				tokio::time::sleep(delay).await;
+			}
+			config.get_initiator_error()?;
+		}
+
+		let transaction =
+			Transaction::Initiator(InitiatorCall::RefundBridgeTransfer(bridge_transfer_id));
+		self.send_transaction(transaction).map_err(BridgeContractInitiatorError::generic)
 	}
 
 	async fn get_bridge_transfer_details(
@@ -266,13 +288,17 @@ where
 	) -> BridgeContractInitiatorResult<Option<BridgeTransferDetails<Self::Hash, Self::Address>>> {
 		unimplemented!()
 	}
+
+	fn secret_constraints(&self) -> Option<SecretConstraints> {
+		self.secret_constraints
+	}
 }
 
 #[async_trait]
 impl<A, H, R> BridgeContractCounterparty for AbstractBlockchainClient<A, H, R>
 where
 	A: BridgeAddressType,
-	H: BridgeHashType,
+	H: BridgeHashType + GenUniqueHash,
 	R: RngSeededClone + Send + Sync + Unpin + Clone,
 {
 	type Address = A;
@@ -285,8 +311,9 @@ where
 		time_lock: TimeLock,
 		initiator: InitiatorAddress<Vec<u8>>,
 		recipient: RecipientAddress<Self::Address>,
+		asset: AssetId,
 		amount: Amount,
-	) -> BridgeContractCounterpartyResult<()> {
+	) -> BridgeContractCounterpartyResult<BridgeTransferId<Self::Hash>> {
 		self.register_call(MethodName::LockBridgeTransferAssets);
 		if let Some(config) = self.have_call_config(MethodName::LockBridgeTransferAssets) {
 			tracing::error!("lock_bridge_transfer_assets {:?}", config);
@@ -296,16 +323,25 @@ where
 			config.get_counterparty_error()?;
 		}
 
+		let bridge_transfer_id = if self.assign_counterparty_ids {
+			BridgeTransferId::gen_unique_hash(&mut self.rng)
+		} else {
+			bridge_transfer_id
+		};
 		let transaction = Transaction::Counterparty(CounterpartyCall::LockBridgeTransfer(
-			bridge_transfer_id,
+			bridge_transfer_id.clone(),
 			hash_lock,
 			time_lock,
 			initiator,
 			recipient,
+			asset,
 			amount,
 		));
-		self.send_transaction(transaction)
-			.map_err(BridgeContractCounterpartyError::generic)
+		self.send_transaction(transaction).map_err(BridgeContractCounterpartyError::generic)?;
+		if let Some(delay) = self.lock_return_delay {
+			tokio::time::sleep(delay).await;
+		}
+		Ok(bridge_transfer_id)
 	}
 
 	async fn complete_bridge_transfer(
@@ -327,15 +363,24 @@ where
 			bridge_transfer_id,
 			secret,
 		));
-		self.send_transaction(transaction)
-			.map_err(BridgeContractCounterpartyError::generic)
+		self.send_transaction(transaction).map_err(BridgeContractCounterpartyError::generic)
 	}
 
 	async fn abort_bridge_transfer(
 		&mut self,
-		_bridge_transfer_id: BridgeTransferId<Self::Hash>,
+		bridge_transfer_id: BridgeTransferId<Self::Hash>,
 	) -> BridgeContractCounterpartyResult<()> {
-		unimplemented!()
+		self.register_call(MethodName::AbortBridgeTransfer);
+		if let Some(config) = self.have_call_config(MethodName::AbortBridgeTransfer) {
+			if let Some(delay) = config.delay {
+				tokio::time::sleep(delay).await;
+			}
+			config.get_counterparty_error()?;
+		}
+
+		let transaction =
+			Transaction::Counterparty(CounterpartyCall::AbortBridgeTransfer(bridge_transfer_id));
+		self.send_transaction(transaction).map_err(BridgeContractCounterpartyError::generic)
 	}
 
 	async fn get_bridge_transfer_details(
@@ -345,4 +390,8 @@ where
 	{
 		unimplemented!()
 	}
+
+	fn secret_constraints(&self) -> Option<SecretConstraints> {
+		self.secret_constraints
+	}
 }
@@ -1,16 +1,18 @@
 use std::collections::HashMap;
 
 use bridge_shared::types::{
-	Amount, BridgeAddressType, BridgeHashType, BridgeTransferId, CounterpartyCompletedDetails,
-	GenUniqueHash, HashLock, HashLockPreImage, InitiatorAddress, LockDetails, RecipientAddress,
-	TimeLock,
+	Amount, AssetId, BridgeAddressType, BridgeHashType, BridgeTransferId,
+	CounterpartyCompletedDetails, GenUniqueHash, HashLock, HashLockPreImage, InitiatorAddress,
+	LockDetails, RecipientAddress, TimeLock,
 };
 use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum SmartContractCounterpartyEvent<A, H> {
 	LockedBridgeTransfer(LockDetails<A, H>),
 	CompletedBridgeTransfer(CounterpartyCompletedDetails<A, H>),
+	AbortedBridgeTransfer(BridgeTransferId<H>),
 }
 
 #[derive(Debug, Error, Clone, PartialEq, Eq)]
@@ -22,14 +24,17 @@ pub enum SmartContractCounterpartyError {
 }
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum CounterpartyCall<A, H> {
 	CompleteBridgeTransfer(BridgeTransferId<H>, HashLockPreImage),
+	AbortBridgeTransfer(BridgeTransferId<H>),
 	LockBridgeTransfer(
 		BridgeTransferId<H>,
 		HashLock<H>,
 		TimeLock,
 		InitiatorAddress<Vec<u8>>,
 		RecipientAddress<A>,
+		AssetId,
 		Amount,
 	),
 }
@@ -52,6 +57,7 @@ where
 		Self { locked_transfers: HashMap::new() }
 	}
 
+	#[allow(clippy::too_many_arguments)]
 	pub fn lock_bridge_transfer(
 		&mut self,
 
@@ -60,6 +66,7 @@ where
 		time_lock: TimeLock,
 		initiator_address: InitiatorAddress<Vec<u8>>,
 		recipient_address: RecipientAddress<A>,
+		asset: AssetId,
 		amount: Amount,
 	) -> SCCResult<A, H> {
 		tracing::trace!(
@@ -74,6 +81,7 @@ where
 				recipient_address: recipient_address.clone(),
 				hash_lock: hash_lock.clone(),
 				time_lock: time_lock.clone(),
+				asset: asset.clone(),
 				amount,
 			},
 		);
@@ -84,6 +92,7 @@ where
 			recipient_address,
 			hash_lock,
 			time_lock,
+			asset,
 			amount,
 		}))
 	}
@@ -120,4 +129,19 @@ where
 			CounterpartyCompletedDetails::from_lock_details(transfer, pre_image),
 		))
 	}
+
+	pub fn abort_bridge_transfer(
+		&mut self,
+		bridge_transfer_id: &BridgeTransferId<H>,
+	) -> SCCResult<A, H> {
+		// Time locks are not enforced
+		let transfer = self
+			.locked_transfers
+			.remove(bridge_transfer_id)
+			.ok_or(SmartContractCounterpartyError::TransferNotFound)?;
+
+		tracing::trace!("SmartContractCounterparty: Aborting bridge transfer: {:?}", transfer);
+
+		Ok(SmartContractCounterpartyEvent::AbortedBridgeTransfer(transfer.bridge_transfer_id))
+	}
 }
@@ -1,29 +1,40 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use rand::Rng;
 use thiserror::Error;
 
-use bridge_shared::types::{
-	Amount, BridgeAddressType, BridgeHashType, BridgeTransferDetails, BridgeTransferId,
-	GenUniqueHash, HashLock, HashLockPreImage, InitiatorAddress, RecipientAddress, TimeLock,
+use bridge_shared::{
+	chain_clock::ChainClock,
+	types::{
+		Amount, AssetId, BridgeAddressType, BridgeHashType, BridgeTransferDetails,
+		BridgeTransferId, ChainId, GenUniqueHash, HashLock, HashLockPreImage, InitiatorAddress,
+		RecipientAddress, TimeLock,
+	},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum SmartContractInitiatorEvent<A, H> {
 	InitiatedBridgeTransfer(BridgeTransferDetails<A, H>),
 	CompletedBridgeTransfer(BridgeTransferId<H>, HashLockPreImage),
+	RefundedBridgeTransfer(BridgeTransferId<H>),
 }
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum InitiatorCall<A, H> {
 	InitiateBridgeTransfer(
 		InitiatorAddress<A>,
 		RecipientAddress<Vec<u8>>,
+		AssetId,
 		Amount,
 		TimeLock,
 		HashLock<H>,
+		Option<HashLock<Vec<u8>>>,
+		Option<ChainId>,
 	),
 	CompleteBridgeTransfer(BridgeTransferId<H>, HashLockPreImage),
+	RefundBridgeTransfer(BridgeTransferId<H>),
 }
 
 #[derive(Debug)]
@@ -31,6 +42,8 @@ pub struct SmartContractInitiator<A, H, R> {
 	pub initiated_transfers: HashMap<BridgeTransferId<H>, BridgeTransferDetails<A, H>>,
 	pub accounts: HashMap<A, Amount>,
 	pub rng: R,
+	/// Enforces time locks on refunds when set. Relative time locks start when initiated.
+	pub clock: Option<Arc<dyn ChainClock>>,
 }
 
 #[derive(Debug, Error, Clone, PartialEq, Eq)]
@@ -41,6 +54,8 @@ pub enum SmartContractInitiatorError {
 	TransferNotFound,
 	#[error("Invalid hash lock pre image (secret)")]
 	InvalidHashLockPreImage,
+	#[error("Time lock not expired")]
+	TimeLockNotExpired,
 }
 
 pub type SCIResult<A, H> = Result<SmartContractInitiatorEvent<A, H>, SmartContractInitiatorError>;
@@ -53,16 +68,20 @@ where
 	H: From<HashLockPreImage>,
 {
 	pub fn new(rng: R) -> Self {
-		Self { initiated_transfers: HashMap::new(), accounts: HashMap::default(), rng }
+		Self { initiated_transfers: HashMap::new(), accounts: HashMap::default(), rng, clock: None }
 	}
 
+	#[allow(clippy::too_many_arguments)]
 	pub fn initiate_bridge_transfer(
 		&mut self,
 		initiator: InitiatorAddress<A>,
 		recipient: RecipientAddress<Vec<u8>>,
+		asset: AssetId,
 		amount: Amount,
 		time_lock: TimeLock,
 		hash_lock: HashLock<H>,
+		counterparty_hash_lock: Option<HashLock<Vec<u8>>>,
+		destination_chain: Option<ChainId>,
 	) -> SCIResult<A, H> {
 		let bridge_transfer_id = BridgeTransferId::<H>::gen_unique_hash(&mut self.rng);
 
@@ -83,8 +102,14 @@ where
 				initiator_address: initiator.clone(),
 				recipient_address: recipient.clone(),
 				hash_lock: hash_lock.clone(),
-				time_lock: time_lock.clone(),
+				counterparty_hash_lock: counterparty_hash_lock.clone(),
+				time_lock: match &self.clock {
+					Some(clock) => time_lock.resolve(&clock.now()),
+					None => time_lock.clone(),
+				},
+				asset: asset.clone(),
 				amount,
+				destination_chain,
 			},
 		);
 
@@ -93,8 +118,11 @@ where
 			initiator_address: initiator,
 			recipient_address: recipient,
 			hash_lock,
+			counterparty_hash_lock,
 			time_lock,
+			asset,
 			amount,
+			destination_chain,
 		}))
 	}
 
@@ -124,4 +152,19 @@ where
 
 		Ok(SmartContractInitiatorEvent::CompletedBridgeTransfer(transfer_id, pre_image))
 	}
+
+	pub fn refund_bridge_transfer(&mut self, transfer_id: BridgeTransferId<H>) -> SCIResult<A, H> {
+		tracing::trace!("SmartContractInitiator: Refunding bridge transfer: {:?}", transfer_id);
+
+		let transfer = self
+			.initiated_transfers
+			.get(&transfer_id)
+			.ok_or(SmartContractInitiatorError::TransferNotFound)?;
+		if self.clock.as_ref().is_some_and(|clock| !transfer.time_lock.is_expired(&clock.now())) {
+			return Err(SmartContractInitiatorError::TimeLockNotExpired);
+		}
+		self.initiated_transfers.remove(&transfer_id);
+
+		Ok(SmartContractInitiatorEvent::RefundedBridgeTransfer(transfer_id))
+	}
 }
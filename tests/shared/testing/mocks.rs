@@ -9,7 +9,7 @@ use std::{
 use bridge_shared::{
 	blockchain_service::{BlockchainService, ContractEvent},
 	bridge_contracts::BridgeContractCounterpartyResult,
-	types::{HashLock, InitiatorAddress, RecipientAddress, TimeLock},
+	types::{AssetId, ChainId, HashLock, InitiatorAddress, RecipientAddress, TimeLock},
 };
 use bridge_shared::{
 	bridge_contracts::BridgeContractInitiatorResult,
@@ -252,22 +252,26 @@ where
 		initiator_address: InitiatorAddress<Self::Address>,
 		recipient_address: RecipientAddress<Vec<u8>>,
 		hash_lock: HashLock<Self::Hash>,
+		counterparty_hash_lock: Option<HashLock<Vec<u8>>>,
 		time_lock: TimeLock,
+		asset: AssetId,
 		amount: Amount,
+		destination_chain: Option<ChainId>,
 	) -> BridgeContractInitiatorResult<()> {
 		let mut state = self.state.lock().expect("lock poisoned");
 		let next_bridge_transfer_id =
 			state.mock_next_bridge_transfer_id.take().expect("no next bridge transfer id");
-		state
-			.events
-			.push(BridgeContractInitiatorEvent::Initiated(BridgeTransferDetails {
-				bridge_transfer_id: next_bridge_transfer_id,
-				initiator_address,
-				recipient_address,
-				hash_lock,
-				time_lock,
-				amount,
-			}));
+		state.events.push(BridgeContractInitiatorEvent::Initiated(BridgeTransferDetails {
+			bridge_transfer_id: next_bridge_transfer_id,
+			initiator_address,
+			recipient_address,
+			hash_lock,
+			counterparty_hash_lock,
+			time_lock,
+			asset,
+			amount,
+			destination_chain,
+		}));
 		Ok(())
 	}
 
@@ -316,14 +320,15 @@ where
 
 	async fn lock_bridge_transfer_assets(
 		&mut self,
-		_bridge_transfer_id: BridgeTransferId<Self::Hash>,
+		bridge_transfer_id: BridgeTransferId<Self::Hash>,
 		_hash_lock: HashLock<Self::Hash>,
 		_time_lock: TimeLock,
 		_initiator: InitiatorAddress<Vec<u8>>,
 		_recipient: RecipientAddress<A>,
+		_asset: AssetId,
 		_amount: Amount,
-	) -> BridgeContractCounterpartyResult<()> {
-		Ok(())
+	) -> BridgeContractCounterpartyResult<BridgeTransferId<Self::Hash>> {
+		Ok(bridge_transfer_id)
 	}
 
 	async fn complete_bridge_transfer(
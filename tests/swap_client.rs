@@ -0,0 +1,353 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_contracts::{BridgeContractCounterpartyError, BridgeContractInitiatorError},
+	bridge_service::{
+		active_swap::ActiveSwapConfig,
+		anomaly::SwapAnomaly,
+		assets::{AssetConfig, AssetFee, AssetMapping},
+		direction::{Direction, EnabledDirections},
+		BridgeServiceConfig, ChainConfig,
+	},
+	chain_clock::{ChainTime, ManualClock},
+	swap_client::{
+		SwapClient, SwapClientConfig, SwapClientError, SwapEvent, SwapRequest, SwapState,
+	},
+	types::{
+		Amount, AssetId, BridgeTransferId, HashLockPreImage, InitiatorAddress, RecipientAddress,
+		TimeLock,
+	},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, setup_bridge_service, test_chain_config,
+	testing::blockchain::client::{CallConfig, ErrorConfig, MethodName},
+	B1Client, B2Client, BC1Address, BC1Hash, BC2Address, BC2Hash, CounterpartyContractMonitoring,
+	InitiatorContractMonitoring, SetupBridgeServiceResult, TEST_ASSET,
+};
+
+type TestSwapClient = SwapClient<
+	B1Client,
+	InitiatorContractMonitoring<BC1Address, BC1Hash>,
+	B2Client,
+	CounterpartyContractMonitoring<BC2Address, BC2Hash>,
+>;
+
+const BLOCK_TIME: Duration = Duration::from_secs(12);
+const NOW: ChainTime = ChainTime { height: 500, timestamp: 1_700_000_000 };
+
+/// Runs a bridge service between two chains and a swap client from blockchain 1 to blockchain 2,
+/// whose first `claim_errors` claims and `refund_errors` refunds fail.
+fn setup_swap_client(
+	config: BridgeServiceConfig,
+	initiator_chain: ChainConfig,
+	claim_errors: usize,
+	refund_errors: usize,
+) -> TestSwapClient {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		mut blockchain_2_client,
+		mut blockchain_1,
+		mut blockchain_2,
+	) = setup_bridge_service(config);
+	for n in 1..=claim_errors {
+		blockchain_2_client.set_call_config(
+			MethodName::CompleteBridgeTransferCounterparty,
+			n,
+			CallConfig {
+				error: ErrorConfig::CounterpartyError(
+					BridgeContractCounterpartyError::CompleteTransferError,
+				),
+				delay: None,
+			},
+		);
+	}
+	for n in 1..=refund_errors {
+		blockchain_1_client.set_call_config(
+			MethodName::RefundBridgeTransfer,
+			n,
+			CallConfig {
+				error: ErrorConfig::InitiatorError(BridgeContractInitiatorError::GenericError(
+					"refund failed".to_string(),
+				)),
+				delay: None,
+			},
+		);
+	}
+
+	let swap_client = SwapClient::new(
+		blockchain_1_client,
+		InitiatorContractMonitoring::build(blockchain_1.add_event_listener()),
+		blockchain_2_client,
+		CounterpartyContractMonitoring::build(blockchain_2.add_event_listener()),
+		SwapClientConfig {
+			initiator_chain,
+			counterparty_chain: test_chain_config(),
+			calls: ActiveSwapConfig {
+				error_delay: Duration::from_millis(10),
+				..Default::default()
+			},
+		},
+	);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+	tokio::spawn(async move { while bridge_service.next().await.is_some() {} });
+	swap_client
+}
+
+fn swap_request(time_lock: Duration) -> SwapRequest<BC1Address> {
+	SwapRequest {
+		initiator_address: InitiatorAddress(BC1Address("initiator")),
+		recipient_address: RecipientAddress::from(BC1Address("recipient")),
+		secret: HashLockPreImage::new(b"secret".to_vec()),
+		time_lock: TimeLock::Relative(time_lock),
+		asset: AssetId::from(TEST_ASSET),
+		amount: Amount(1000),
+		destination_chain: None,
+		quote: None,
+	}
+}
+
+/// States the swap went through, once it finished.
+async fn states<IH, CH>(
+	events: impl futures::Stream<Item = SwapEvent<IH, CH>>,
+) -> Vec<SwapState<IH, CH>> {
+	events
+		.filter_map(|event| async move {
+			match event {
+				SwapEvent::State(state) => Some(state),
+				SwapEvent::CallError(_) | SwapEvent::LockRejected(..) => None,
+			}
+		})
+		.collect()
+		.await
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_swap_client_completes_swap() {
+	let mut swap_client =
+		setup_swap_client(default_bridge_service_config(), test_chain_config(), 0, 0);
+
+	let (swap, events) = swap_client.swap(swap_request(Duration::from_secs(100)));
+	let state = tokio::time::timeout(Duration::from_secs(10), swap)
+		.await
+		.expect("Timed out waiting for the swap")
+		.expect("Swap failed");
+	assert!(matches!(state, SwapState::Completed { .. }));
+
+	let states = states(events).await;
+	assert!(matches!(
+		states.as_slice(),
+		[
+			SwapState::Initiating { .. },
+			SwapState::Initiated { .. },
+			SwapState::Locked { .. },
+			SwapState::Completed { .. },
+		]
+	));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_swap_client_refunds_after_time_lock() {
+	// The bridge never locks on blockchain 2, and refunds fail past the attempts of a call
+	let mut swap_client = setup_swap_client(
+		BridgeServiceConfig {
+			directions: EnabledDirections::only(Direction::B2ToB1),
+			..default_bridge_service_config()
+		},
+		test_chain_config(),
+		0,
+		5,
+	);
+
+	let (swap, events) = swap_client.swap(swap_request(Duration::from_millis(500)));
+	let state = tokio::time::timeout(Duration::from_secs(10), swap)
+		.await
+		.expect("Timed out waiting for the swap")
+		.expect("Swap failed");
+	assert!(matches!(state, SwapState::Refunded { .. }));
+
+	let states = states(events).await;
+	assert!(matches!(
+		states.as_slice(),
+		[
+			SwapState::Initiating { .. },
+			SwapState::Initiated { .. },
+			SwapState::Refunding { .. },
+			SwapState::Refunded { .. },
+		]
+	));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_swap_client_resumes_locked_swap() {
+	// Claiming fails on every attempt of the first run. Resuming needs the initiator chain clock
+	let clock = ManualClock::new(NOW, BLOCK_TIME);
+	let mut swap_client = setup_swap_client(
+		default_bridge_service_config(),
+		ChainConfig { clock: Some(Arc::new(clock)), ..test_chain_config() },
+		4,
+		0,
+	);
+
+	let (swap, mut events) = swap_client.swap(swap_request(Duration::from_secs(100)));
+	let mut swap = Box::pin(swap);
+	let (mut locked, mut claim_errors) = (None, 0);
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while claim_errors < 4 {
+			tokio::select! {
+				result = &mut swap => panic!("Swap finished early: {result:?}"),
+				Some(event) = events.next() => match event {
+					SwapEvent::State(state @ SwapState::Locked { .. }) => locked = Some(state),
+					SwapEvent::CallError(_) => claim_errors += 1,
+					SwapEvent::State(_) | SwapEvent::LockRejected(..) => {}
+				},
+			}
+		}
+	})
+	.await
+	.expect("Timed out waiting for the claim to fail");
+	drop(swap);
+
+	// The swap is resumed from the persisted state
+	let locked = locked.expect("Swap was not locked");
+	let (swap, _events) = swap_client.resume(swap_request(Duration::from_secs(100)), locked);
+	let state = tokio::time::timeout(Duration::from_secs(10), swap)
+		.await
+		.expect("Timed out waiting for the swap")
+		.expect("Swap failed");
+	assert!(matches!(state, SwapState::Completed { .. }));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_swap_client_refunds_unexpected_lock() {
+	// The bridge locks less than the client asked for
+	let mut swap_client = setup_swap_client(
+		BridgeServiceConfig {
+			assets_b1_to_b2: AssetMapping::new().with_asset(
+				TEST_ASSET,
+				AssetConfig::new(TEST_ASSET)
+					.with_fee(AssetFee { flat: Amount(10), basis_points: 0 }),
+			),
+			..default_bridge_service_config()
+		},
+		test_chain_config(),
+		0,
+		0,
+	);
+
+	let (swap, mut events) = swap_client.swap(swap_request(Duration::from_millis(500)));
+	let state = tokio::time::timeout(Duration::from_secs(10), swap)
+		.await
+		.expect("Timed out waiting for the swap")
+		.expect("Swap failed");
+	assert!(matches!(state, SwapState::Refunded { .. }));
+
+	let mut states = Vec::new();
+	let mut anomalies = Vec::new();
+	while let Some(event) = events.next().await {
+		match event {
+			SwapEvent::State(state) => states.push(state),
+			SwapEvent::LockRejected(_, lock_anomalies) => anomalies.extend(lock_anomalies),
+			SwapEvent::CallError(error) => panic!("Unexpected call error {error:?}"),
+		}
+	}
+	assert_eq!(
+		anomalies,
+		[SwapAnomaly::AmountMismatch { expected: Amount(1000), actual: Amount(990) }]
+	);
+	assert!(matches!(
+		states.as_slice(),
+		[
+			SwapState::Initiating { .. },
+			SwapState::Initiated { .. },
+			SwapState::Refunding { .. },
+			SwapState::Refunded { .. },
+		]
+	));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_swap_client_requires_clock_for_absolute_time_lock() {
+	let mut swap_client =
+		setup_swap_client(default_bridge_service_config(), test_chain_config(), 0, 0);
+
+	let request = SwapRequest {
+		time_lock: TimeLock::AbsoluteTimestamp(1_000),
+		..swap_request(Duration::from_secs(100))
+	};
+	let (swap, _events) = swap_client.swap(request);
+	let result = tokio::time::timeout(Duration::from_secs(10), swap)
+		.await
+		.expect("Timed out waiting for the swap");
+	assert!(matches!(result, Err(SwapClientError::MissingClock)), "{result:?}");
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_swap_client_requires_clock_to_resume_relative_time_lock() {
+	let mut swap_client =
+		setup_swap_client(default_bridge_service_config(), test_chain_config(), 0, 0);
+
+	// When the transfer was initiated is not known anymore
+	let state = SwapState::Initiated {
+		bridge_transfer_id: BridgeTransferId(BC1Hash::from("transfer")),
+		time_lock: TimeLock::Relative(Duration::from_secs(100)),
+	};
+	let (swap, _events) = swap_client.resume(swap_request(Duration::from_secs(100)), state);
+	let result = tokio::time::timeout(Duration::from_secs(10), swap)
+		.await
+		.expect("Timed out waiting for the swap");
+	assert!(matches!(result, Err(SwapClientError::MissingClock)), "{result:?}");
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_swap_client_refunds_by_initiator_chain_clock() {
+	// The bridge never locks on blockchain 2
+	let clock = ManualClock::new(NOW, BLOCK_TIME);
+	let mut swap_client = setup_swap_client(
+		BridgeServiceConfig {
+			directions: EnabledDirections::only(Direction::B2ToB1),
+			..default_bridge_service_config()
+		},
+		ChainConfig { clock: Some(Arc::new(clock.clone())), ..test_chain_config() },
+		0,
+		0,
+	);
+
+	let request = SwapRequest {
+		time_lock: TimeLock::AbsoluteTimestamp(NOW.timestamp + 1),
+		..swap_request(Duration::from_secs(100))
+	};
+	let (swap, events) = swap_client.swap(request);
+	let mut swap = Box::pin(swap);
+
+	// The local timer elapses while the chain is behind, so nothing is refunded yet
+	let result = tokio::time::timeout(Duration::from_millis(2_500), &mut swap).await;
+	assert!(result.is_err(), "Swap finished early: {result:?}");
+	assert!(matches!(swap.state(), SwapState::Initiated { .. }), "{:?}", swap.state());
+
+	clock.set(ChainTime { height: NOW.height + 1, timestamp: NOW.timestamp + 1 });
+	let state = tokio::time::timeout(Duration::from_secs(10), swap)
+		.await
+		.expect("Timed out waiting for the swap")
+		.expect("Swap failed");
+	assert!(matches!(state, SwapState::Refunded { .. }));
+
+	let states = states(events).await;
+	assert!(matches!(
+		states.as_slice(),
+		[
+			SwapState::Initiating { .. },
+			SwapState::Initiated { .. },
+			SwapState::Refunding { .. },
+			SwapState::Refunded { .. },
+		]
+	));
+}
@@ -0,0 +1,293 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_monitoring::BridgeContractCounterpartyEvent,
+	bridge_service::{
+		active_swap::ActiveSwapMapError,
+		call_limit::CallLimitConfig,
+		events::{CEvent, CWarn, IEvent, IWarn},
+		hash_lock_index::{HashLockReuseConfig, HashLockReusePolicy},
+		time_lock::{TimeLockError, TimeLockKind, TimeLockPolicy},
+		BridgeServiceConfig, ChainConfig,
+	},
+	chain_clock::{ChainTime, ManualClock},
+	types::{Convert, TimeLock},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, initiate_transfer, next_matching, setup_bridge_service,
+	test_chain_config,
+	testing::blockchain::client::{CallConfig, ErrorConfig, MethodName},
+	SetupBridgeServiceResult, TestTransfer,
+};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+const BLOCK_TIME: Duration = Duration::from_secs(12);
+const NOW: ChainTime = ChainTime { height: 500, timestamp: 1_700_000_000 };
+
+fn chain_config(time_lock_kind: TimeLockKind) -> ChainConfig {
+	ChainConfig {
+		time_lock_kind,
+		clock: Some(Arc::new(ManualClock::new(NOW, BLOCK_TIME))),
+		..test_chain_config()
+	}
+}
+
+#[test]
+fn test_time_lock_expiry() {
+	let time_lock = TimeLock::AbsoluteHeight(800);
+	assert!(!time_lock.is_expired(&NOW));
+	assert_eq!(time_lock.time_remaining(&NOW, BLOCK_TIME), HOUR);
+	assert!(time_lock.is_expired(&ChainTime { height: 800, ..NOW }));
+
+	let time_lock = TimeLock::Relative(HOUR).resolve(&NOW);
+	assert_eq!(time_lock, TimeLock::AbsoluteTimestamp(NOW.timestamp + 3600));
+	assert_eq!(time_lock.time_remaining(&NOW, BLOCK_TIME), HOUR);
+	let later = ChainTime { timestamp: NOW.timestamp + 7200, ..NOW };
+	assert!(time_lock.is_expired(&later));
+	assert_eq!(time_lock.time_remaining(&later, BLOCK_TIME), Duration::ZERO);
+}
+
+#[test]
+fn test_time_lock_policy() {
+	let policy = TimeLockPolicy { safety_margin: HOUR };
+	let timestamps = chain_config(TimeLockKind::AbsoluteTimestamp);
+	let heights = chain_config(TimeLockKind::AbsoluteHeight);
+
+	// Three hours of initiator time leave two hours, or 600 blocks, on the counterparty chain
+	assert_eq!(
+		policy.counterparty_time_lock(
+			&TimeLock::AbsoluteTimestamp(NOW.timestamp + 3 * 3600),
+			&timestamps,
+			&heights
+		),
+		Ok(TimeLock::AbsoluteHeight(NOW.height + 600))
+	);
+	assert_eq!(
+		policy.counterparty_time_lock(
+			&TimeLock::AbsoluteHeight(NOW.height + 900),
+			&heights,
+			&timestamps
+		),
+		Ok(TimeLock::AbsoluteTimestamp(NOW.timestamp + 2 * 3600))
+	);
+	assert_eq!(
+		policy.counterparty_time_lock(
+			&TimeLock::Relative(HOUR),
+			&test_chain_config(),
+			&test_chain_config()
+		),
+		Err(TimeLockError::MarginNotMet { time_lock: HOUR, safety_margin: HOUR })
+	);
+	// Less than a single counterparty block remains after the margin
+	assert!(policy
+		.counterparty_time_lock(&TimeLock::Relative(HOUR + BLOCK_TIME / 2), &timestamps, &heights)
+		.is_err());
+
+	assert_eq!(
+		policy.counterparty_time_lock(
+			&TimeLock::AbsoluteHeight(NOW.height + 900),
+			&test_chain_config(),
+			&timestamps
+		),
+		Err(TimeLockError::MissingClock)
+	);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_applies_time_lock_policy() {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_1: chain_config(TimeLockKind::AbsoluteTimestamp),
+		blockchain_2: chain_config(TimeLockKind::AbsoluteHeight),
+		time_lock_policy: TimeLockPolicy { safety_margin: HOUR },
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	// The counterparty lock would expire after the initiator lock
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			time_lock: TimeLock::AbsoluteTimestamp(NOW.timestamp + 1800),
+			..Default::default()
+		},
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(
+		event.B1I().and_then(IEvent::warn).expect("not a b1i warn event"),
+		IWarn::TransferRejected(
+			_,
+			ActiveSwapMapError::TimeLock(TimeLockError::MarginNotMet { .. })
+		)
+	));
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			time_lock: TimeLock::AbsoluteTimestamp(NOW.timestamp + 3 * 3600),
+			..Default::default()
+		},
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	let bridge_transfer_id =
+		event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+	assert_eq!(
+		bridge_service.active_swaps_b1_to_b2.time_remaining(&bridge_transfer_id),
+		Some(3 * HOUR)
+	);
+
+	let event = bridge_service.next().await.expect("No event");
+	let event = event.B2C_ContractEvent().expect("Not a B2C event");
+	let BridgeContractCounterpartyEvent::Locked(details) = event else {
+		panic!("Not a locked event: {event:?}");
+	};
+	// Two hours of 12 second blocks
+	assert_eq!(details.time_lock, TimeLock::AbsoluteHeight(NOW.height + 600));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_counts_relative_time_lock_from_initiation() {
+	let clock = ManualClock::new(NOW, BLOCK_TIME);
+	let chain_config = ChainConfig {
+		time_lock_kind: TimeLockKind::AbsoluteHeight,
+		clock: Some(Arc::new(clock.clone())),
+		..test_chain_config()
+	};
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_1: chain_config.clone(),
+		blockchain_2: chain_config,
+		time_lock_policy: TimeLockPolicy { safety_margin: HOUR },
+		hash_lock_reuse: HashLockReuseConfig {
+			policy: HashLockReusePolicy::Hold,
+			..Default::default()
+		},
+		..default_bridge_service_config()
+	});
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	// The second transfer reuses the hash lock of the first, so it is held
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { time_lock: TimeLock::Relative(3 * HOUR), ..Default::default() },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+	let event = bridge_service.next().await.expect("No event");
+	assert!(matches!(event.B2C_ContractEvent(), Some(BridgeContractCounterpartyEvent::Locked(_))));
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { time_lock: TimeLock::Relative(3 * HOUR), ..Default::default() },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	let Some(IWarn::HashLockReused { details, held: true, .. }) =
+		event.B1I().and_then(IEvent::warn)
+	else {
+		panic!("Not a held hash lock reuse: {event:?}");
+	};
+	let held_id = details.bridge_transfer_id.clone();
+
+	// Half an hour of the initiator lock is gone once the transfer is released
+	clock.set(ChainTime { height: NOW.height + 150, timestamp: NOW.timestamp + 1800 });
+	bridge_service
+		.active_swaps_b1_to_b2
+		.release_held_transfer(&held_id)
+		.expect("release_held_transfer failed");
+	assert_eq!(bridge_service.active_swaps_b1_to_b2.time_remaining(&held_id), Some(5 * HOUR / 2));
+	let event = bridge_service.next().await.expect("No event");
+	let Some(BridgeContractCounterpartyEvent::Locked(details)) = event.B2C_ContractEvent() else {
+		panic!("Not a locked event: {event:?}");
+	};
+	// An hour and a half of 12 second blocks
+	assert_eq!(details.time_lock, TimeLock::AbsoluteHeight(NOW.height + 150 + 450));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_bridge_service_derives_time_lock_when_lock_is_due() {
+	let clock = ManualClock::new(NOW, BLOCK_TIME);
+	let chain_config = ChainConfig {
+		time_lock_kind: TimeLockKind::AbsoluteTimestamp,
+		clock: Some(Arc::new(clock.clone())),
+		call_limit: CallLimitConfig { max_in_flight: Some(1), ..Default::default() },
+		..test_chain_config()
+	};
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		blockchain_1,
+		blockchain_2,
+	) = setup_bridge_service(BridgeServiceConfig {
+		blockchain_1: chain_config.clone(),
+		blockchain_2: chain_config,
+		time_lock_policy: TimeLockPolicy { safety_margin: HOUR },
+		..default_bridge_service_config()
+	});
+	bridge_service.active_swaps_b1_to_b2.counterparty_contract.set_call_config(
+		MethodName::LockBridgeTransferAssets,
+		1,
+		CallConfig { error: ErrorConfig::None, delay: Some(Duration::from_secs(1)) },
+	);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+
+	let time_lock = TimeLock::AbsoluteTimestamp(NOW.timestamp + 3 * 3600);
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { time_lock: time_lock.clone(), ..TestTransfer::new("first") },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	assert!(event.B1I_ContractEvent().is_some());
+	// The first lock call is in flight and holds the only slot
+	let event = tokio::time::timeout(Duration::from_millis(100), bridge_service.next()).await;
+	assert!(event.is_err(), "Unexpected event: {event:?}");
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer { time_lock, ..TestTransfer::new("second") },
+	)
+	.await;
+	let event = bridge_service.next().await.expect("No event");
+	let queued_id =
+		event.B1I_ContractEvent().expect("Not a B1I event").bridge_transfer_id().clone();
+
+	// Only half an hour of the initiator lock is left by the time the slot frees up
+	clock.set(ChainTime { height: NOW.height + 750, timestamp: NOW.timestamp + 9000 });
+	let event = next_matching(&mut bridge_service, |event| {
+		matches!(event.B2C().and_then(CEvent::warn), Some(CWarn::LockingExpired(..)))
+	})
+	.await;
+	let Some(CWarn::LockingExpired(counterparty_id, TimeLockError::MarginNotMet { .. })) =
+		event.B2C().and_then(CEvent::warn)
+	else {
+		panic!("Not an expired lock: {event:?}");
+	};
+	assert_eq!(*counterparty_id, Convert::convert(&queued_id));
+	assert_eq!(bridge_service.active_swaps_b1_to_b2.capacity().active, 1);
+}
@@ -0,0 +1,263 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use test_log::test;
+
+use bridge_shared::{
+	bridge_contracts::{BridgeContractInitiator, BridgeContractInitiatorError},
+	bridge_service::{
+		active_swap::ActiveSwapConfig,
+		direction::{Direction, EnabledDirections},
+		BridgeServiceConfig, ChainConfig,
+	},
+	chain_clock::{ChainTime, ManualClock},
+	types::TimeLock,
+	watchtower::{Watchtower, WatchtowerConfig, WatchtowerEvent},
+};
+
+mod shared;
+
+use crate::shared::{
+	default_bridge_service_config, events_until, initiate_transfer, setup_bridge_service,
+	test_chain_config,
+	testing::blockchain::client::{CallConfig, ErrorConfig, MethodName},
+	B1Client, BC1Address, BC1Hash, BC2Address, BC2Hash, CounterpartyContractMonitoring,
+	InitiatorContractMonitoring, SetupBridgeServiceResult, TestTransfer,
+};
+
+type TestWatchtower = Watchtower<
+	B1Client,
+	InitiatorContractMonitoring<BC1Address, BC1Hash>,
+	CounterpartyContractMonitoring<BC2Address, BC2Hash>,
+>;
+
+const BLOCK_TIME: Duration = Duration::from_secs(12);
+const NOW: ChainTime = ChainTime { height: 500, timestamp: 1_700_000_000 };
+
+/// Runs a bridge service between two chains and a watchtower over transfers from blockchain 1 to
+/// blockchain 2, whose first `refund_errors` refunds fail.
+fn setup_watchtower(
+	config: BridgeServiceConfig,
+	initiator_chain: ChainConfig,
+	refund_errors: usize,
+) -> (TestWatchtower, B1Client) {
+	let SetupBridgeServiceResult(
+		mut bridge_service,
+		mut blockchain_1_client,
+		_blockchain_2_client,
+		mut blockchain_1,
+		mut blockchain_2,
+	) = setup_bridge_service(config);
+	for n in 1..=refund_errors {
+		blockchain_1_client.set_call_config(
+			MethodName::RefundBridgeTransfer,
+			n,
+			CallConfig {
+				error: ErrorConfig::InitiatorError(BridgeContractInitiatorError::GenericError(
+					"refund failed".to_string(),
+				)),
+				delay: None,
+			},
+		);
+	}
+
+	let watchtower = Watchtower::new(
+		blockchain_1_client.clone(),
+		InitiatorContractMonitoring::build(blockchain_1.add_event_listener()),
+		CounterpartyContractMonitoring::build(blockchain_2.add_event_listener()),
+		WatchtowerConfig {
+			initiator_chain,
+			counterparty_chain: test_chain_config(),
+			calls: ActiveSwapConfig {
+				error_delay: Duration::from_millis(10),
+				..Default::default()
+			},
+		},
+	);
+
+	tokio::spawn(blockchain_1);
+	tokio::spawn(blockchain_2);
+	tokio::spawn(async move { while bridge_service.next().await.is_some() {} });
+	(watchtower, blockchain_1_client)
+}
+
+/// The watchtower stops watching the transfer.
+fn is_released(event: &WatchtowerEvent<BC1Hash>) -> bool {
+	matches!(event, WatchtowerEvent::Dismissed(_) | WatchtowerEvent::Refunded(_))
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_watchtower_refunds_transfer_never_locked() {
+	// The bridge never locks on blockchain 2
+	let (mut watchtower, mut blockchain_1_client) = setup_watchtower(
+		BridgeServiceConfig {
+			directions: EnabledDirections::only(Direction::B2ToB1),
+			..default_bridge_service_config()
+		},
+		test_chain_config(),
+		0,
+	);
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			time_lock: TimeLock::Relative(Duration::from_millis(300)),
+			..Default::default()
+		},
+	)
+	.await;
+	let events = events_until(&mut watchtower, is_released).await;
+	assert!(matches!(
+		events.as_slice(),
+		[WatchtowerEvent::Watching(_), WatchtowerEvent::Refunding(_), WatchtowerEvent::Refunded(_)]
+	));
+	assert_eq!(watchtower.watched().count(), 0);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_watchtower_dismisses_locked_transfer() {
+	let (mut watchtower, mut blockchain_1_client) =
+		setup_watchtower(default_bridge_service_config(), test_chain_config(), 0);
+
+	// Long enough for the bridge to lock under load
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			time_lock: TimeLock::Relative(Duration::from_secs(1)),
+			..Default::default()
+		},
+	)
+	.await;
+	let events = events_until(&mut watchtower, is_released).await;
+	assert!(matches!(
+		events.as_slice(),
+		[WatchtowerEvent::Watching(_), WatchtowerEvent::Dismissed(_)]
+	));
+
+	// Nothing is refunded once the time lock expires
+	let event = tokio::time::timeout(Duration::from_millis(1_500), watchtower.next()).await;
+	assert!(event.is_err(), "Unexpected event {event:?}");
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_watchtower_retries_refund() {
+	let (mut watchtower, mut blockchain_1_client) = setup_watchtower(
+		BridgeServiceConfig {
+			directions: EnabledDirections::only(Direction::B2ToB1),
+			..default_bridge_service_config()
+		},
+		test_chain_config(),
+		5,
+	);
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			time_lock: TimeLock::Relative(Duration::from_millis(300)),
+			..Default::default()
+		},
+	)
+	.await;
+	let events = events_until(&mut watchtower, is_released).await;
+	assert!(matches!(
+		events.as_slice(),
+		[
+			WatchtowerEvent::Watching(_),
+			WatchtowerEvent::Refunding(_),
+			WatchtowerEvent::RefundError(..),
+			WatchtowerEvent::RefundError(..),
+			WatchtowerEvent::RefundError(..),
+			WatchtowerEvent::RefundStalled(_),
+			WatchtowerEvent::RefundError(..),
+			WatchtowerEvent::Refunded(_),
+		]
+	));
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_watchtower_waits_for_initiator_chain_clock() {
+	let clock = ManualClock::new(NOW, BLOCK_TIME);
+	let (mut watchtower, mut blockchain_1_client) = setup_watchtower(
+		BridgeServiceConfig {
+			directions: EnabledDirections::only(Direction::B2ToB1),
+			..default_bridge_service_config()
+		},
+		ChainConfig { clock: Some(Arc::new(clock.clone())), ..test_chain_config() },
+		0,
+	);
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			time_lock: TimeLock::AbsoluteTimestamp(NOW.timestamp + 1),
+			..Default::default()
+		},
+	)
+	.await;
+	let event = watchtower.next().await.expect("No event");
+	assert!(matches!(event, WatchtowerEvent::Watching(_)), "{event:?}");
+
+	// The local timer elapses while the chain is behind, so nothing is refunded yet
+	let event = tokio::time::timeout(Duration::from_millis(2_500), watchtower.next()).await;
+	assert!(event.is_err(), "Unexpected event {event:?}");
+
+	clock.set(ChainTime { height: NOW.height + 1, timestamp: NOW.timestamp + 1 });
+	let events = events_until(&mut watchtower, is_released).await;
+	assert!(
+		matches!(events.as_slice(), [WatchtowerEvent::Refunding(_), WatchtowerEvent::Refunded(_)]),
+		"{events:?}"
+	);
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_watchtower_stops_refunding_transfer_refunded_by_initiator() {
+	let (mut watchtower, mut blockchain_1_client) = setup_watchtower(
+		BridgeServiceConfig {
+			directions: EnabledDirections::only(Direction::B2ToB1),
+			..default_bridge_service_config()
+		},
+		test_chain_config(),
+		0,
+	);
+	// The refund of the watchtower hangs, and then fails
+	blockchain_1_client.set_call_config(
+		MethodName::RefundBridgeTransfer,
+		1,
+		CallConfig {
+			error: ErrorConfig::InitiatorError(BridgeContractInitiatorError::GenericError(
+				"refund failed".to_string(),
+			)),
+			delay: Some(Duration::from_millis(500)),
+		},
+	);
+
+	initiate_transfer(
+		&mut blockchain_1_client,
+		TestTransfer {
+			time_lock: TimeLock::Relative(Duration::from_millis(100)),
+			..Default::default()
+		},
+	)
+	.await;
+	let event = watchtower.next().await.expect("No event");
+	let WatchtowerEvent::Watching(bridge_transfer_id) = event else {
+		panic!("Not a watching event: {event:?}");
+	};
+	let event = watchtower.next().await.expect("No event");
+	assert!(matches!(event, WatchtowerEvent::Refunding(_)), "{event:?}");
+
+	// The initiator refunds while the refund of the watchtower is in flight
+	let event = tokio::time::timeout(Duration::from_millis(100), watchtower.next()).await;
+	assert!(event.is_err(), "Unexpected event {event:?}");
+	blockchain_1_client
+		.refund_bridge_transfer(bridge_transfer_id)
+		.await
+		.expect("refund_bridge_transfer failed");
+	let events = events_until(&mut watchtower, is_released).await;
+	assert!(matches!(events.as_slice(), [WatchtowerEvent::Refunded(_)]), "{events:?}");
+	assert_eq!(watchtower.watched().count(), 0);
+
+	// The refund in flight was dropped, so no failure is reported
+	let event = tokio::time::timeout(Duration::from_millis(700), watchtower.next()).await;
+	assert!(event.is_err(), "Unexpected event {event:?}");
+}